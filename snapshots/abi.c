@@ -0,0 +1,76 @@
+// Checks the functions `abi.a` exports against the same functions written in C.
+//
+//     a snapshots/abi.a --object
+//     cc snapshots/abi.c snapshots/abi.o -o abi && ./abi
+#include <stdint.h>
+#include <stdio.h>
+
+uint64_t nested(void);
+int64_t weigh(int64_t a, int64_t b, int64_t c, int64_t d, int64_t e, int64_t f, int64_t g,
+              int64_t h);
+int64_t weigh_in_c(int64_t a, int64_t b, int64_t c, int64_t d, int64_t e, int64_t f, int64_t g,
+                   int64_t h);
+double blend(double a, double b, double c, double d, double e, double f, double g, double h,
+             double i, double j);
+int64_t mixed(int32_t a, double x, uint8_t b, int32_t c, uint64_t d, int32_t e, uint32_t f,
+              int32_t g, double y);
+uint64_t spill(uint64_t a, uint64_t b, uint64_t c, uint64_t d, uint64_t e, uint64_t f, uint64_t g,
+               uint64_t h);
+double spill_floats(double a, double b, double c, double d, double e, double f, double g,
+                    double h);
+
+int64_t c_weigh8(int64_t a, int64_t b, int64_t c, int64_t d, int64_t e, int64_t f, int64_t g,
+                 int64_t h) {
+    return a + b * 2 + c * 3 + d * 4 + e * 5 + f * 6 + g * 7 + h * 8;
+}
+
+static double c_blend(double a, double b, double c, double d, double e, double f, double g,
+                      double h, double i, double j) {
+    return a + b * 2.0 + c * 3.0 + d * 4.0 + e * 5.0 + f * 6.0 + g * 7.0 + h * 8.0 + i * 9.0 +
+           j * 10.0;
+}
+
+static int64_t c_mixed(int32_t a, double x, uint8_t b, int32_t c, uint64_t d, int32_t e,
+                       uint32_t f, int32_t g, double y) {
+    return (int64_t)a * 10 + b + (int64_t)c * 10 + (int64_t)d + e + f + g + (int64_t)x +
+           (int64_t)y;
+}
+
+static uint64_t c_add(uint64_t a, uint64_t b) { return a + b; }
+
+static uint64_t c_spill(uint64_t a, uint64_t b, uint64_t c, uint64_t d, uint64_t e, uint64_t f,
+                        uint64_t g, uint64_t h) {
+    return a + b * c_add(c, d + e * c_add(f, g + h * c_add(a, b + c * c_add(d, e))));
+}
+
+static double c_addf(double a, double b) { return a + b; }
+
+static double c_spill_floats(double a, double b, double c, double d, double e, double f, double g,
+                             double h) {
+    double inner = c_addf(d, e + f * c_addf(g, h + a * c_addf(b, c)));
+    return a + b * c_addf(c, d + e * c_addf(f, g + h * c_addf(a, b + c * c_addf(d, e + f * c_addf(g, inner)))));
+}
+
+static int failed = 0;
+
+static void check(const char *name, int ok) {
+    if (!ok) {
+        printf("%s differs from C\n", name);
+        failed = 1;
+    }
+}
+
+int main(void) {
+    check("nested", nested() == 4);
+    check("weigh", weigh(1, -2, 3, -4, 5, -6, 7, -8) == c_weigh8(1, -2, 3, -4, 5, -6, 7, -8));
+    check("weigh_in_c", weigh_in_c(8, 7, 6, 5, 4, 3, 2, 1) == c_weigh8(8, 7, 6, 5, 4, 3, 2, 1));
+    check("blend", blend(0.5, 1.5, 2.5, 3.5, 4.5, 5.5, 6.5, 7.5, 8.5, 9.5) ==
+                       c_blend(0.5, 1.5, 2.5, 3.5, 4.5, 5.5, 6.5, 7.5, 8.5, 9.5));
+    check("mixed", mixed(-3, 2.5, 200, -40, 7, -5, 4000000000u, -6, 3.5) ==
+                       c_mixed(-3, 2.5, 200, -40, 7, -5, 4000000000u, -6, 3.5));
+    check("spill", spill(1, 2, 3, 4, 5, 6, 7, 8) == c_spill(1, 2, 3, 4, 5, 6, 7, 8));
+    check("spill_floats", spill_floats(0.5, 1.5, 2.5, 3.5, 4.5, 5.5, 6.5, 7.5) ==
+                              c_spill_floats(0.5, 1.5, 2.5, 3.5, 4.5, 5.5, 6.5, 7.5));
+    puts(failed ? "failed" : "ok");
+    return failed;
+}
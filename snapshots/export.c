@@ -0,0 +1,20 @@
+// Links against the functions `export.a` exports to C.
+//
+//     a snapshots/export.a --object
+//     cc snapshots/export.c snapshots/export.o -o export && ./export
+#include <stdint.h>
+#include <stdio.h>
+
+int32_t add(int32_t a, int32_t b);
+double scale(double x, uint8_t n);
+uint64_t sum(const uint64_t *data, uint64_t len);
+
+int main(void) {
+    uint64_t data[] = {1, 2, 3, 4};
+    int failed = 0;
+    failed |= add(-7, 2) != -5;
+    failed |= scale(1.5, 4) != 6.0;
+    failed |= sum(data, 4) != 10;
+    puts(failed ? "failed" : "ok");
+    return failed;
+}
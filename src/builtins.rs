@@ -0,0 +1,95 @@
+//! Functions the compiler provides without them being declared anywhere.
+use crate::symbol_table::TypeName;
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    Print,
+    Println,
+    Eprint,
+    Eprintln,
+    /// Number of command line arguments, the program name included.
+    ArgCount,
+    /// A command line argument as a `str`.
+    Arg,
+    /// `syscall(n, a1..a6)` with the arguments in the registers linux expects them in.
+    Syscall,
+    /// Length of a `str` in bytes.
+    Len,
+    /// `panic(msg)` writes where it was called from and `msg` to stderr, then exits.
+    Panic,
+    /// `size_of_val(ptr)` is the number of bytes of what `ptr` points at, it is never read.
+    SizeOfVal,
+}
+
+impl Builtin {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "print" => Some(Self::Print),
+            "println" => Some(Self::Println),
+            "eprint" => Some(Self::Eprint),
+            "eprintln" => Some(Self::Eprintln),
+            "arg_count" => Some(Self::ArgCount),
+            "arg" => Some(Self::Arg),
+            "syscall" => Some(Self::Syscall),
+            "len" => Some(Self::Len),
+            "panic" => Some(Self::Panic),
+            "size_of_val" => Some(Self::SizeOfVal),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Print => "print",
+            Self::Println => "println",
+            Self::Eprint => "eprint",
+            Self::Eprintln => "eprintln",
+            Self::ArgCount => "arg_count",
+            Self::Arg => "arg",
+            Self::Syscall => "syscall",
+            Self::Len => "len",
+            Self::Panic => "panic",
+            Self::SizeOfVal => "size_of_val",
+        }
+    }
+
+    /// How many arguments the builtin takes, `println()` on its own is just a newline.
+    pub fn arity(&self) -> RangeInclusive<usize> {
+        match self {
+            Self::Print | Self::Println | Self::Eprint | Self::Eprintln => 0..=1,
+            Self::ArgCount => 0..=0,
+            Self::Arg | Self::Len | Self::Panic | Self::SizeOfVal => 1..=1,
+            Self::Syscall => 1..=7,
+        }
+    }
+
+    pub fn ret_type(&self) -> TypeName {
+        match self {
+            Self::Print | Self::Println | Self::Eprint | Self::Eprintln | Self::Panic => {
+                TypeName::Void
+            }
+            Self::ArgCount | Self::Syscall | Self::Len | Self::SizeOfVal => TypeName::U64,
+            Self::Arg => TypeName::Str,
+        }
+    }
+
+    pub fn is_print(&self) -> bool {
+        matches!(
+            self,
+            Self::Print | Self::Println | Self::Eprint | Self::Eprintln
+        )
+    }
+
+    /// File descriptor the output is written to.
+    pub fn fd(&self) -> u64 {
+        match self {
+            Self::Eprint | Self::Eprintln => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn newline(&self) -> bool {
+        matches!(self, Self::Println | Self::Eprintln)
+    }
+}
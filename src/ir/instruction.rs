@@ -1,11 +1,36 @@
-use crate::{lexer::Token, parse::Ident};
+use crate::{lexer::Token, parse::Ident, parse::Type as AstType, symbol_table::TypeName};
 
 use super::{Imm, Label, Reg};
+use std::collections::HashMap;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub enum Type {
+    #[default]
     Null,
     U64,
+    /// Ints narrower or signed are held in a whole register, sign extended when signed and zero
+    /// extended otherwise.
+    U32,
+    I64,
+    I32,
+    Bool,
+    /// A unicode scalar value.
+    Char,
+    /// A single byte, kept zero extended in a whole register like a `bool`.
+    U8,
+    F32,
+    F64,
+    /// A `(ptr, len)` pair, held in two registers.
+    Str,
+    Array(Box<Type>, usize),
+    Ptr(Box<Type>),
+    /// A struct by its type and fields, a register holding one holds its address.
+    Struct(TypeName, Vec<(String, Type)>),
+    /// The address of a function and the type it returns.
+    Fn(Box<Type>),
+    /// The address of the environment of a closure and the type it returns, the environment
+    /// starts with the address of its function.
+    Closure(Box<Type>),
 }
 
 impl std::fmt::Display for Type {
@@ -13,6 +38,20 @@ impl std::fmt::Display for Type {
         match self {
             Self::Null => write!(f, "null"),
             Self::U64 => write!(f, "u64"),
+            Self::U32 => write!(f, "u32"),
+            Self::I64 => write!(f, "i64"),
+            Self::I32 => write!(f, "i32"),
+            Self::Bool => write!(f, "bool"),
+            Self::Char => write!(f, "char"),
+            Self::U8 => write!(f, "u8"),
+            Self::F32 => write!(f, "f32"),
+            Self::F64 => write!(f, "f64"),
+            Self::Str => write!(f, "str"),
+            Self::Array(ty, len) => write!(f, "[{ty}; {len}]"),
+            Self::Ptr(ty) => write!(f, "*{ty}"),
+            Self::Struct(name, _) => write!(f, "{name}"),
+            Self::Fn(ret) => write!(f, "fn -> {ret}"),
+            Self::Closure(ret) => write!(f, "closure -> {ret}"),
         }
     }
 }
@@ -22,14 +61,162 @@ impl TryFrom<&Ident> for Type {
     fn try_from(value: &Ident) -> Result<Self, Self::Error> {
         match value.value().as_str() {
             "u64" => Ok(Self::U64),
+            "u32" => Ok(Self::U32),
+            "u8" => Ok(Self::U8),
+            "i64" => Ok(Self::I64),
+            "i32" => Ok(Self::I32),
+            "str" => Ok(Self::Str),
+            "bool" => Ok(Self::Bool),
+            "char" => Ok(Self::Char),
+            "f32" => Ok(Self::F32),
+            "f64" => Ok(Self::F64),
             "null" => Ok(Self::Null),
             _ => Err("unknown type"),
         }
     }
 }
-impl Default for Type {
-    fn default() -> Self {
-        Self::Null
+
+impl From<&AstType> for Type {
+    fn from(value: &AstType) -> Self {
+        match value {
+            // NOTE: every scalar is a qword for now.
+            AstType::Name(ident) => Self::try_from(ident).unwrap_or(Self::U64),
+            AstType::Generic(_) => Self::U64,
+            AstType::Array(array) => {
+                Self::Array(Box::new(array.elem.as_ref().into()), array.len.value())
+            }
+            AstType::Ptr(ptr) => Self::Ptr(Box::new(ptr.elem.as_ref().into())),
+            AstType::Fn(func) => Self::Fn(Box::new(
+                func.ret.as_deref().map(Self::from).unwrap_or(Self::Null),
+            )),
+            AstType::Closure(closure) => Self::Closure(Box::new(
+                closure.ret.as_deref().map(Self::from).unwrap_or(Self::Null),
+            )),
+        }
+    }
+}
+
+impl From<&TypeName> for Type {
+    fn from(value: &TypeName) -> Self {
+        match value {
+            TypeName::Null | TypeName::Void => Self::Null,
+            TypeName::Str => Self::Str,
+            TypeName::Bool => Self::Bool,
+            TypeName::Char => Self::Char,
+            TypeName::U32 => Self::U32,
+            TypeName::U8 => Self::U8,
+            TypeName::I64 => Self::I64,
+            TypeName::I32 => Self::I32,
+            TypeName::F32 => Self::F32,
+            TypeName::F64 => Self::F64,
+            TypeName::Array(ty, len) => Self::Array(Box::new(ty.as_ref().into()), *len),
+            TypeName::Ptr(_, ty) => Self::Ptr(Box::new(ty.as_ref().into())),
+            TypeName::Fn(_, ret) => Self::Fn(Box::new(ret.as_ref().into())),
+            TypeName::Closure(_, ret) => Self::Closure(Box::new(ret.as_ref().into())),
+            _ => Self::U64,
+        }
+    }
+}
+
+impl Type {
+    /// Number of bytes a value of this type takes up on the stack.
+    pub fn bytes(&self) -> usize {
+        match self {
+            Self::Null => 0,
+            Self::U64 | Self::I64 | Self::Char | Self::F64 => 8,
+            Self::U32 | Self::I32 | Self::F32 => 4,
+            Self::Bool | Self::U8 => 1,
+            Self::Str => 16,
+            Self::Array(ty, len) => ty.bytes() * len,
+            Self::Ptr(_) | Self::Fn(_) | Self::Closure(_) => 8,
+            Self::Struct(_, fields) => fields
+                .iter()
+                .map(|(_, ty)| ty.bytes().next_multiple_of(8))
+                .sum(),
+        }
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, Self::Array(..))
+    }
+
+    pub fn is_struct(&self) -> bool {
+        matches!(self, Self::Struct(..))
+    }
+
+    /// Whether a value of the type takes more than a register, it is reached through its address.
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self, Self::Str | Self::Array(..) | Self::Struct(..))
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+
+    /// How many of the bits of the register an int, `bool` or `char` uses and whether it is
+    /// signed.
+    pub fn int_width(&self) -> Option<(usize, bool)> {
+        match self {
+            Self::U64 => Some((64, false)),
+            Self::I64 => Some((64, true)),
+            Self::U32 | Self::Char => Some((32, false)),
+            Self::I32 => Some((32, true)),
+            Self::Bool | Self::U8 => Some((8, false)),
+            _ => None,
+        }
+    }
+
+    /// Where a field of a struct starts and its type, every field is qword aligned.
+    pub fn field(&self, name: &str) -> Option<(usize, Type)> {
+        let Self::Struct(_, fields) = self else {
+            return None;
+        };
+        let mut offset = 0;
+        for (field, ty) in fields.iter() {
+            if field == name {
+                return Some((offset, ty.clone()));
+            }
+            offset += ty.bytes().next_multiple_of(8);
+        }
+        None
+    }
+
+    pub fn pointee(&self) -> Option<&Type> {
+        match self {
+            Self::Ptr(ty) => Some(ty),
+            _ => None,
+        }
+    }
+}
+
+/// A variable living in the current functions stack frame at `rbp - offset`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slot {
+    pub offset: usize,
+    pub ty: Type,
+}
+
+impl std::fmt::Display for Slot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "local[{}]", self.offset)
+    }
+}
+
+impl Slot {
+    /// Element type and length when the slot holds an array.
+    pub fn array(&self) -> Option<(&Type, usize)> {
+        match &self.ty {
+            Type::Array(ty, len) => Some((ty, *len)),
+            _ => None,
+        }
+    }
+
+    /// The part of this slot `bytes` above its start.
+    pub fn field(&self, bytes: usize, ty: Type) -> Slot {
+        Slot {
+            offset: self.offset - bytes,
+            ty,
+        }
     }
 }
 
@@ -37,12 +224,22 @@ impl Default for Type {
 pub enum Instruction {
     DefFunc(DefFunc),
     LoadImm(LoadImm),
+    LoadFloat(LoadFloat),
     CopyReg(CopyReg),
     Add(Add),
     Sub(Sub),
     Mul(Mul),
     Div(Div),
     Grt(Grt),
+    Les(Les),
+    Geq(Geq),
+    Leq(Leq),
+    Equal(Equal),
+    Neq(Neq),
+    Convert(Convert),
+    Trunc(Trunc),
+    ZExt(ZExt),
+    SExt(SExt),
     Copy(Copy),
     Conditional(Conditional),
     Jump(Jump),
@@ -51,6 +248,31 @@ pub enum Instruction {
     Return(Return),
     Enter(Enter),
     Leave(Leave),
+    LoadLocal(LoadLocal),
+    StoreLocal(StoreLocal),
+    LoadElem(LoadElem),
+    StoreElem(StoreElem),
+    FillArray(FillArray),
+    BoundsCheck(BoundsCheck),
+    OverflowCheck(OverflowCheck),
+    ZeroCheck(ZeroCheck),
+    DivOverflowCheck(DivOverflowCheck),
+    Panic(Panic),
+    AddrOf(AddrOf),
+    Load(Load),
+    Store(Store),
+    CopyMem(CopyMem),
+    ElemPtr(ElemPtr),
+    DataAddr(DataAddr),
+    DefData(DefData),
+    Extern(Extern),
+    Export(Export),
+    Discard(Discard),
+    Push(Push),
+    Syscall(Syscall),
+    LoadStatic(LoadStatic),
+    StoreStatic(StoreStatic),
+    DefStatic(DefStatic),
 }
 
 impl std::fmt::Display for Instruction {
@@ -58,12 +280,22 @@ impl std::fmt::Display for Instruction {
         match self {
             Self::DefFunc(i) => write!(f, "{i}"),
             Self::LoadImm(i) => write!(f, "{i}"),
+            Self::LoadFloat(i) => write!(f, "{i}"),
             Self::CopyReg(i) => write!(f, "{i}"),
             Self::Add(i) => write!(f, "{i}"),
             Self::Sub(i) => write!(f, "{i}"),
             Self::Mul(i) => write!(f, "{i}"),
             Self::Div(i) => write!(f, "{i}"),
             Self::Grt(i) => write!(f, "{i}"),
+            Self::Les(i) => write!(f, "{i}"),
+            Self::Geq(i) => write!(f, "{i}"),
+            Self::Leq(i) => write!(f, "{i}"),
+            Self::Equal(i) => write!(f, "{i}"),
+            Self::Neq(i) => write!(f, "{i}"),
+            Self::Convert(i) => write!(f, "{i}"),
+            Self::Trunc(i) => write!(f, "{i}"),
+            Self::ZExt(i) => write!(f, "{i}"),
+            Self::SExt(i) => write!(f, "{i}"),
             Self::Copy(i) => write!(f, "{i}"),
             Self::Conditional(i) => write!(f, "{i}"),
             Self::Jump(i) => write!(f, "{i}"),
@@ -72,6 +304,31 @@ impl std::fmt::Display for Instruction {
             Self::Return(i) => write!(f, "{i}"),
             Self::Enter(i) => write!(f, "{i}"),
             Self::Leave(i) => write!(f, "{i}"),
+            Self::LoadLocal(i) => write!(f, "{i}"),
+            Self::StoreLocal(i) => write!(f, "{i}"),
+            Self::LoadElem(i) => write!(f, "{i}"),
+            Self::StoreElem(i) => write!(f, "{i}"),
+            Self::FillArray(i) => write!(f, "{i}"),
+            Self::BoundsCheck(i) => write!(f, "{i}"),
+            Self::OverflowCheck(i) => write!(f, "{i}"),
+            Self::ZeroCheck(i) => write!(f, "{i}"),
+            Self::DivOverflowCheck(i) => write!(f, "{i}"),
+            Self::Panic(i) => write!(f, "{i}"),
+            Self::AddrOf(i) => write!(f, "{i}"),
+            Self::Load(i) => write!(f, "{i}"),
+            Self::Store(i) => write!(f, "{i}"),
+            Self::CopyMem(i) => write!(f, "{i}"),
+            Self::ElemPtr(i) => write!(f, "{i}"),
+            Self::DataAddr(i) => write!(f, "{i}"),
+            Self::DefData(i) => write!(f, "{i}"),
+            Self::Extern(i) => write!(f, "{i}"),
+            Self::Export(i) => write!(f, "{i}"),
+            Self::Discard(i) => write!(f, "{i}"),
+            Self::Push(i) => write!(f, "{i}"),
+            Self::Syscall(i) => write!(f, "{i}"),
+            Self::LoadStatic(i) => write!(f, "{i}"),
+            Self::StoreStatic(i) => write!(f, "{i}"),
+            Self::DefStatic(i) => write!(f, "{i}"),
         }
     }
 }
@@ -104,7 +361,12 @@ macro_rules! from_to {
 
 from_to!(DefFunc, Instruction);
 from_to!(LoadImm, Instruction);
+from_to!(LoadFloat, Instruction);
 from_to!(CopyReg, Instruction);
+from_to!(Convert, Instruction);
+from_to!(Trunc, Instruction);
+from_to!(ZExt, Instruction);
+from_to!(SExt, Instruction);
 from_to!(Copy, Instruction);
 from_to!(Conditional, Instruction);
 from_to!(Jump, Instruction);
@@ -113,6 +375,31 @@ from_to!(Call, Instruction);
 from_to!(Return, Instruction);
 from_to!(Enter, Instruction);
 from_to!(Leave, Instruction);
+from_to!(LoadLocal, Instruction);
+from_to!(StoreLocal, Instruction);
+from_to!(LoadElem, Instruction);
+from_to!(StoreElem, Instruction);
+from_to!(FillArray, Instruction);
+from_to!(BoundsCheck, Instruction);
+from_to!(OverflowCheck, Instruction);
+from_to!(ZeroCheck, Instruction);
+from_to!(DivOverflowCheck, Instruction);
+from_to!(Panic, Instruction);
+from_to!(AddrOf, Instruction);
+from_to!(Load, Instruction);
+from_to!(Store, Instruction);
+from_to!(CopyMem, Instruction);
+from_to!(ElemPtr, Instruction);
+from_to!(DataAddr, Instruction);
+from_to!(DefData, Instruction);
+from_to!(Extern, Instruction);
+from_to!(Export, Instruction);
+from_to!(Discard, Instruction);
+from_to!(Push, Instruction);
+from_to!(Syscall, Instruction);
+from_to!(LoadStatic, Instruction);
+from_to!(StoreStatic, Instruction);
+from_to!(DefStatic, Instruction);
 
 macro_rules! op_instruction {
     ($name:ident) => {
@@ -137,6 +424,11 @@ macro_rules! op_instruction {
                     "Mul" => "*",
                     "Div" => "/",
                     "Grt" => ">",
+                    "Les" => "<",
+                    "Geq" => ">=",
+                    "Leq" => "<=",
+                    "Equal" => "==",
+                    "Neq" => "!=",
                     _ => unreachable!(),
                 };
                 write!(f, "    {} = {} {} {}", self.des, self.lhs, op, self.rhs)
@@ -150,12 +442,23 @@ op_instruction!(Sub);
 op_instruction!(Mul);
 op_instruction!(Div);
 op_instruction!(Grt);
+op_instruction!(Les);
+op_instruction!(Geq);
+op_instruction!(Leq);
+op_instruction!(Equal);
+op_instruction!(Neq);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DefFunc {
     pub name: String,
     pub params: Vec<(Reg, Type)>,
     pub body: Vec<Instruction>,
+    /// Bytes of stack needed for the functions locals.
+    pub stack_size: usize,
+    /// The registers holding an `f32`, `f64`, `bool` or signed int. The backend keeps floats apart
+    /// from the rest, moves a `bool` in and out of memory a byte at a time and divides signed ints
+    /// with `idiv`.
+    pub mem_types: HashMap<Reg, Type>,
 }
 
 impl std::fmt::Display for DefFunc {
@@ -188,6 +491,75 @@ impl std::fmt::Display for LoadImm {
     }
 }
 
+/// An `f64` literal, kept as its bits so the instruction stays comparable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadFloat {
+    pub des: Reg,
+    pub bits: u64,
+}
+
+impl std::fmt::Display for LoadFloat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    load {} {:?}", self.des, f64::from_bits(self.bits))
+    }
+}
+
+/// Converts the number in `src` to a `ty`, where one of the two is a float.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Convert {
+    pub des: Reg,
+    pub src: Reg,
+    pub ty: Type,
+}
+
+impl std::fmt::Display for Convert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    {} = {} as {}", self.des, self.src, self.ty)
+    }
+}
+
+/// Keeps the low `bits` of `src` and clears the rest, for a narrower unsigned int.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trunc {
+    pub des: Reg,
+    pub src: Reg,
+    pub bits: usize,
+}
+
+impl std::fmt::Display for Trunc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    {} = trunc{} {}", self.des, self.bits, self.src)
+    }
+}
+
+/// Widens the unsigned int in the low `bits` of `src`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZExt {
+    pub des: Reg,
+    pub src: Reg,
+    pub bits: usize,
+}
+
+impl std::fmt::Display for ZExt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    {} = zext{} {}", self.des, self.bits, self.src)
+    }
+}
+
+/// Widens the signed int in the low `bits` of `src`, whatever is above them is dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SExt {
+    pub des: Reg,
+    pub src: Reg,
+    pub bits: usize,
+}
+
+impl std::fmt::Display for SExt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    {} = sext{} {}", self.des, self.bits, self.src)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CopyReg {
     pub des: Reg,
@@ -224,9 +596,29 @@ impl std::fmt::Display for Conditional {
     }
 }
 
+/// What a call jumps to, a function by its label, an address held in a register or a C function
+/// linked in with the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Callee {
+    Label(Label),
+    Reg(Reg),
+    Extern(Label),
+}
+
+impl std::fmt::Display for Callee {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Label(label) => write!(f, "{label}"),
+            Self::Reg(reg) => write!(f, "{reg}"),
+            Self::Extern(label) => write!(f, "extern {label}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Call {
-    pub caller: Label,
+    pub caller: Callee,
+    /// Pushed in order before the call, kept for the types of what was pushed.
     pub args: Vec<Reg>,
     pub ret: Reg,
 }
@@ -302,3 +694,372 @@ impl std::fmt::Display for Leave {
         write!(f, "    leave")
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadLocal {
+    pub des: Reg,
+    pub slot: Slot,
+}
+
+impl std::fmt::Display for LoadLocal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    load {} {}", self.des, self.slot)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreLocal {
+    pub slot: Slot,
+    pub src: Reg,
+}
+
+impl std::fmt::Display for StoreLocal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    store {} {}", self.slot, self.src)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadElem {
+    pub des: Reg,
+    pub slot: Slot,
+    pub index: Reg,
+}
+
+impl std::fmt::Display for LoadElem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    load {} {}[{}]", self.des, self.slot, self.index)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreElem {
+    pub slot: Slot,
+    pub index: Reg,
+    pub src: Reg,
+}
+
+impl std::fmt::Display for StoreElem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    store {}[{}] {}", self.slot, self.index, self.src)
+    }
+}
+
+/// Stores `src` into every element of the array in `slot`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FillArray {
+    pub slot: Slot,
+    pub src: Reg,
+}
+
+impl std::fmt::Display for FillArray {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    fill {} {}", self.slot, self.src)
+    }
+}
+
+/// Aborts the program when `index` is not below `len`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundsCheck {
+    pub index: Reg,
+    pub len: usize,
+}
+
+impl std::fmt::Display for BoundsCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    check {} < {}", self.index, self.len)
+    }
+}
+
+/// Panics with the `len` bytes at `msg` when the int arithmetic that just wrote `reg` did not
+/// fit in `bits`, signed or not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverflowCheck {
+    pub reg: Reg,
+    pub bits: usize,
+    pub signed: bool,
+    pub msg: Label,
+    pub len: usize,
+}
+
+impl std::fmt::Display for OverflowCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.signed { 'i' } else { 'u' };
+        write!(
+            f,
+            "    check {} fits {sign}{} else {}",
+            self.reg, self.bits, self.msg
+        )
+    }
+}
+
+/// Panics with the `len` bytes at `msg` when `reg` is zero, checked before it is divided by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZeroCheck {
+    pub reg: Reg,
+    pub msg: Label,
+    pub len: usize,
+}
+
+impl std::fmt::Display for ZeroCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    check {} != 0 else {}", self.reg, self.msg)
+    }
+}
+
+/// Panics with the `len` bytes at `msg` when `lhs` is the smallest signed int of `bits` and
+/// `rhs` is -1, the quotient does not fit and the cpu faults on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DivOverflowCheck {
+    pub lhs: Reg,
+    pub rhs: Reg,
+    pub bits: usize,
+    pub msg: Label,
+    pub len: usize,
+}
+
+impl std::fmt::Display for DivOverflowCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "    check {} / {} fits i{} else {}",
+            self.lhs, self.rhs, self.bits, self.msg
+        )
+    }
+}
+
+/// Writes the `len` bytes at `msg` to stderr and exits with `code`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Panic {
+    pub code: u64,
+    pub msg: Label,
+    pub len: usize,
+}
+
+impl std::fmt::Display for Panic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    panic {} {}", self.code, self.msg)
+    }
+}
+
+/// Address of the local in `slot`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddrOf {
+    pub des: Reg,
+    pub slot: Slot,
+}
+
+impl std::fmt::Display for AddrOf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    addr {} {}", self.des, self.slot)
+    }
+}
+
+/// Reads the value `addr` points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Load {
+    pub des: Reg,
+    pub addr: Reg,
+}
+
+impl std::fmt::Display for Load {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    load {} [{}]", self.des, self.addr)
+    }
+}
+
+/// Writes `src` to the place `addr` points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Store {
+    pub addr: Reg,
+    pub src: Reg,
+}
+
+impl std::fmt::Display for Store {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    store [{}] {}", self.addr, self.src)
+    }
+}
+
+/// Copies the `bytes` at `from` to `to`, a whole struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyMem {
+    pub to: Reg,
+    pub from: Reg,
+    pub bytes: usize,
+}
+
+impl std::fmt::Display for CopyMem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    copy [{}] [{}] {}", self.to, self.from, self.bytes)
+    }
+}
+
+/// Address of element `index` in the array starting at `base`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElemPtr {
+    pub des: Reg,
+    pub base: Reg,
+    pub index: Reg,
+    pub ty: Type,
+}
+
+impl std::fmt::Display for ElemPtr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    elem {} {}[{}]", self.des, self.base, self.index)
+    }
+}
+
+/// Address of a label in one of the data segments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataAddr {
+    pub des: Reg,
+    pub label: Label,
+}
+
+impl std::fmt::Display for DataAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    addr {} {}", self.des, self.label)
+    }
+}
+
+/// Read only bytes, for now only the contents of string literals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefData {
+    pub label: Label,
+    pub bytes: Vec<u8>,
+}
+
+impl std::fmt::Display for DefData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = String::from_utf8_lossy(&self.bytes);
+        writeln!(f, "data {} \"{}\"", self.label, text.escape_debug())
+    }
+}
+
+/// A function the program is linked against, by the symbol it is defined under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extern(pub Label);
+
+impl std::fmt::Display for Extern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "extern {}", self.0)
+    }
+}
+
+/// A function C calls by `symbol`, with the types of the params C passes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Export {
+    pub symbol: Label,
+    pub func: Label,
+    pub params: Vec<Type>,
+}
+
+impl std::fmt::Display for Export {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            symbol,
+            func,
+            params,
+        } = self;
+        let params = params
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(f, "export {symbol} = {func}({params})")
+    }
+}
+
+/// The value in the register is never read again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discard(pub Reg);
+
+impl std::fmt::Display for Discard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    discard {}", self.0)
+    }
+}
+
+/// Puts the value on the stack for a `Syscall` or a `Call` to pick up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Push(pub Reg);
+
+impl std::fmt::Display for Push {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    push {}", self.0)
+    }
+}
+
+/// Pops the syscall number and its arguments off the stack, last one on top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Syscall {
+    pub des: Reg,
+    pub argc: usize,
+}
+
+impl std::fmt::Display for Syscall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    syscall {} -> {}", self.argc, self.des)
+    }
+}
+
+/// Reads the `static` at `label`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadStatic {
+    pub des: Reg,
+    pub label: Label,
+    pub ty: Type,
+}
+
+impl std::fmt::Display for LoadStatic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    load {} {}", self.des, self.label)
+    }
+}
+
+/// Writes `src` to the `static` at `label`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreStatic {
+    pub label: Label,
+    pub ty: Type,
+    pub src: Reg,
+}
+
+impl std::fmt::Display for StoreStatic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "    store {} {}", self.label, self.src)
+    }
+}
+
+/// A `static` in the writable data segment and the value it starts out with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefStatic {
+    pub label: Label,
+    pub ty: Type,
+    pub value: StaticValue,
+}
+
+impl std::fmt::Display for DefStatic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "static {}: {} = {}", self.label, self.ty, self.value)
+    }
+}
+
+/// The value a `static` starts out with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaticValue {
+    /// The bits of a scalar, only the first `ty.bytes()` of them are used.
+    Bits(u64),
+    /// A `str` points at interned data and has its length next to it.
+    Str(Label, u64),
+}
+
+impl std::fmt::Display for StaticValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bits(bits) => write!(f, "{bits}"),
+            Self::Str(label, len) => write!(f, "{label} {len}"),
+        }
+    }
+}
@@ -1,25 +1,116 @@
 mod instruction;
 #[cfg(test)]
 mod test;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub use instruction::*;
 
 use crate::lexer::*;
 
 use crate::parse::{
-    Expr, ExprBinary, ExprBlock, ExprCall, ExprIf, ExprLet, ExprLit, ExprReturn, ExprVar, Ident,
-    Item, ItemFn, Lit, LitBool, LitInt, Op, Param, Statement,
+    Expr, ExprArray, ExprArrayRepeat, ExprAssign, ExprBinary, ExprBlock, ExprCall, ExprCast,
+    ExprClosure, ExprField, ExprFor, ExprIf, ExprIndex, ExprLet, ExprLit, ExprMethodCall,
+    ExprReturn, ExprStruct, ExprUnary, ExprVar, Ident, Item, ItemExtern, ItemFn, ItemImpl,
+    ItemStatic, ItemStruct, Lit, LitBool, LitChar, LitFloat, LitInt, LitStr, Op, Param, Statement,
+    Type as AstType,
 };
 // use crate::semantic_analysis::{Symbol, SymbolTable, Variable};
 
-use crate::symbol_table::SymbolTable;
+use crate::builtins::Builtin;
+use crate::semantic_analysis::{
+    eval_globals, method_name, Calls, Capture, Global, Method, Receiver, Value,
+};
+use crate::stdlib;
+use crate::symbol_table::{Symbol, SymbolTable, SymbolType, TypeName};
+use crate::x86_64_linux::runtime;
 
+/// `overflow_checks` makes every int `+`, `-` and `*` panic at runtime when the result does not
+/// fit its type.
 pub fn code_gen(
-    (ast, symbol_table): (Vec<Item>, SymbolTable),
+    (ast, symbol_table, calls): (Vec<Item>, SymbolTable, Calls),
+    overflow_checks: bool,
 ) -> Result<(Vec<Instruction>, SymbolTable), Vec<String>> {
-    let mut gen = IrGenerator::new(symbol_table);
+    let (globals, _) = eval_globals(&ast);
+    let mut gen = IrGenerator::new(symbol_table, globals, calls);
+    gen.overflow_checks = overflow_checks;
+    gen.generic_fns = ast
+        .iter()
+        .filter_map(Item::as_fn)
+        .filter(|item_fn| item_fn.is_generic())
+        .map(|item_fn| (item_fn.name.value(), item_fn.clone()))
+        .collect();
+    // NOTE: the methods of a generic struct get a copy for every type they are called with, the
+    // type parameters of the impl are theirs.
+    for item in ast.iter() {
+        let Item::Impl(item_impl) = item else {
+            continue;
+        };
+        if item_impl.generics.is_empty() {
+            continue;
+        }
+        let ty = TypeName::from(&item_impl.ty);
+        for item_fn in item_impl.fns.iter() {
+            let mut item_fn = item_fn.clone();
+            let label = method_name(item_impl.trait_name(), &ty, &item_fn.name.value);
+            item_fn.name.value = label.clone();
+            let generics = item_impl.generics.iter().cloned();
+            item_fn.generics = generics.chain(item_fn.generics).collect();
+            gen.generic_fns.insert(label.clone(), item_fn);
+            gen.impl_types.insert(label, ty.clone());
+        }
+    }
+    gen.externs = ast
+        .iter()
+        .filter_map(|item| match item {
+            Item::Extern(item_extern) => Some(item_extern.name.value()),
+            _ => None,
+        })
+        .collect();
+    gen.structs = ast
+        .iter()
+        .filter_map(|item| match item {
+            Item::Struct(ItemStruct {
+                name,
+                generics,
+                fields,
+                ..
+            }) => Some((
+                name.value(),
+                (
+                    generics
+                        .iter()
+                        .map(|generic| generic.name.value())
+                        .collect(),
+                    fields
+                        .iter()
+                        .map(|Param { name, kind, .. }| (name.value(), TypeName::from(kind)))
+                        .collect(),
+                ),
+            )),
+            _ => None,
+        })
+        .collect();
+    for item in ast.iter() {
+        let Item::Impl(item_impl) = item else {
+            continue;
+        };
+        let ty = TypeName::from(&item_impl.ty);
+        let this = HashMap::from([("Self".to_string(), ty.clone())]);
+        for item_fn in item_impl.fns.iter() {
+            let ret = item_fn
+                .ret_type
+                .as_ref()
+                .map(|ret| TypeName::from(ret).substitute(&this))
+                .unwrap_or(TypeName::Null);
+            let label = method_name(item_impl.trait_name(), &ty, &item_fn.name.value);
+            gen.methods.insert(label, ret);
+        }
+    }
     gen.visit(&ast);
+    while !gen.pending.is_empty() || !gen.closures.is_empty() {
+        gen.monomorphize();
+        gen.lift_closures();
+    }
     // for i in gen.code.iter() {
     //     match i {
     //         Instruction::DefFunc(DefFunc { name, params, body }) => {
@@ -44,12 +135,39 @@ pub fn code_gen(
     //         Instruction::Leave(_) => eprintln!("leave"),
     //     }
     // }
+    if !gen.errors.is_empty() {
+        return Err(gen.errors);
+    }
+    for (i, string) in gen.strings.iter().enumerate() {
+        // NOTE: nul terminated so a literal can be handed to the kernel as a path.
+        let mut bytes = string.as_bytes().to_vec();
+        bytes.push(0);
+        gen.code.push(
+            DefData {
+                label: str_label(i),
+                bytes,
+            }
+            .into(),
+        );
+    }
     Ok((gen.code, gen.symbol_table))
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Label(pub String);
 
+fn str_label(index: usize) -> Label {
+    Label(format!("_str{index}"))
+}
+
+/// `max$u64` for `max<T>` called with a `u64`.
+fn instance_name(name: &str, type_args: &[TypeName]) -> String {
+    type_args
+        .iter()
+        .map(TypeName::mangle)
+        .fold(name.to_string(), |name, ty| format!("{name}${ty}"))
+}
+
 impl std::fmt::Display for Label {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -83,15 +201,6 @@ impl std::fmt::Display for Reg {
     }
 }
 
-#[derive(Debug, Hash, Clone, PartialEq, Eq)]
-pub struct Var(pub String);
-
-impl std::fmt::Display for Var {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
-
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Imm(pub u64);
 
@@ -111,10 +220,35 @@ trait Ir {
     fn def_label(&mut self, label: Label);
     fn jump(&mut self, label: Label);
     fn load_imm(&mut self, imm: Imm) -> Reg;
+    /// A register that is never written to, for things without a value.
+    fn null(&mut self) -> Reg;
     fn binary(&mut self, op: &Op, lhs: Reg, rhs: Reg) -> Reg;
     fn conditional(&mut self, label: Label, reg: Reg) -> Reg;
     fn call(&mut self, label: Label, args: Vec<Reg>, ret: Reg) -> Reg;
+    fn call_indirect(&mut self, callee: Reg, args: Vec<Reg>, ret: Reg) -> Reg;
+    fn call_extern(&mut self, label: Label, args: Vec<Reg>, ret: Reg) -> Reg;
     fn early_return(&mut self, reg: Option<Reg>);
+    fn copy_mem(&mut self, to: Reg, from: Reg, bytes: usize);
+    fn load_local(&mut self, slot: Slot) -> Reg;
+    fn store_local(&mut self, slot: Slot, src: Reg);
+    fn load_elem(&mut self, slot: Slot, index: Reg) -> Reg;
+    fn store_elem(&mut self, slot: Slot, index: Reg, src: Reg);
+    fn fill_array(&mut self, slot: Slot, src: Reg);
+    fn bounds_check(&mut self, index: Reg, len: usize);
+    fn overflow_check(&mut self, reg: Reg, bits: usize, signed: bool, msg: &str);
+    fn zero_check(&mut self, reg: Reg, msg: &str);
+    fn div_overflow_check(&mut self, lhs: Reg, rhs: Reg, bits: usize, msg: &str);
+    fn panic(&mut self, msg: &str, code: u64);
+    fn addr_of(&mut self, slot: Slot) -> Reg;
+    fn load(&mut self, addr: Reg, ty: Type) -> Reg;
+    fn store(&mut self, addr: Reg, src: Reg);
+    fn elem_ptr(&mut self, base: Reg, index: Reg, ty: Type) -> Reg;
+    fn data_addr(&mut self, label: Label) -> Reg;
+    fn discard(&mut self, reg: Reg);
+    fn push(&mut self, reg: Reg);
+    fn syscall(&mut self, argc: usize) -> Reg;
+    fn load_static(&mut self, label: Label, ty: Type) -> Reg;
+    fn store_static(&mut self, label: Label, ty: Type, src: Reg);
 }
 
 trait AstVisitor: Ir {
@@ -122,28 +256,39 @@ trait AstVisitor: Ir {
     // FIXME: Not really what i wanted to do.
     fn visit_params(&mut self, expr: &Param) -> Reg;
     fn visit_expr_call(&mut self, expr_call: &ExprCall) -> Reg;
+    fn visit_expr_method_call(&mut self, expr_method_call: &ExprMethodCall) -> Reg;
     fn visit_expr_binary(&mut self, bin: &ExprBinary) -> Reg;
     fn visit_item_fn(&mut self, item_fn: &ItemFn);
+    fn visit_item_impl(&mut self, item_impl: &ItemImpl);
+    fn visit_item_static(&mut self, item_static: &ItemStatic);
+    fn visit_item_extern(&mut self, item_extern: &ItemExtern);
     fn visit_lit_int(&mut self, lit_int: &LitInt) -> Reg;
+    fn visit_lit_float(&mut self, lit_float: &LitFloat) -> Reg;
     fn visit_lit_bool(&mut self, lit_bool: &LitBool) -> Reg;
+    fn visit_lit_str(&mut self, lit_str: &LitStr) -> Reg;
+    fn visit_lit_char(&mut self, lit_char: &LitChar) -> Reg;
     fn visit_expr_if(&mut self, expr_if: &ExprIf) -> Reg;
+    fn visit_expr_for(&mut self, expr_for: &ExprFor) -> Reg;
     fn visit_expr_let(&mut self, expr_let: &ExprLet) -> Reg;
-
-    fn visit_expr_return(&mut self, expr_ret: &ExprReturn) -> Reg {
-        let ExprReturn { expr, .. } = expr_ret;
-        let reg = self.visit_expr(expr);
-        let r = if (**expr).is_call() { None } else { Some(reg) };
-        self.early_return(r);
-        self.jump(".exit".into());
-        reg
-    }
+    fn visit_expr_index(&mut self, expr_index: &ExprIndex) -> Reg;
+    fn visit_expr_assign(&mut self, expr_assign: &ExprAssign) -> Reg;
+    fn visit_expr_unary(&mut self, expr_unary: &ExprUnary) -> Reg;
+    fn visit_expr_struct(&mut self, expr_struct: &ExprStruct) -> Reg;
+    fn visit_expr_field(&mut self, expr_field: &ExprField) -> Reg;
+    fn visit_expr_return(&mut self, expr_ret: &ExprReturn) -> Reg;
+    fn visit_expr_closure(&mut self, expr_closure: &ExprClosure) -> Reg;
+    fn visit_expr_cast(&mut self, expr_cast: &ExprCast) -> Reg;
+    fn visit_expr_array(&mut self, expr: &Expr) -> Reg;
+    fn enter_scope(&mut self);
+    fn leave_scope(&mut self);
 
     fn visit_lit(&mut self, lit: &Lit) -> Reg {
         match lit {
             Lit::Int(ref lint) => self.visit_lit_int(lint),
+            Lit::Float(ref lfloat) => self.visit_lit_float(lfloat),
             Lit::Bool(ref lbool) => self.visit_lit_bool(lbool),
-            Lit::Str(_lstr) => unimplemented!(),
-            Lit::Char(_lchar) => unimplemented!(),
+            Lit::Str(ref lstr) => self.visit_lit_str(lstr),
+            Lit::Char(ref lchar) => self.visit_lit_char(lchar),
         }
     }
 
@@ -157,11 +302,21 @@ trait AstVisitor: Ir {
             Expr::Lit(ref elit) => self.visit_expr_lit(elit),
             Expr::Binary(ref ebinary) => self.visit_expr_binary(ebinary),
             Expr::Call(ref ecall) => self.visit_expr_call(ecall),
+            Expr::MethodCall(ecall) => self.visit_expr_method_call(ecall),
             Expr::Var(evar) => self.visit_expr_var(evar),
             Expr::If(eif) => self.visit_expr_if(eif),
+            Expr::For(efor) => self.visit_expr_for(efor),
             Expr::Block(eblock) => self.visit_expr_block(eblock),
             Expr::Return(ereturn) => self.visit_expr_return(ereturn),
-            Expr::Let(_) => unimplemented!(),
+            Expr::Let(elet) => self.visit_expr_let(elet),
+            Expr::Index(eindex) => self.visit_expr_index(eindex),
+            Expr::Assign(eassign) => self.visit_expr_assign(eassign),
+            Expr::Unary(eunary) => self.visit_expr_unary(eunary),
+            Expr::Struct(estruct) => self.visit_expr_struct(estruct),
+            Expr::Field(efield) => self.visit_expr_field(efield),
+            Expr::Closure(eclosure) => self.visit_expr_closure(eclosure),
+            Expr::Cast(ecast) => self.visit_expr_cast(ecast),
+            Expr::Array(_) | Expr::ArrayRepeat(_) => self.visit_expr_array(expr),
         }
     }
 
@@ -171,42 +326,322 @@ trait AstVisitor: Ir {
     }
 
     fn visit_expr_block(&mut self, block: &ExprBlock) -> Reg {
+        self.enter_scope();
         let mut reg: Option<Reg> = None;
         for stmt in block.stmts.iter() {
+            if let Some(unused) = reg {
+                self.discard(unused);
+            }
             reg = Some(self.visit_stmt(stmt));
         }
-        let Some(reg) = reg else {
-            panic!("WHAT DO I DO HERE!");
-        };
-        reg
+        self.leave_scope();
+        // NOTE: an empty block has no value, the register is never written to.
+        reg.unwrap_or_else(|| self.null())
     }
 
     fn visit(&mut self, items: &[Item]) {
         for item in items.iter() {
             match item {
+                // NOTE: generic functions get a copy for every set of types they are called with.
+                Item::Fn(ref item_fn) if item_fn.is_generic() => {}
                 Item::Fn(ref item_fn) => self.visit_item_fn(item_fn),
+                Item::Static(ref item_static) => self.visit_item_static(item_static),
+                Item::Impl(ref item_impl) => self.visit_item_impl(item_impl),
+                Item::Extern(ref item_extern) => self.visit_item_extern(item_extern),
+                // NOTE: modules are already loaded and their names resolved, consts are
+                // inlined where they are used, method calls go straight to the impl and a
+                // struct is only a layout.
+                Item::Use(_) | Item::Const(_) | Item::Trait(_) | Item::Struct(_) => {}
             }
         }
     }
 }
 
+/// Somewhere a value can be read from, written to or borrowed.
+#[derive(Debug, Clone)]
+enum Place {
+    Local(Slot),
+    Elem(Slot, Reg),
+    Addr(Reg, Type),
+    Static(Label, Type),
+}
+
+impl Place {
+    fn ty(&self) -> Type {
+        match self {
+            Self::Local(slot) => slot.ty.clone(),
+            Self::Elem(slot, _) => slot.array().map(|(ty, _)| ty.clone()).unwrap_or_default(),
+            Self::Addr(_, ty) | Self::Static(_, ty) => ty.clone(),
+        }
+    }
+}
+
+/// The type parameters of a struct and its fields.
+type StructFields = (Vec<String>, Vec<(String, TypeName)>);
+
 #[derive(Debug, Default)]
 struct IrGenerator {
     code: Vec<Instruction>,
     block: Vec<Instruction>,
     reg_counter: usize,
-    vars: HashMap<String, Reg>,
+    /// Variables in scope, the innermost block last.
+    vars: Vec<HashMap<String, Slot>>,
+    reg_types: HashMap<Reg, Type>,
+    /// For `str` values the register holding the length next to the pointer.
+    str_lens: HashMap<Reg, Reg>,
+    strings: Vec<String>,
+    stack_size: usize,
     gen_label_number: usize,
     symbol_table: SymbolTable,
+    globals: HashMap<String, Global>,
+    calls: Calls,
+    generic_fns: HashMap<String, ItemFn>,
+    /// Return type of every method, by its label.
+    methods: HashMap<String, TypeName>,
+    /// The type parameters and fields of every struct, by its name.
+    structs: HashMap<String, StructFields>,
+    /// What `Self` is in the methods of generic structs, by their label.
+    impl_types: HashMap<String, TypeName>,
+    /// Functions linked in from outside of the program, by their name.
+    externs: HashSet<String>,
+    /// Copies of generic functions still to be generated.
+    pending: Vec<(String, Vec<TypeName>)>,
+    instantiated: HashSet<String>,
+    /// The types the type parameters stand for in the copy being generated, or what `Self`
+    /// stands for in a method.
+    type_args: HashMap<String, TypeName>,
+    /// Label of the copy being generated.
+    instance_name: Option<String>,
+    /// Name of the function in the source, calls to generic functions are found by it.
+    current_fn: String,
+    /// Where the current function writes the struct it returns.
+    sret: Option<Slot>,
+    /// Closures made so far still waiting for their function to be generated.
+    closures: Vec<Lifted>,
+    closure_count: usize,
+    /// Where the environment of the closure being generated is kept.
+    env: Option<Slot>,
+    /// The locals the closure being generated took along, by where they are in its environment.
+    captures: HashMap<String, EnvField>,
+    /// The file the function being generated is written in, for where a panic says it is from.
+    file: Option<String>,
+    overflow_checks: bool,
+    errors: Vec<String>,
+}
+
+/// A closure turned into a function of its own, it takes the address of its environment before
+/// its params.
+#[derive(Debug)]
+struct Lifted {
+    label: String,
+    expr: ExprClosure,
+    ret: Type,
+    captures: HashMap<String, EnvField>,
+    /// The function it is made in and the type parameters of its copy, the body is checked as
+    /// part of it.
+    current_fn: String,
+    type_args: HashMap<String, TypeName>,
+    file: Option<String>,
+}
+
+/// A captured local, the environment holds its address when it is captured by reference.
+#[derive(Debug, Clone)]
+struct EnvField {
+    offset: usize,
+    ty: Type,
+    by_ref: bool,
 }
 
 impl IrGenerator {
-    fn new(symbol_table: SymbolTable) -> Self {
+    fn new(symbol_table: SymbolTable, globals: HashMap<String, Global>, calls: Calls) -> Self {
         Self {
             symbol_table,
+            globals,
+            calls,
             ..Default::default()
         }
     }
+
+    /// Generates a copy of every generic function for each set of types it is called with,
+    /// including the calls from inside of other copies.
+    fn monomorphize(&mut self) {
+        while let Some((name, type_args)) = self.pending.pop() {
+            let item_fn = self.generic_fns[&name].clone();
+            self.type_args = item_fn
+                .generics
+                .iter()
+                .map(|generic| generic.name.value())
+                .zip(type_args.iter().cloned())
+                .collect();
+            if let Some(ty) = self.impl_types.get(&name) {
+                let this = ty.substitute(&self.type_args);
+                self.type_args.insert("Self".into(), this);
+            }
+            self.instance_name = Some(instance_name(&name, &type_args));
+            self.visit_item_fn(&item_fn);
+        }
+        self.type_args.clear();
+    }
+
+    /// Generates the function of every closure, including the ones made inside of them.
+    fn lift_closures(&mut self) {
+        while let Some(lifted) = self.closures.pop() {
+            let Lifted {
+                label,
+                expr,
+                ret,
+                captures,
+                current_fn,
+                type_args,
+                file,
+            } = lifted;
+            self.current_fn = current_fn;
+            self.type_args = type_args;
+            self.file = file;
+            self.captures = captures;
+            let params = self.enter_fn(true, &expr.params, ret.clone());
+            let reg = self.visit_expr(&expr.body);
+            // NOTE: the body is the value of the closure unless it returns on its own.
+            let returns = match expr.body.as_ref() {
+                Expr::Block(block) => block
+                    .stmts
+                    .last()
+                    .is_some_and(|stmt| matches!(stmt.stmt, Expr::Return(_))),
+                body => matches!(body, Expr::Return(_)),
+            };
+            match ret {
+                Type::Null => self.discard(reg),
+                _ if returns => self.discard(reg),
+                _ => {
+                    self.return_value(reg);
+                }
+            }
+            self.finish_fn(label, params);
+        }
+        self.captures.clear();
+        self.type_args.clear();
+    }
+
+    /// A type written in the source, with the type parameters of the current copy filled in.
+    fn ast_type(&self, ty: &AstType) -> Type {
+        self.ir_type(&TypeName::from(ty).substitute(&self.type_args))
+    }
+
+    /// The type semantic analysis gave a number literal that is not a `u64` or an `f64`.
+    fn lit_type(&self, span: Span) -> Option<Type> {
+        let key = (self.current_fn.clone(), span);
+        let ty = self.calls.lits.get(&key)?;
+        Some(self.ir_type(&ty.substitute(&self.type_args)))
+    }
+
+    /// How a value of the type is held, a struct gets the layout of its fields.
+    fn ir_type(&self, ty: &TypeName) -> Type {
+        self.layout(ty, &mut vec![])
+    }
+
+    /// A struct pointing at itself is left without fields inside of itself, `resolve` lays it
+    /// out once a value of it is reached.
+    fn layout(&self, ty: &TypeName, outer: &mut Vec<TypeName>) -> Type {
+        match ty {
+            TypeName::Custom(_) | TypeName::Generic(..) if outer.contains(ty) => {
+                Type::Struct(ty.clone(), vec![])
+            }
+            TypeName::Custom(name) | TypeName::Generic(name, _)
+                if self.structs.contains_key(name) =>
+            {
+                let (generics, fields) = &self.structs[name];
+                let args = match ty {
+                    TypeName::Generic(_, args) => args.as_slice(),
+                    _ => &[],
+                };
+                let bindings = generics.iter().cloned().zip(args.iter().cloned()).collect();
+                outer.push(ty.clone());
+                let fields = fields
+                    .iter()
+                    .map(|(field, ty)| {
+                        (field.clone(), self.layout(&ty.substitute(&bindings), outer))
+                    })
+                    .collect();
+                outer.pop();
+                Type::Struct(ty.clone(), fields)
+            }
+            TypeName::Array(ty, len) => Type::Array(Box::new(self.layout(ty, outer)), *len),
+            TypeName::Ptr(_, ty) => Type::Ptr(Box::new(self.layout(ty, outer))),
+            TypeName::Fn(_, ret) => Type::Fn(Box::new(self.layout(ret, outer))),
+            TypeName::Closure(_, ret) => Type::Closure(Box::new(self.layout(ret, outer))),
+            ty => Type::from(ty),
+        }
+    }
+
+    /// Lays out the structs a value of the type holds or points at.
+    fn resolve(&self, ty: Type) -> Type {
+        match ty {
+            Type::Struct(ty, _) => self.ir_type(&ty),
+            Type::Array(ty, len) => Type::Array(Box::new(self.resolve(*ty)), len),
+            Type::Ptr(ty) => Type::Ptr(Box::new(self.resolve(*ty))),
+            Type::Fn(ret) => Type::Fn(Box::new(self.resolve(*ret))),
+            Type::Closure(ret) => Type::Closure(Box::new(self.resolve(*ret))),
+            ty => ty,
+        }
+    }
+
+    /// A function returning a struct, an array or a `str` takes the address to write it to after
+    /// its parameters.
+    fn sret_param(&mut self, ret: Type, params: &mut Vec<(Reg, Slot)>) {
+        self.sret = None;
+        if !ret.is_aggregate() {
+            return;
+        }
+        let ty = Type::Ptr(Box::new(ret));
+        let reg = self.get_reg();
+        self.set_type(reg, ty.clone());
+        let slot = self.alloc_slot(ty);
+        params.push((reg, slot.clone()));
+        self.sret = Some(slot);
+    }
+
+    /// The caller of a function returning a struct, an array or a `str` hands it a slot to write
+    /// it to, pushed after the arguments.
+    fn ret_slot(&mut self, ret: Reg, args: &mut Vec<Reg>) {
+        let ty = self.type_of(ret);
+        if !ty.is_aggregate() {
+            return;
+        }
+        let slot = self.alloc_slot(ty);
+        let addr = self.addr_of(slot);
+        self.push(addr);
+        args.push(addr);
+    }
+
+    /// The arguments of a call are pushed in order, the registers are kept for their types.
+    fn push_args(&mut self, args: Vec<Reg>) -> Vec<Reg> {
+        for reg in args.iter() {
+            self.push(*reg);
+        }
+        args
+    }
+
+    /// Pushes each argument as soon as it is worked out, so no more than one of them is ever held
+    /// in a register.
+    fn eval_args(&mut self, exprs: &[Expr]) -> Vec<Reg> {
+        exprs
+            .iter()
+            .flat_map(|expr| {
+                let reg = self.visit_expr(expr);
+                let regs = self.value_regs(reg);
+                self.push_args(regs)
+            })
+            .collect()
+    }
+
+    /// What a call gave back, a `str` is read out of the slot it was written to.
+    fn returned(&mut self, ret: Reg) -> Reg {
+        match self.type_of(ret) {
+            Type::Str => self.read_place(Place::Addr(ret, Type::Str)),
+            _ => ret,
+        }
+    }
+
     fn push_to_block(&mut self, ir: impl Into<Instruction>) {
         self.block.push(ir.into());
     }
@@ -223,6 +658,774 @@ impl IrGenerator {
 
     fn reset_regester_count(&mut self) {
         self.reg_counter = 0;
+        self.reg_types.clear();
+        self.str_lens.clear();
+    }
+
+    fn set_type(&mut self, reg: Reg, ty: Type) -> Reg {
+        let ty = self.resolve(ty);
+        self.reg_types.insert(reg, ty);
+        reg
+    }
+
+    fn type_of(&self, reg: Reg) -> Type {
+        self.reg_types.get(&reg).cloned().unwrap_or(Type::U64)
+    }
+
+    fn alloc_slot(&mut self, ty: Type) -> Slot {
+        // NOTE: every slot starts on a qword, a `bool` alone still gets a whole one.
+        self.stack_size += ty.bytes().next_multiple_of(8);
+        Slot {
+            offset: self.stack_size,
+            ty,
+        }
+    }
+
+    /// Registers a value is passed around in, two for a `str`.
+    fn value_regs(&self, reg: Reg) -> Vec<Reg> {
+        match self.str_lens.get(&reg) {
+            Some(len) => vec![reg, *len],
+            None => vec![reg],
+        }
+    }
+
+    fn builtin_call(&mut self, builtin: Builtin, args: &[Expr], span: Span) -> Reg {
+        match builtin {
+            Builtin::ArgCount => {
+                let addr = self.data_addr(runtime::ARGC.into());
+                self.load(addr, Type::U64)
+            }
+            Builtin::Arg => {
+                let index = self.visit_expr(&args[0]);
+                let ptr = self.get_reg();
+                let args = self.push_args(vec![index]);
+                self.call(runtime::ARG.into(), args, ptr);
+                // NOTE: call arguments are consumed so strlen gets a copy of the pointer.
+                let src = self.get_reg();
+                self.push_to_block(CopyReg { des: src, src: ptr });
+                let len = self.get_reg();
+                let args = self.push_args(vec![src]);
+                self.call(runtime::STRLEN.into(), args, len);
+                self.str_lens.insert(ptr, len);
+                self.set_type(ptr, Type::Str)
+            }
+            Builtin::Syscall => {
+                // NOTE: the arguments go through the stack as all seven of them would not fit in
+                // the registers the allocator hands out.
+                for arg in args.iter() {
+                    let value = self.visit_expr(arg);
+                    self.push(value);
+                    if let Some(len) = self.str_lens.get(&value).copied() {
+                        self.push_to_block(Discard(len));
+                    }
+                }
+                self.syscall(args.len())
+            }
+            Builtin::Len => {
+                let value = self.visit_expr(&args[0]);
+                let Some(len) = self.str_lens.get(&value).copied() else {
+                    unreachable!("len of a value that is not a str");
+                };
+                self.push_to_block(Discard(value));
+                len
+            }
+            Builtin::SizeOfVal => {
+                let ptr = self.visit_expr(&args[0]);
+                let ty = self.type_of(ptr).pointee().cloned().unwrap_or_default();
+                self.discard(ptr);
+                let bytes = self.resolve(ty).bytes() as u64;
+                let size = self.load_imm(bytes.into());
+                self.set_type(size, Type::U64)
+            }
+            Builtin::Panic => {
+                let msg = self.visit_expr(&args[0]);
+                let ret = self.get_reg();
+                let prefix = format!("panicked at {}: ", self.location(span));
+                let len = prefix.len() as u64;
+                let label = self.intern_str(prefix);
+                let fd = self.load_imm(builtin.fd().into());
+                let ptr = self.data_addr(label);
+                let len = self.load_imm(len.into());
+                let args = self.push_args(vec![fd, ptr, len]);
+                self.call(runtime::WRITE.into(), args, ret);
+                let fd = self.load_imm(builtin.fd().into());
+                let mut args = vec![fd];
+                args.extend(self.value_regs(msg));
+                let args = self.push_args(args);
+                self.call(runtime::WRITE.into(), args, ret);
+                self.panic("\n", runtime::EXIT_PANIC);
+                self.set_type(ret, Type::Null)
+            }
+            _ => self.print(builtin, args),
+        }
+    }
+
+    /// Calls the copy of `callee` for the types inferred at this call, asking for it to be
+    /// generated the first time.
+    fn generic_call(&mut self, callee: &ItemFn, expr_call: &ExprCall) -> Reg {
+        let key = (self.current_fn.clone(), expr_call.span());
+        let Some(type_args) = self.calls.generic.get(&key) else {
+            unreachable!("checked in semantic analysis");
+        };
+        let type_args = type_args
+            .iter()
+            .map(|ty| ty.substitute(&self.type_args))
+            .collect::<Vec<_>>();
+        let bindings = callee
+            .generics
+            .iter()
+            .map(|generic| generic.name.value())
+            .zip(type_args.iter().cloned())
+            .collect();
+        let ty = callee
+            .ret_type
+            .as_ref()
+            .map(|ty| self.ir_type(&TypeName::from(ty).substitute(&bindings)))
+            .unwrap_or(Type::Null);
+        let label = instance_name(&callee.name.value, &type_args);
+        if self.instantiated.insert(label.clone()) {
+            self.pending.push((callee.name.value(), type_args));
+        }
+        let ret = self.get_reg();
+        self.set_type(ret, ty);
+        let mut args = self.eval_args(&expr_call.args);
+        self.ret_slot(ret, &mut args);
+        self.call(label.into(), args, ret);
+        self.returned(ret)
+    }
+
+    /// The return type of the function a name stands for when it is used as a value, `None`
+    /// when a variable, const or static by that name is in scope.
+    fn fn_value(&self, name: &Ident) -> Option<Type> {
+        if self.is_local(name) || self.globals.contains_key(&name.value) {
+            return None;
+        }
+        let data = self.symbol_table.get(&Symbol::function(&name.value))?;
+        (data.ty == SymbolType::Function).then(|| self.ir_type(&data.type_name))
+    }
+
+    /// Calls the function whose address `caller` evaluates to, a closure is called with its
+    /// environment before the arguments.
+    fn indirect_call(&mut self, caller: &Expr, args_exprs: &[Expr]) -> Reg {
+        let callee = self.visit_expr(caller);
+        let (callee, env, ty) = match self.type_of(callee) {
+            Type::Fn(ty) => (callee, None, ty),
+            Type::Closure(ty) => {
+                let env = self.get_reg();
+                self.push_to_block(CopyReg {
+                    des: env,
+                    src: callee,
+                });
+                let code = self.read_place(Place::Addr(callee, Type::Fn(ty.clone())));
+                (code, Some(env), ty)
+            }
+            _ => unreachable!("checked in semantic analysis"),
+        };
+        let ret = self.get_reg();
+        self.set_type(ret, *ty);
+        let mut args = self.push_args(env.into_iter().collect());
+        args.extend(self.eval_args(args_exprs));
+        self.ret_slot(ret, &mut args);
+        self.call_indirect(callee, args, ret);
+        self.returned(ret)
+    }
+
+    /// Starts a function and stores its params in their slots, a closure takes the address of
+    /// its environment first.
+    fn enter_fn(&mut self, env: bool, params: &[Param], ret: Type) -> Vec<(Reg, Slot)> {
+        self.gen_label_number = 0;
+        self.stack_size = 0;
+        self.vars = vec![HashMap::new()];
+        self.reset_regester_count();
+        self.env = None;
+        let mut regs = vec![];
+        if env {
+            let ty = Type::Ptr(Box::new(Type::U64));
+            let reg = self.get_reg();
+            self.set_type(reg, ty.clone());
+            let slot = self.alloc_slot(ty);
+            self.env = Some(slot.clone());
+            regs.push((reg, slot));
+        }
+        for param in params.iter() {
+            let reg = self.visit_params(param);
+            regs.push((reg, self.get_var(&param.name)));
+        }
+        self.sret_param(ret, &mut regs);
+
+        self.push_to_block(Enter);
+        for (reg, slot) in regs.iter() {
+            self.write_place(Place::Local(slot.clone()), *reg);
+        }
+        regs
+    }
+
+    fn finish_fn(&mut self, name: String, params: Vec<(Reg, Slot)>) {
+        self.def_label(".exit".into());
+        self.push_to_block(Leave);
+
+        let body = self.block.clone();
+        self.block.clear();
+        let mem_types = self
+            .reg_types
+            .iter()
+            .filter(|(_, ty)| {
+                ty.is_float()
+                    || ty
+                        .int_width()
+                        .is_some_and(|(bits, signed)| signed || bits < 64)
+            })
+            .map(|(reg, ty)| (*reg, ty.clone()))
+            .collect();
+        self.push_fn(DefFunc {
+            name,
+            params: params
+                .into_iter()
+                .flat_map(|(reg, slot)| match self.str_lens.get(&reg) {
+                    Some(len) => vec![(reg, slot.ty), (*len, Type::U64)],
+                    None => vec![(reg, slot.ty)],
+                })
+                .collect(),
+            body,
+            stack_size: self.stack_size,
+            mem_types,
+        });
+    }
+
+    /// Turns an int, `bool` or `char` into another one of them, extended the way `to` is kept in
+    /// a register.
+    fn int_cast(&mut self, src: Reg, to: Type) -> Reg {
+        let (Some((from_bits, from_signed)), Some((to_bits, to_signed))) =
+            (self.type_of(src).int_width(), to.int_width())
+        else {
+            unreachable!("checked in semantic analysis");
+        };
+        // NOTE: `i64` and `u64` or `u32` and `char` only differ in how they are read.
+        if from_bits == to_bits && (from_signed == to_signed || to_bits == 64) {
+            return self.set_type(src, to);
+        }
+        let des = self.get_reg();
+        let instruction: Instruction = match (from_bits < to_bits, from_signed, to_signed) {
+            (true, true, _) => SExt {
+                des,
+                src,
+                bits: from_bits,
+            }
+            .into(),
+            (true, false, _) => ZExt {
+                des,
+                src,
+                bits: from_bits,
+            }
+            .into(),
+            (false, _, true) => SExt {
+                des,
+                src,
+                bits: to_bits,
+            }
+            .into(),
+            (false, _, false) => Trunc {
+                des,
+                src,
+                bits: to_bits,
+            }
+            .into(),
+        };
+        self.push_to_block(instruction);
+        self.set_type(des, to)
+    }
+
+    /// C only sets the low bits of a return value narrower than a register, they are extended
+    /// the way A keeps its type.
+    fn extend_c_ret(&mut self, ret: Reg) -> Reg {
+        let ty = self.type_of(ret);
+        let Some((bits, signed)) = ty.int_width().filter(|(bits, _)| *bits < 64) else {
+            return ret;
+        };
+        let des = self.get_reg();
+        let instruction: Instruction = match signed {
+            true => SExt {
+                des,
+                src: ret,
+                bits,
+            }
+            .into(),
+            false => ZExt {
+                des,
+                src: ret,
+                bits,
+            }
+            .into(),
+        };
+        self.push_to_block(instruction);
+        self.set_type(des, ty)
+    }
+
+    /// Hands `reg` back to the caller and leaves the function.
+    fn return_value(&mut self, mut reg: Reg) -> Reg {
+        // NOTE: a struct or an array is copied to where the caller asked for it and that address
+        // is returned.
+        if let Some(sret) = self.sret.clone() {
+            let ty = self.type_of(reg);
+            let to = self.load_local(sret);
+            let copy = self.get_reg();
+            self.push_to_block(CopyReg { des: copy, src: to });
+            match ty {
+                Type::Str => self.write_place(Place::Addr(copy, ty), reg),
+                _ => self.copy_mem(copy, reg, ty.bytes()),
+            }
+            reg = to;
+        }
+        self.early_return(Some(reg));
+        self.jump(".exit".into());
+        reg
+    }
+
+    /// A variable of the current function, or one the closure being generated took along.
+    fn is_local(&self, name: &Ident) -> bool {
+        self.find_var(name).is_some() || self.captures.contains_key(&name.value)
+    }
+
+    /// Where a captured local is, through the environment of the closure being generated.
+    fn capture(&mut self, name: &Ident) -> Place {
+        let EnvField { offset, ty, by_ref } = self.captures[&name.value].clone();
+        let Some(env) = self.env.clone() else {
+            unreachable!("captures only exist inside of a closure");
+        };
+        let env = self.load_local(env);
+        let offset = self.load_imm((offset as u64).into());
+        let addr = self.elem_ptr(env, offset, Type::U8);
+        match by_ref {
+            true => {
+                let ptr = self.read_place(Place::Addr(addr, Type::Ptr(Box::new(ty.clone()))));
+                Place::Addr(ptr, ty)
+            }
+            false => Place::Addr(addr, ty),
+        }
+    }
+
+    /// The label of a method of `ty` and the type it returns. A method of a generic struct is
+    /// asked for a copy for the type arguments of `ty`.
+    fn method(&mut self, trait_name: Option<&str>, ty: &TypeName, name: &str) -> (Label, Type) {
+        let label = method_name(trait_name, ty, name);
+        let ret = self.methods[&label].clone();
+        let TypeName::Generic(_, args) = ty else {
+            return (label.into(), self.ir_type(&ret));
+        };
+        let bindings = self.generic_fns[&label]
+            .generics
+            .iter()
+            .map(|generic| generic.name.value())
+            .zip(args.iter().cloned())
+            .collect();
+        let instance = instance_name(&label, args);
+        if self.instantiated.insert(instance.clone()) {
+            self.pending.push((label, args.clone()));
+        }
+        (instance.into(), self.ir_type(&ret.substitute(&bindings)))
+    }
+
+    fn print(&mut self, builtin: Builtin, args: &[Expr]) -> Reg {
+        let ret = self.get_reg();
+        for arg in args.iter() {
+            let value = self.visit_expr(arg);
+            // NOTE: a struct prints itself through its impl of the std `Display`.
+            if let Type::Struct(ty, _) = self.type_of(value) {
+                let (label, _) = self.method(Some(stdlib::DISPLAY), &ty, "fmt");
+                let fd = self.load_imm(builtin.fd().into());
+                let args = self.push_args(vec![value, fd]);
+                self.call(label, args, ret);
+                continue;
+            }
+            let routine = match self.type_of(value) {
+                Type::Str => runtime::WRITE,
+                Type::Bool => runtime::PRINT_BOOL,
+                Type::Char => runtime::PRINT_CHAR,
+                Type::I64 | Type::I32 => runtime::PRINT_I64,
+                _ => runtime::PRINT_U64,
+            };
+            let fd = self.load_imm(builtin.fd().into());
+            let mut args = vec![fd];
+            args.extend(self.value_regs(value));
+            let args = self.push_args(args);
+            self.call(routine.into(), args, ret);
+        }
+        if builtin.newline() {
+            let fd = self.load_imm(builtin.fd().into());
+            let newline = self.load_imm(('\n' as u64).into());
+            let args = self.push_args(vec![fd, newline]);
+            self.call(runtime::PRINT_CHAR.into(), args, ret);
+        }
+        self.set_type(ret, Type::Null)
+    }
+
+    /// `file:line:column` of where `span` starts in the function being generated, counting from
+    /// one.
+    fn location(&self, span: Span) -> String {
+        let (line, column) = (span.row_start + 1, span.col_start + 1);
+        match &self.file {
+            Some(file) => format!("{file}:{line}:{column}"),
+            None => format!("{line}:{column}"),
+        }
+    }
+
+    fn intern_str(&mut self, string: String) -> Label {
+        let index = match self.strings.iter().position(|s| *s == string) {
+            Some(index) => index,
+            None => {
+                self.strings.push(string);
+                self.strings.len() - 1
+            }
+        };
+        str_label(index)
+    }
+
+    /// A `let` shadows anything with the same name until the end of its block, the shadowed
+    /// variable keeps its own slot as a reference to it may still be around.
+    fn declare_var(&mut self, name: &Ident, slot: Slot) {
+        if let Some(scope) = self.vars.last_mut() {
+            scope.insert(name.value(), slot);
+        }
+    }
+
+    fn find_var(&self, name: &Ident) -> Option<Slot> {
+        self.vars
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name.value()))
+            .cloned()
+    }
+
+    fn get_var(&self, name: &Ident) -> Slot {
+        let Some(slot) = self.find_var(name) else {
+            panic!("unknown variable '{}'", name.value());
+        };
+        slot
+    }
+
+    /// The value of a `const` that is not shadowed by a variable.
+    fn constant(&self, name: &Ident) -> Option<(Value, Type)> {
+        if self.is_local(name) {
+            return None;
+        }
+        self.globals
+            .get(&name.value)
+            .filter(|global| !global.is_static)
+            .map(|global| (global.value.clone(), self.ir_type(&global.ty)))
+    }
+
+    fn load_constant(&mut self, value: Value, ty: Type) -> Reg {
+        let reg = match value {
+            Value::Int(_) => self.load_imm(value.bits().into()),
+            Value::Float(_) => {
+                let des = self.get_reg();
+                let bits = value.bits();
+                self.push_to_block(LoadFloat { des, bits });
+                des
+            }
+            Value::Str(string) => return self.load_str(string),
+        };
+        self.set_type(reg, ty)
+    }
+
+    fn load_str(&mut self, string: String) -> Reg {
+        let len = string.len() as u64;
+        let label = self.intern_str(string);
+        let ptr = self.data_addr(label);
+        let len = self.load_imm(len.into());
+        self.str_lens.insert(ptr, len);
+        self.set_type(ptr, Type::Str)
+    }
+
+    fn array_init(&mut self, slot: &Slot, expr: &Expr) {
+        let Some((_, len)) = slot.array() else {
+            unreachable!("{slot} is not an array");
+        };
+        match expr {
+            Expr::Array(ExprArray { elems, .. }) => {
+                if elems.len() != len {
+                    self.errors.push(format!(
+                        "{:?} expected an array of {len} elements but found {}",
+                        expr.span(),
+                        elems.len()
+                    ));
+                }
+                for (idx, elem) in elems.iter().enumerate() {
+                    let src = self.visit_expr(elem);
+                    let index = self.load_imm((idx as u64).into());
+                    self.write_place(Place::Elem(slot.clone(), index), src);
+                }
+            }
+            Expr::ArrayRepeat(ExprArrayRepeat { expr, .. }) => {
+                let src = self.visit_expr(expr);
+                let elem = slot.array().map(|(ty, _)| ty.clone()).unwrap_or_default();
+                if !elem.is_aggregate() {
+                    self.fill_array(slot.clone(), src);
+                    return;
+                }
+                // NOTE: the first element is written once and copied into the others.
+                let first = self.load_imm(0.into());
+                self.write_place(Place::Elem(slot.clone(), first), src);
+                for idx in 1..len {
+                    let first = self.load_imm(0.into());
+                    let from = self.place_addr(Place::Elem(slot.clone(), first));
+                    let index = self.load_imm((idx as u64).into());
+                    let to = self.place_addr(Place::Elem(slot.clone(), index));
+                    self.copy_mem(to, from, elem.bytes());
+                }
+            }
+            expr => {
+                let src = self.visit_expr(expr);
+                self.write_place(Place::Local(slot.clone()), src);
+            }
+        }
+    }
+
+    /// A field of the std `Vec` whose address `vec` holds.
+    fn vec_field(&mut self, vec: &Slot, name: &str) -> Place {
+        let ty = vec.ty.pointee().cloned().unwrap_or_default();
+        let Some((offset, ty)) = self.resolve(ty).field(name) else {
+            unreachable!("the std Vec has a '{name}'");
+        };
+        let addr = self.read_place(Place::Local(vec.clone()));
+        let offset = self.load_imm((offset as u64).into());
+        let addr = self.elem_ptr(addr, offset, Type::U8);
+        Place::Addr(addr, ty)
+    }
+
+    /// The `Vec` that a value of type `ty` is or points at.
+    fn vec_type(&self, ty: &Type) -> Option<TypeName> {
+        match ty.pointee().unwrap_or(ty) {
+            Type::Struct(ty @ TypeName::Generic(name, _), _) if name == stdlib::VEC => {
+                Some(ty.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// `v[i]` is the item the std `Vec::index` hands back the address of, it panics when the
+    /// index is past the end.
+    fn vec_index(&mut self, base: Place, ty: TypeName, index: &Expr) -> Place {
+        let this = match base.ty().pointee() {
+            Some(_) => self.read_place(base),
+            None => self.place_addr(base),
+        };
+        let (label, ret_ty) = self.method(None, &ty, stdlib::VEC_INDEX);
+        let mut args = self.push_args(vec![this]);
+        args.extend(self.eval_args(std::slice::from_ref(index)));
+        let ret = self.get_reg();
+        self.set_type(ret, ret_ty.clone());
+        self.call(label, args, ret);
+        let addr = self.returned(ret);
+        Place::Addr(addr, ret_ty.pointee().cloned().unwrap_or_default())
+    }
+
+    /// Resolves an expression that names a memory location.
+    fn place(&mut self, expr: &Expr) -> Place {
+        match expr {
+            Expr::Var(ExprVar { name, .. }) => match self.find_var(name) {
+                Some(slot) => Place::Local(slot),
+                None if self.captures.contains_key(&name.value) => self.capture(name),
+                None => {
+                    let Some(global) = self.globals.get(&name.value) else {
+                        panic!("unknown variable '{}'", name.value);
+                    };
+                    Place::Static(name.into(), Type::from(&global.ty))
+                }
+            },
+            Expr::Unary(expr_unary) if expr_unary.is_deref() => {
+                let addr = self.visit_expr(&expr_unary.expr);
+                let Some(ty) = self.type_of(addr).pointee().cloned() else {
+                    panic!(
+                        "{:?} dereferenced a value that is not a pointer",
+                        expr.span()
+                    );
+                };
+                Place::Addr(addr, ty)
+            }
+            Expr::Field(ExprField {
+                expr: base, name, ..
+            }) => {
+                // NOTE: a field is reached through a pointer to its struct as well.
+                let base = match Self::is_place(base) {
+                    true => match self.place(base) {
+                        base if base.ty().pointee().is_some() => {
+                            let ty = base.ty().pointee().cloned().unwrap_or_default();
+                            Place::Addr(self.read_place(base), ty)
+                        }
+                        base => base,
+                    },
+                    false => {
+                        let value = self.visit_expr(base);
+                        let ty = self.type_of(value);
+                        Place::Addr(value, ty.pointee().cloned().unwrap_or(ty))
+                    }
+                };
+                let Some((offset, ty)) = self.resolve(base.ty()).field(&name.value) else {
+                    unreachable!("checked in semantic analysis");
+                };
+                match base {
+                    Place::Local(slot) => Place::Local(slot.field(offset, ty)),
+                    base => {
+                        let addr = self.place_addr(base);
+                        let offset = self.load_imm((offset as u64).into());
+                        let addr = self.elem_ptr(addr, offset, Type::U8);
+                        Place::Addr(addr, ty)
+                    }
+                }
+            }
+            Expr::Index(ExprIndex { expr, index, .. }) => {
+                // NOTE: an array that is not a place is indexed where its value was written.
+                let mut base = match Self::is_place(expr) {
+                    true => self.place(expr),
+                    false => {
+                        let value = self.visit_expr(expr);
+                        Place::Addr(value, self.type_of(value))
+                    }
+                };
+                if let Some(ty) = self.vec_type(&base.ty()) {
+                    return self.vec_index(base, ty, index);
+                }
+                // NOTE: indexing through a pointer to an array derefs it for you.
+                if let Some(ty) = base.ty().pointee().cloned() {
+                    base = Place::Addr(self.read_place(base), ty);
+                }
+                let Type::Array(elem, len) = base.ty() else {
+                    panic!("{:?} is not an array", expr.span());
+                };
+                let elem = *elem;
+                let index = self.visit_expr(index);
+                self.bounds_check(index, len);
+                match base {
+                    Place::Local(slot) => Place::Elem(slot, index),
+                    base => {
+                        let addr = self.place_addr(base);
+                        let addr = self.elem_ptr(addr, index, elem.clone());
+                        Place::Addr(addr, elem)
+                    }
+                }
+            }
+            _ => panic!("{:?} is not a place in memory", expr.span()),
+        }
+    }
+
+    fn read_place(&mut self, place: Place) -> Reg {
+        // NOTE: only a scalar is loaded by its index, anything bigger is read at its address.
+        if let Place::Elem(..) = place {
+            if place.ty().is_aggregate() {
+                let ty = place.ty();
+                let addr = self.place_addr(place);
+                return self.read_place(Place::Addr(addr, ty));
+            }
+        }
+        // NOTE: a struct or an array is handed around by its address, it is copied when it is
+        // written.
+        if place.ty().is_struct() || place.ty().is_array() {
+            let ty = place.ty();
+            let addr = self.place_addr(place);
+            return self.set_type(addr, ty);
+        }
+        match place {
+            Place::Local(slot) if slot.ty == Type::Str => {
+                let ptr = self.load_local(slot.field(0, Type::U64));
+                let len = self.load_local(slot.field(8, Type::U64));
+                self.str_lens.insert(ptr, len);
+                self.set_type(ptr, Type::Str)
+            }
+            Place::Addr(addr, Type::Str) => {
+                // NOTE: loading consumes the address, the len is read through a copy of it.
+                let copy = self.get_reg();
+                self.push_to_block(CopyReg {
+                    des: copy,
+                    src: addr,
+                });
+                let ptr = self.load(addr, Type::U64);
+                let offset = self.load_imm(8.into());
+                let len_addr = self.elem_ptr(copy, offset, Type::U8);
+                let len = self.load(len_addr, Type::U64);
+                self.str_lens.insert(ptr, len);
+                self.set_type(ptr, Type::Str)
+            }
+            Place::Static(label, Type::Str) => {
+                let addr = self.data_addr(label);
+                self.read_place(Place::Addr(addr, Type::Str))
+            }
+            Place::Local(slot) => self.load_local(slot),
+            Place::Elem(slot, index) => self.load_elem(slot, index),
+            Place::Addr(addr, ty) => self.load(addr, ty),
+            Place::Static(label, ty) => self.load_static(label, ty),
+        }
+    }
+
+    fn write_place(&mut self, place: Place, src: Reg) {
+        if let Place::Elem(..) = place {
+            if place.ty().is_aggregate() {
+                let ty = place.ty();
+                let addr = self.place_addr(place);
+                return self.write_place(Place::Addr(addr, ty), src);
+            }
+        }
+        if place.ty().is_struct() || place.ty().is_array() {
+            let bytes = place.ty().bytes();
+            let to = self.place_addr(place);
+            self.copy_mem(to, src, bytes);
+            return;
+        }
+        match place {
+            Place::Local(slot) if slot.ty == Type::Str => {
+                let len = self.str_lens[&src];
+                self.store_local(slot.field(0, Type::U64), src);
+                self.store_local(slot.field(8, Type::U64), len);
+            }
+            Place::Addr(addr, Type::Str) => {
+                let len = self.str_lens[&src];
+                let copy = self.get_reg();
+                self.push_to_block(CopyReg {
+                    des: copy,
+                    src: addr,
+                });
+                self.store(addr, src);
+                let offset = self.load_imm(8.into());
+                let len_addr = self.elem_ptr(copy, offset, Type::U8);
+                self.store(len_addr, len);
+            }
+            Place::Static(label, Type::Str) => {
+                let addr = self.data_addr(label);
+                self.write_place(Place::Addr(addr, Type::Str), src)
+            }
+            Place::Local(slot) => self.store_local(slot, src),
+            Place::Elem(slot, index) => self.store_elem(slot, index, src),
+            Place::Addr(addr, _) => self.store(addr, src),
+            Place::Static(label, ty) => self.store_static(label, ty, src),
+        }
+    }
+
+    /// Whether the expression names a memory location rather than a temporary value.
+    fn is_place(expr: &Expr) -> bool {
+        match expr {
+            Expr::Var(_) | Expr::Index(_) | Expr::Field(_) => true,
+            Expr::Unary(expr_unary) => expr_unary.is_deref(),
+            _ => false,
+        }
+    }
+
+    fn place_addr(&mut self, place: Place) -> Reg {
+        match place {
+            Place::Local(slot) => self.addr_of(slot),
+            Place::Elem(slot, index) => {
+                let Some((elem, _)) = slot.array() else {
+                    unreachable!("{slot} is not an array");
+                };
+                let elem = elem.clone();
+                let base = self.addr_of(slot);
+                self.elem_ptr(base, index, elem)
+            }
+            Place::Addr(addr, _) => addr,
+            Place::Static(label, ty) => {
+                let addr = self.data_addr(label);
+                self.set_type(addr, Type::Ptr(Box::new(ty)))
+            }
+        }
     }
 
     fn gen_label(&mut self) -> Label {
@@ -246,7 +1449,12 @@ impl Ir for IrGenerator {
         let des = self.get_reg();
         let load = LoadImm { des, imm };
         self.push_to_block(load);
-        des
+        self.set_type(des, Type::U64)
+    }
+
+    fn null(&mut self) -> Reg {
+        let des = self.get_reg();
+        self.set_type(des, Type::Null)
     }
 
     fn binary(&mut self, op: &Op, lhs: Reg, rhs: Reg) -> Reg {
@@ -257,10 +1465,19 @@ impl Ir for IrGenerator {
             Op::Mul(_) => Mul { des, lhs, rhs }.into(),
             Op::Div(_) => Div { des, lhs, rhs }.into(),
             Op::Grt(_) => Grt { des, lhs, rhs }.into(),
+            Op::Les(_) => Les { des, lhs, rhs }.into(),
+            Op::Geq(_) => Geq { des, lhs, rhs }.into(),
+            Op::Leq(_) => Leq { des, lhs, rhs }.into(),
+            Op::EqualEqual(_) => Equal { des, lhs, rhs }.into(),
+            Op::Neq(_) => Neq { des, lhs, rhs }.into(),
             _ => unimplemented!("{op:?}"),
         };
         self.push_to_block(instruction);
-        des
+        let ty = match op.is_comparison() {
+            true => Type::Bool,
+            false => self.type_of(lhs),
+        };
+        self.set_type(des, ty)
     }
 
     fn conditional(&mut self, label: Label, reg: Reg) -> Reg {
@@ -270,6 +1487,21 @@ impl Ir for IrGenerator {
     }
 
     fn call(&mut self, caller: Label, args: Vec<Reg>, ret: Reg) -> Reg {
+        let caller = Callee::Label(caller);
+        let instruction: Instruction = Call { caller, args, ret }.into();
+        self.push_to_block(instruction);
+        ret
+    }
+
+    fn call_indirect(&mut self, callee: Reg, args: Vec<Reg>, ret: Reg) -> Reg {
+        let caller = Callee::Reg(callee);
+        let instruction: Instruction = Call { caller, args, ret }.into();
+        self.push_to_block(instruction);
+        ret
+    }
+
+    fn call_extern(&mut self, label: Label, args: Vec<Reg>, ret: Reg) -> Reg {
+        let caller = Callee::Extern(label);
         let instruction: Instruction = Call { caller, args, ret }.into();
         self.push_to_block(instruction);
         ret
@@ -279,33 +1511,276 @@ impl Ir for IrGenerator {
         let instruction: Instruction = Return(reg).into();
         self.push_to_block(instruction);
     }
+
+    fn copy_mem(&mut self, to: Reg, from: Reg, bytes: usize) {
+        self.push_to_block(CopyMem { to, from, bytes });
+    }
+
+    fn load_local(&mut self, slot: Slot) -> Reg {
+        let des = self.get_reg();
+        let ty = slot.ty.clone();
+        self.push_to_block(LoadLocal { des, slot });
+        self.set_type(des, ty)
+    }
+
+    fn store_local(&mut self, slot: Slot, src: Reg) {
+        self.push_to_block(StoreLocal { slot, src });
+    }
+
+    fn load_elem(&mut self, slot: Slot, index: Reg) -> Reg {
+        let des = self.get_reg();
+        let ty = slot.array().map(|(ty, _)| ty.clone()).unwrap_or_default();
+        self.push_to_block(LoadElem { des, slot, index });
+        self.set_type(des, ty)
+    }
+
+    fn store_elem(&mut self, slot: Slot, index: Reg, src: Reg) {
+        self.push_to_block(StoreElem { slot, index, src });
+    }
+
+    fn fill_array(&mut self, slot: Slot, src: Reg) {
+        self.push_to_block(FillArray { slot, src });
+    }
+
+    fn bounds_check(&mut self, index: Reg, len: usize) {
+        self.push_to_block(BoundsCheck { index, len });
+    }
+
+    fn overflow_check(&mut self, reg: Reg, bits: usize, signed: bool, msg: &str) {
+        let len = msg.len();
+        let msg = self.intern_str(msg.into());
+        self.push_to_block(OverflowCheck {
+            reg,
+            bits,
+            signed,
+            msg,
+            len,
+        });
+    }
+
+    fn zero_check(&mut self, reg: Reg, msg: &str) {
+        let len = msg.len();
+        let msg = self.intern_str(msg.into());
+        self.push_to_block(ZeroCheck { reg, msg, len });
+    }
+
+    fn div_overflow_check(&mut self, lhs: Reg, rhs: Reg, bits: usize, msg: &str) {
+        let len = msg.len();
+        let msg = self.intern_str(msg.into());
+        self.push_to_block(DivOverflowCheck {
+            lhs,
+            rhs,
+            bits,
+            msg,
+            len,
+        });
+    }
+
+    fn panic(&mut self, msg: &str, code: u64) {
+        let len = msg.len();
+        let msg = self.intern_str(msg.into());
+        self.push_to_block(Panic { code, msg, len });
+    }
+
+    fn addr_of(&mut self, slot: Slot) -> Reg {
+        let des = self.get_reg();
+        let ty = Type::Ptr(Box::new(slot.ty.clone()));
+        self.push_to_block(AddrOf { des, slot });
+        self.set_type(des, ty)
+    }
+
+    fn load(&mut self, addr: Reg, ty: Type) -> Reg {
+        let des = self.get_reg();
+        self.push_to_block(Load { des, addr });
+        self.set_type(des, ty)
+    }
+
+    fn store(&mut self, addr: Reg, src: Reg) {
+        self.push_to_block(Store { addr, src });
+    }
+
+    fn elem_ptr(&mut self, base: Reg, index: Reg, ty: Type) -> Reg {
+        // NOTE: an address can only scale the index by 1, 2, 4 or 8, anything else is multiplied
+        // out first.
+        let (index, scale) = match ty.bytes() {
+            1 | 2 | 4 | 8 => (index, ty.clone()),
+            bytes => {
+                let size = self.load_imm((bytes as u64).into());
+                let offset = self.get_reg();
+                self.push_to_block(Mul {
+                    des: offset,
+                    lhs: index,
+                    rhs: size,
+                });
+                self.set_type(offset, Type::U64);
+                (offset, Type::U8)
+            }
+        };
+        let des = self.get_reg();
+        self.push_to_block(ElemPtr {
+            des,
+            base,
+            index,
+            ty: scale,
+        });
+        self.set_type(des, Type::Ptr(Box::new(ty)))
+    }
+
+    fn data_addr(&mut self, label: Label) -> Reg {
+        let des = self.get_reg();
+        self.push_to_block(DataAddr { des, label });
+        des
+    }
+
+    fn discard(&mut self, reg: Reg) {
+        for reg in self.value_regs(reg) {
+            self.push_to_block(Discard(reg));
+        }
+    }
+
+    fn push(&mut self, reg: Reg) {
+        self.push_to_block(Push(reg));
+    }
+
+    fn syscall(&mut self, argc: usize) -> Reg {
+        let des = self.get_reg();
+        self.push_to_block(Syscall { des, argc });
+        self.set_type(des, Type::U64)
+    }
+
+    fn load_static(&mut self, label: Label, ty: Type) -> Reg {
+        let des = self.get_reg();
+        self.push_to_block(LoadStatic {
+            des,
+            label,
+            ty: ty.clone(),
+        });
+        self.set_type(des, ty)
+    }
+
+    fn store_static(&mut self, label: Label, ty: Type, src: Reg) {
+        self.push_to_block(StoreStatic { label, ty, src });
+    }
 }
 
 impl AstVisitor for IrGenerator {
     fn visit_expr_var(&mut self, expr_var: &ExprVar) -> Reg {
-        let ExprVar { name, .. } = expr_var;
-        *self.vars.get(&name.value()).unwrap()
+        if let Some((value, ty)) = self.constant(&expr_var.name) {
+            return self.load_constant(value, ty);
+        }
+        if let Some(ret) = self.fn_value(&expr_var.name) {
+            let addr = self.data_addr((&expr_var.name).into());
+            return self.set_type(addr, Type::Fn(Box::new(ret)));
+        }
+        let place = self.place(&Expr::Var(expr_var.clone()));
+        self.read_place(place)
     }
 
     fn visit_params(&mut self, params: &Param) -> Reg {
-        let Param { name, .. } = params;
+        let Param { name, kind, .. } = params;
+        let ty = self.ast_type(kind);
         let des = self.get_reg();
-        self.vars.insert(name.value(), des);
-        des
+        if ty == Type::Str {
+            let len = self.get_reg();
+            self.str_lens.insert(des, len);
+        }
+        let slot = self.alloc_slot(ty.clone());
+        self.declare_var(name, slot);
+        self.set_type(des, ty)
     }
 
     fn visit_expr_call(&mut self, expr_call: &ExprCall) -> Reg {
         let ExprCall { caller, args, .. } = expr_call;
-        let Expr::Var(ExprVar { name, .. }) = &**caller else {
-            panic!("expected Ident");
+        let Expr::Var(ExprVar { path, name }) = &**caller else {
+            return self.indirect_call(caller, args);
         };
+        if path.is_empty() && self.is_local(name) {
+            return self.indirect_call(caller, args);
+        }
+        if let [ty] = path.as_slice() {
+            let mut ty = TypeName::from(ty.value.as_str()).substitute(&self.type_args);
+            // NOTE: `Vec::new()` has the type arguments semantic analysis inferred for it.
+            let key = (self.current_fn.clone(), expr_call.span());
+            if let (TypeName::Custom(name), Some(type_args)) = (&ty, self.calls.generic.get(&key)) {
+                let type_args = type_args.iter().map(|ty| ty.substitute(&self.type_args));
+                ty = TypeName::Generic(name.clone(), type_args.collect());
+            }
+            let (label, ret_ty) = self.method(None, &ty, &name.value);
+            let ret = self.get_reg();
+            self.set_type(ret, ret_ty);
+            let mut args = self.eval_args(args);
+            self.ret_slot(ret, &mut args);
+            self.call(label, args, ret);
+            return self.returned(ret);
+        }
+        if let Some(builtin) = Builtin::from_name(&name.value()) {
+            return self.builtin_call(builtin, args, expr_call.span());
+        }
+        if let Some(callee) = self.generic_fns.get(&name.value).cloned() {
+            return self.generic_call(&callee, expr_call);
+        }
         // FIXME: this reg needs to be stored with var in discriper?
         let ret = self.get_reg();
-        let args = args
-            .iter()
-            .map(|expr| self.visit_expr(expr))
-            .collect::<Vec<Reg>>();
-        self.call(name.into(), args, ret)
+        let ty = self
+            .symbol_table
+            .get(&Symbol::function(&name.value))
+            .map(|data| self.ir_type(&data.type_name))
+            .unwrap_or(Type::U64);
+        self.set_type(ret, ty);
+        let mut args = self.eval_args(args);
+        if self.externs.contains(&name.value) {
+            self.call_extern(name.into(), args, ret);
+            return self.extend_c_ret(ret);
+        }
+        self.ret_slot(ret, &mut args);
+        self.call(name.into(), args, ret);
+        self.returned(ret)
+    }
+
+    fn visit_expr_method_call(&mut self, expr_method_call: &ExprMethodCall) -> Reg {
+        let ExprMethodCall {
+            receiver,
+            name,
+            args,
+            ..
+        } = expr_method_call;
+        let key = (self.current_fn.clone(), expr_method_call.span());
+        let Some(Method {
+            trait_name,
+            ty,
+            receiver: how,
+        }) = self.calls.method.get(&key).cloned()
+        else {
+            unreachable!("checked in semantic analysis");
+        };
+        let ty = ty.substitute(&self.type_args);
+        let (label, ret_ty) = self.method(trait_name.as_deref(), &ty, &name.value);
+        let this = match how {
+            Receiver::Value => self.visit_expr(receiver),
+            // NOTE: a temporary is written to a slot of its own to be borrowed, like `let` does.
+            Receiver::Borrow if !Self::is_place(receiver) => {
+                let value = self.visit_expr(receiver);
+                let slot = self.alloc_slot(self.type_of(value));
+                self.write_place(Place::Local(slot.clone()), value);
+                self.addr_of(slot)
+            }
+            Receiver::Borrow => {
+                let place = self.place(receiver);
+                self.place_addr(place)
+            }
+            Receiver::Deref => {
+                let addr = self.visit_expr(receiver);
+                self.read_place(Place::Addr(addr, self.ir_type(&ty)))
+            }
+        };
+        let this = self.value_regs(this);
+        let mut regs = self.push_args(this);
+        let ret = self.get_reg();
+        self.set_type(ret, ret_ty);
+        regs.extend(self.eval_args(args));
+        self.ret_slot(ret, &mut regs);
+        self.call(label, regs, ret);
+        self.returned(ret)
     }
 
     fn visit_expr_binary(&mut self, bin: &ExprBinary) -> Reg {
@@ -314,7 +1789,37 @@ impl AstVisitor for IrGenerator {
         } = bin;
         let lhs = self.visit_expr(left);
         let rhs = self.visit_expr(right);
-        self.binary(op, lhs, rhs)
+        if let (Op::Div(_), Some(_)) = (op, self.type_of(rhs).int_width()) {
+            let msg = format!(
+                "panicked at {}: attempt to divide by zero\n",
+                self.location(bin.span())
+            );
+            self.zero_check(rhs, &msg);
+        }
+        // NOTE: unlike the other overflows this one is always checked, the cpu faults on it.
+        if let (Op::Div(_), Some((bits, true))) = (op, self.type_of(rhs).int_width()) {
+            let msg = format!(
+                "panicked at {}: attempt to divide with overflow\n",
+                self.location(bin.span())
+            );
+            self.div_overflow_check(lhs, rhs, bits, &msg);
+        }
+        let des = self.binary(op, lhs, rhs);
+        let verb = match op {
+            Op::Add(_) => "add",
+            Op::Sub(_) => "subtract",
+            Op::Mul(_) => "multiply",
+            _ => return des,
+        };
+        if let (true, Some((bits, signed))) = (self.overflow_checks, self.type_of(des).int_width())
+        {
+            let msg = format!(
+                "panicked at {}: attempt to {verb} with overflow\n",
+                self.location(bin.span())
+            );
+            self.overflow_check(des, bits, signed, &msg);
+        }
+        des
     }
 
     fn visit_item_fn(&mut self, item_fn: &ItemFn) {
@@ -322,58 +1827,303 @@ impl AstVisitor for IrGenerator {
             name,
             params,
             block,
-            ret_type: _,
+            ret_type,
+            file,
             ..
         } = item_fn;
 
-        self.gen_label_number = 0;
-        self.reset_regester_count();
-        let params = params
-            .iter()
-            .map(|p| (self.visit_params(p), Type::U64))
-            .collect();
+        self.current_fn = name.value();
+        self.file = file.clone();
+        self.captures.clear();
+        let ret = ret_type
+            .as_ref()
+            .map(|ty| self.ast_type(ty))
+            .unwrap_or_default();
+        let params = self.enter_fn(false, params, ret);
+        let reg = self.visit_expr_block(block);
+        self.discard(reg);
+        let name = self.instance_name.take().unwrap_or(name.value());
+        if item_fn.is_export() {
+            // NOTE: C knows it by the name it is written with, not the path of its module.
+            let symbol = name.rsplit("::").next().unwrap_or(&name).to_string();
+            let params = params.iter().map(|(_, slot)| slot.ty.clone()).collect();
+            self.push_fn(Export {
+                symbol: Label(symbol),
+                func: Label(name.clone()),
+                params,
+            });
+        }
+        self.finish_fn(name, params);
+    }
 
-        self.push_to_block(Enter);
-        let _reg = self.visit_expr_block(block);
-        self.def_label(".exit".into());
-        self.push_to_block(Leave);
+    fn visit_item_impl(&mut self, item_impl: &ItemImpl) {
+        let ItemImpl { ty, fns, .. } = item_impl;
+        // NOTE: the methods of a generic struct get a copy for every type they are called with.
+        if !item_impl.generics.is_empty() {
+            return;
+        }
+        let ty = TypeName::from(ty);
+        self.type_args = HashMap::from([("Self".into(), ty.clone())]);
+        for item_fn in fns.iter() {
+            let mut item_fn = item_fn.clone();
+            item_fn.name.value = method_name(item_impl.trait_name(), &ty, &item_fn.name.value);
+            self.visit_item_fn(&item_fn);
+        }
+        self.type_args.clear();
+    }
 
-        let body = self.block.clone();
-        self.block.clear();
-        self.push_fn(DefFunc {
-            name: name.value(),
-            params,
-            body,
-        });
+    fn visit_item_static(&mut self, item_static: &ItemStatic) {
+        let ItemStatic { name, .. } = item_static;
+        let Some(global) = self.globals.get(&name.value) else {
+            unreachable!("checked in semantic analysis");
+        };
+        let ty = self.ir_type(&global.ty);
+        let value = match &global.value {
+            Value::Str(string) => {
+                let len = string.len() as u64;
+                StaticValue::Str(self.intern_str(string.clone()), len)
+            }
+            Value::Float(value) if ty == Type::F32 => {
+                StaticValue::Bits((*value as f32).to_bits().into())
+            }
+            value => StaticValue::Bits(value.bits()),
+        };
+        let def = DefStatic {
+            label: name.into(),
+            ty,
+            value,
+        };
+        self.push_fn(def);
+    }
+
+    fn visit_item_extern(&mut self, item_extern: &ItemExtern) {
+        self.push_fn(Extern((&item_extern.name).into()));
     }
 
     fn visit_lit_int(&mut self, lit_int: &LitInt) -> Reg {
         let imm: Imm = lit_int.parse::<u64>().unwrap().into();
-        self.load_imm(imm)
+        let reg = self.load_imm(imm);
+        let ty = self.lit_type(lit_int.span).unwrap_or(Type::U64);
+        self.set_type(reg, ty)
+    }
+
+    fn visit_lit_float(&mut self, lit_float: &LitFloat) -> Reg {
+        let value = lit_float.value.replace('_', "").parse::<f64>().unwrap();
+        let des = self.get_reg();
+        let bits = value.to_bits();
+        self.push_to_block(LoadFloat { des, bits });
+        let ty = self.lit_type(lit_float.span).unwrap_or(Type::F64);
+        self.set_type(des, ty)
     }
 
     fn visit_lit_bool(&mut self, lit_bool: &LitBool) -> Reg {
         let num: bool = lit_bool.parse::<bool>().unwrap();
         let imm: Imm = (num as u64).into();
-        self.load_imm(imm)
+        let reg = self.load_imm(imm);
+        self.set_type(reg, Type::Bool)
+    }
+
+    fn visit_lit_str(&mut self, lit_str: &LitStr) -> Reg {
+        self.load_str(lit_str.unescape())
+    }
+
+    fn visit_lit_char(&mut self, lit_char: &LitChar) -> Reg {
+        let Some(c) = lit_char.to_char() else {
+            unreachable!("checked in semantic analysis");
+        };
+        let reg = self.load_imm((c as u32 as u64).into());
+        self.set_type(reg, Type::Char)
     }
 
     fn visit_expr_let(&mut self, expr_let: &ExprLet) -> Reg {
-        let ExprLet { expr, .. } = expr_let;
-        // let Some(Symbol::Variable(var)) = self._symbol_table.get(&name.value()) else {
-        //     panic!("expected symbol");
-        // };
-        // FIXME: ssa variables are just regesters.
-        // I guess we need to store the variable name
-        // with the register its origanly bound to?
-        //
-        // let Variable {
-        //     scope,
-        //     name,
-        //     ty,
-        //     span,
-        // } = var;
-        self.visit_expr(expr)
+        let ExprLet { name, ty, expr, .. } = expr_let;
+        let ty = ty
+            .as_ref()
+            .map(|ty| self.ast_type(ty))
+            .or_else(|| self.lit_type(expr.span()).filter(Type::is_array));
+        let (slot, reg) = match ty {
+            Some(ty) if ty.is_array() => {
+                let slot = self.alloc_slot(ty);
+                self.array_init(&slot, expr);
+                (slot, self.get_reg())
+            }
+            ty => {
+                let reg = self.visit_expr(expr);
+                let ty = ty.unwrap_or_else(|| self.type_of(reg));
+                let slot = self.alloc_slot(ty);
+                self.write_place(Place::Local(slot.clone()), reg);
+                (slot, reg)
+            }
+        };
+        // NOTE: inserted after the initializer so `let x = x + 1;` reads the old `x`.
+        self.declare_var(name, slot);
+        reg
+    }
+
+    /// An array literal or a repeated one is written to a slot of its own, like a struct its value is the address.
+    fn visit_expr_array(&mut self, expr: &Expr) -> Reg {
+        let Some(ty) = self.lit_type(expr.span()) else {
+            unreachable!("checked in semantic analysis");
+        };
+        let slot = self.alloc_slot(ty.clone());
+        self.array_init(&slot, expr);
+        let addr = self.addr_of(slot);
+        self.set_type(addr, ty)
+    }
+
+    fn visit_expr_struct(&mut self, expr_struct: &ExprStruct) -> Reg {
+        let ExprStruct { name, fields, .. } = expr_struct;
+        // NOTE: a generic struct has the type semantic analysis worked out for it.
+        let key = (self.current_fn.clone(), expr_struct.span());
+        let ty = match self.calls.lits.get(&key) {
+            Some(ty) => ty.substitute(&self.type_args),
+            None => TypeName::Custom(name.value()),
+        };
+        let ty = self.ir_type(&ty);
+        let slot = self.alloc_slot(ty.clone());
+        for (field, expr) in fields.iter() {
+            let Some((offset, field_ty)) = ty.field(&field.value) else {
+                unreachable!("checked in semantic analysis");
+            };
+            let src = self.visit_expr(expr);
+            self.write_place(Place::Local(slot.field(offset, field_ty)), src);
+        }
+        // NOTE: the value of a struct is its address, whoever keeps it copies it.
+        let addr = self.addr_of(slot);
+        self.set_type(addr, ty)
+    }
+
+    fn visit_expr_field(&mut self, expr_field: &ExprField) -> Reg {
+        let place = self.place(&Expr::Field(expr_field.clone()));
+        self.read_place(place)
+    }
+
+    fn visit_expr_return(&mut self, expr_ret: &ExprReturn) -> Reg {
+        let ExprReturn { expr, .. } = expr_ret;
+        let reg = self.visit_expr(expr);
+        self.return_value(reg)
+    }
+
+    fn visit_expr_closure(&mut self, expr_closure: &ExprClosure) -> Reg {
+        let key = (self.current_fn.clone(), expr_closure.span());
+        let Some(closure) = self.calls.closures.get(&key).cloned() else {
+            unreachable!("checked in semantic analysis");
+        };
+        let label = format!("{}$closure{}", self.current_fn, self.closure_count);
+        self.closure_count += 1;
+        let ret = self.ir_type(&closure.ret.substitute(&self.type_args));
+        // NOTE: the environment lives in the frame of the function making the closure, semantic
+        // analysis keeps the closure from outliving it.
+        let mut fields = vec![("$fn".to_string(), Type::Fn(Box::new(ret.clone())))];
+        for Capture { name, ty, by_ref } in closure.captures.iter() {
+            let ty = self.ir_type(&ty.substitute(&self.type_args));
+            let ty = match by_ref {
+                true => Type::Ptr(Box::new(ty)),
+                false => ty,
+            };
+            fields.push((name.clone(), ty));
+        }
+        let env_ty = Type::Struct(TypeName::Custom(format!("{label}$env")), fields);
+        let slot = self.alloc_slot(env_ty.clone());
+        let code = self.data_addr(label.as_str().into());
+        let code_slot = slot.field(0, Type::Fn(Box::new(ret.clone())));
+        self.write_place(Place::Local(code_slot), code);
+        let mut captures = HashMap::new();
+        for Capture { name, by_ref, .. } in closure.captures.iter() {
+            let Some((offset, field_ty)) = env_ty.field(name) else {
+                unreachable!("every capture has a field");
+            };
+            let place = self.place(&Expr::Var(ExprVar::new(Ident::new(
+                name.clone(),
+                expr_closure.span(),
+            ))));
+            let ty = place.ty();
+            let value = match by_ref {
+                true => self.place_addr(place),
+                false => self.read_place(place),
+            };
+            self.write_place(Place::Local(slot.field(offset, field_ty)), value);
+            let by_ref = *by_ref;
+            captures.insert(name.clone(), EnvField { offset, ty, by_ref });
+        }
+        self.closures.push(Lifted {
+            label,
+            expr: expr_closure.clone(),
+            ret: ret.clone(),
+            captures,
+            current_fn: self.current_fn.clone(),
+            type_args: self.type_args.clone(),
+            file: self.file.clone(),
+        });
+        let addr = self.addr_of(slot);
+        self.set_type(addr, Type::Closure(Box::new(ret)))
+    }
+
+    fn visit_expr_cast(&mut self, expr_cast: &ExprCast) -> Reg {
+        let ExprCast { expr, ty, .. } = expr_cast;
+        let src = self.visit_expr(expr);
+        let ty = self.ast_type(ty);
+        let from = self.type_of(src);
+        if from == ty {
+            return src;
+        }
+        // NOTE: only a `str` carries its length, whatever it is cast to drops it.
+        if let Some(len) = self.str_lens.remove(&src) {
+            self.push_to_block(Discard(len));
+        }
+        // NOTE: a pointer already is the `u64` address it points at.
+        if matches!(from, Type::Ptr(_) | Type::Str) || matches!(ty, Type::Ptr(_)) {
+            return self.set_type(src, ty);
+        }
+        if !from.is_float() && !ty.is_float() {
+            return self.int_cast(src, ty);
+        }
+        // NOTE: a float becomes a whole register int first, a narrower one is cut from that.
+        let to = match ty.int_width() {
+            Some((bits, _)) if bits < 64 => Type::I64,
+            _ => ty.clone(),
+        };
+        let des = self.get_reg();
+        self.push_to_block(Convert {
+            des,
+            src,
+            ty: to.clone(),
+        });
+        self.set_type(des, to.clone());
+        match to == ty {
+            true => des,
+            false => self.int_cast(des, ty),
+        }
+    }
+
+    fn enter_scope(&mut self) {
+        self.vars.push(HashMap::new());
+    }
+
+    fn leave_scope(&mut self) {
+        self.vars.pop();
+    }
+
+    fn visit_expr_index(&mut self, expr_index: &ExprIndex) -> Reg {
+        let place = self.place(&Expr::Index(expr_index.clone()));
+        self.read_place(place)
+    }
+
+    fn visit_expr_assign(&mut self, expr_assign: &ExprAssign) -> Reg {
+        let ExprAssign { target, value, .. } = expr_assign;
+        let place = self.place(target);
+        let src = self.visit_expr(value);
+        self.write_place(place, src);
+        src
+    }
+
+    fn visit_expr_unary(&mut self, expr_unary: &ExprUnary) -> Reg {
+        if expr_unary.is_ref() {
+            let place = self.place(&expr_unary.expr);
+            return self.place_addr(place);
+        }
+        let place = self.place(&Expr::Unary(expr_unary.clone()));
+        self.read_place(place)
     }
 
     fn visit_expr_if(&mut self, expr_if: &ExprIf) -> Reg {
@@ -386,11 +2136,102 @@ impl AstVisitor for IrGenerator {
         let cond_reg = self.visit_expr(cond);
         let label = self.gen_label();
         let des = self.conditional(label.clone(), cond_reg);
-        self.visit_expr_block(then_branch);
+        let then_reg = self.visit_expr_block(then_branch);
+        self.discard(then_reg);
+        let Some((_, else_branch)) = else_branch else {
+            self.def_label(label);
+            return des;
+        };
+        // NOTE: the then branch has to jump over the else branch when it does not return.
+        let end = self.gen_label();
+        self.jump(end.clone());
         self.def_label(label);
-        if let Some((_, else_branch)) = else_branch {
-            self.visit_expr(else_branch);
-        }
+        let else_reg = self.visit_expr(else_branch);
+        self.discard(else_reg);
+        self.def_label(end);
         des
     }
+
+    fn visit_expr_for(&mut self, expr_for: &ExprFor) -> Reg {
+        let ExprFor {
+            name,
+            iter,
+            end,
+            body,
+            ..
+        } = expr_for;
+        let start = self.visit_expr(iter);
+        let ty = self.type_of(start);
+        // NOTE: a range counts in the variable itself, a `Vec` counts an index and copies the item
+        // at it into the variable. Nothing stays in a register from one time around to the next.
+        let (counter, limit, vec) = match end {
+            Some((_, end)) => {
+                let counter = self.alloc_slot(ty);
+                self.write_place(Place::Local(counter.clone()), start);
+                let end = self.visit_expr(end);
+                let limit = self.alloc_slot(self.type_of(end));
+                self.write_place(Place::Local(limit.clone()), end);
+                (counter, Some(limit), None)
+            }
+            None => {
+                let vec = self.alloc_slot(Type::Ptr(Box::new(ty.pointee().cloned().unwrap_or(ty))));
+                self.write_place(Place::Local(vec.clone()), start);
+                let counter = self.alloc_slot(Type::U64);
+                let zero = self.load_imm(0_u64.into());
+                self.write_place(Place::Local(counter.clone()), zero);
+                (counter, None, Some(vec))
+            }
+        };
+        let (top, exit) = (self.gen_label(), self.gen_label());
+        self.def_label(top.clone());
+        let index = self.read_place(Place::Local(counter.clone()));
+        let len = match (&limit, &vec) {
+            (Some(limit), _) => self.read_place(Place::Local(limit.clone())),
+            (None, Some(vec)) => {
+                let len = self.vec_field(vec, "len");
+                self.read_place(len)
+            }
+            (None, None) => unreachable!("a loop goes over a range or a Vec"),
+        };
+        let des = self.get_reg();
+        self.push_to_block(Les {
+            des,
+            lhs: index,
+            rhs: len,
+        });
+        self.set_type(des, Type::Bool);
+        self.conditional(exit.clone(), des);
+        self.enter_scope();
+        let item = match &vec {
+            Some(vec) => {
+                let data = self.vec_field(vec, "data");
+                let elem = data.ty().pointee().cloned().unwrap_or_default();
+                let data = self.read_place(data);
+                let index = self.read_place(Place::Local(counter.clone()));
+                let addr = self.elem_ptr(data, index, elem.clone());
+                let value = self.read_place(Place::Addr(addr, elem.clone()));
+                let item = self.alloc_slot(elem);
+                self.write_place(Place::Local(item.clone()), value);
+                item
+            }
+            None => counter.clone(),
+        };
+        self.declare_var(name, item);
+        let reg = self.visit_expr_block(body);
+        self.discard(reg);
+        self.leave_scope();
+        let index = self.read_place(Place::Local(counter.clone()));
+        let one = self.load_imm(1_u64.into());
+        let next = self.get_reg();
+        self.push_to_block(Add {
+            des: next,
+            lhs: index,
+            rhs: one,
+        });
+        self.set_type(next, self.type_of(index));
+        self.write_place(Place::Local(counter), next);
+        self.jump(top);
+        self.def_label(exit);
+        self.null()
+    }
 }
@@ -1,6 +1,14 @@
-// use crate::semantic_analysis::{SemanticAnalysisVisitor, SymbolTableBuilder};
 macro_rules! snapshot {
+    ($name:tt, $path:tt, std) => {
+        snapshot!($name, $path, false, true);
+    };
     ($name:tt, $path:tt) => {
+        snapshot!($name, $path, false);
+    };
+    ($name:tt, $path:tt, $overflow_checks:expr) => {
+        snapshot!($name, $path, $overflow_checks, false);
+    };
+    ($name:tt, $path:tt, $overflow_checks:expr, $std:expr) => {
         #[test]
         fn $name() {
             use super::*;
@@ -9,10 +17,15 @@ macro_rules! snapshot {
             let contents = include_str!($path);
             let tokens = lex(contents).unwrap();
             let ast = parse(tokens).unwrap();
-            // let mut symbol_table_builder = SymbolTableBuilder::default();
-            // symbol_table_builder.visit(&ast);
-            // let symbol_table = symbol_table_builder.build();
-            let ir_code = code_gen(ast).unwrap();
+            let ast = match $std {
+                true => {
+                    let file = concat!(env!("CARGO_MANIFEST_DIR"), "/src/ir/", $path);
+                    $crate::module::link(file, ast).unwrap()
+                }
+                false => ast,
+            };
+            let ast = $crate::semantic_analysis::check(ast).unwrap();
+            let ir_code = code_gen(ast, $overflow_checks).unwrap();
             let result = ir_code
                 .0
                 .iter()
@@ -31,3 +44,30 @@ macro_rules! snapshot {
 snapshot!(binary, "../../snapshots/binary.a");
 snapshot!(ifelse, "../../snapshots/ifelse.a");
 snapshot!(max, "../../snapshots/max.a");
+snapshot!(array, "../../snapshots/array.a");
+snapshot!(pointer, "../../snapshots/pointer.a");
+snapshot!(string, "../../snapshots/string.a");
+snapshot!(print, "../../snapshots/print.a");
+snapshot!(args, "../../snapshots/args.a");
+snapshot!(syscall, "../../snapshots/syscall.a");
+snapshot!(recursion, "../../snapshots/recursion.a");
+snapshot!(scope, "../../snapshots/scope.a");
+snapshot!(globals, "../../snapshots/globals.a");
+snapshot!(generics, "../../snapshots/generics.a");
+snapshot!(traits, "../../snapshots/traits.a");
+snapshot!(methods, "../../snapshots/methods.a");
+snapshot!(structs, "../../snapshots/structs.a");
+snapshot!(fn_values, "../../snapshots/fn_values.a");
+snapshot!(closures, "../../snapshots/closures.a");
+snapshot!(floats, "../../snapshots/floats.a");
+snapshot!(casts, "../../snapshots/casts.a");
+snapshot!(bools, "../../snapshots/bools.a");
+snapshot!(overflow, "../../snapshots/overflow.a", true);
+snapshot!(mul_overflow, "../../snapshots/mul_overflow.a", true);
+snapshot!(panics, "../../snapshots/panics.a");
+snapshot!(loops, "../../snapshots/loops.a", std);
+snapshot!(extern_fn, "../../snapshots/extern.a");
+snapshot!(export, "../../snapshots/export.a");
+snapshot!(abi, "../../snapshots/abi.a");
+snapshot!(widths, "../../snapshots/widths.a");
+snapshot!(std, "../../std/std.a");
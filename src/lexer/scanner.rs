@@ -2,11 +2,14 @@ use super::Span;
 use crate::parse::{
     keyword,
     CtrlColon,
+    CtrlColonColon,
     CtrlComma,
     CtrlDot,
+    CtrlDotDot,
     CtrlLBrace,
     CtrlLBracet,
     CtrlLParan,
+    CtrlPipe,
     CtrlRBrace,
     CtrlRBracet,
     CtrlRParan,
@@ -17,6 +20,7 @@ use crate::parse::{
     Ident,
     LitBool,
     LitChar,
+    LitFloat,
     LitInt,
     LitStr,
     OpAdd,
@@ -30,6 +34,7 @@ use crate::parse::{
     OpMul,
     OpNeq,
     OpNot,
+    OpRef,
     OpSub,
 };
 use std::iter::Peekable;
@@ -59,7 +64,7 @@ impl<'a> Lexer<'a> {
     fn next(&mut self) -> Option<char> {
         let ch = self.src.next()?;
         self.span.right_shift(ch);
-        self.last_chr_len = ch.to_string().as_bytes().len();
+        self.last_chr_len = ch.to_string().len();
         Some(ch)
     }
 
@@ -85,6 +90,15 @@ impl<'a> Lexer<'a> {
         while let Some(c) = self.next_if(|c| c.is_ascii_digit() || c == '_') {
             number.push(c);
         }
+        // NOTE: `1.max()` is still a method call on an int, a float needs a digit after the dot.
+        let mut rest = self.src.clone();
+        if rest.next() == Some('.') && rest.next().is_some_and(|c| c.is_ascii_digit()) {
+            number.extend(self.next());
+            while let Some(c) = self.next_if(|c| c.is_ascii_digit() || c == '_') {
+                number.push(c);
+            }
+            return Some(Box::new(LitFloat::new(number, self.span())));
+        }
         Some(Box::new(LitInt::new(number, self.span())))
     }
 
@@ -96,12 +110,22 @@ impl<'a> Lexer<'a> {
         let span = self.span();
         Some(match id.as_str() {
             "fn" => Box::new(keyword::Fn(span)),
+            "extern" => Box::new(keyword::Extern(span)),
             "struct" => Box::new(keyword::Struct(span)),
             "if" => Box::new(keyword::If(span)),
             "else" => Box::new(keyword::Else(span)),
             "use" => Box::new(keyword::Use(span)),
+            "pub" => Box::new(keyword::Pub(span)),
+            "const" => Box::new(keyword::Const(span)),
+            "static" => Box::new(keyword::Static(span)),
+            "trait" => Box::new(keyword::Trait(span)),
+            "impl" => Box::new(keyword::Impl(span)),
+            "for" => Box::new(keyword::For(span)),
+            "in" => Box::new(keyword::In(span)),
             "return" => Box::new(keyword::Return(span)),
             "let" => Box::new(keyword::Let(span)),
+            "mut" => Box::new(keyword::Mut(span)),
+            "as" => Box::new(keyword::As(span)),
             "true" => Box::new(LitBool::new(id, span)),
             "false" => Box::new(LitBool::new(id, span)),
             _ => Box::new(Ident::new(id, span)),
@@ -112,6 +136,9 @@ impl<'a> Lexer<'a> {
         let mut string = String::new();
         while let Some(c) = self.next_if(|c| c != '"') {
             string.push(c);
+            if c == '\\' {
+                string.extend(self.next());
+            }
         }
         self.next();
         Some(Box::new(LitStr::new(string, self.span())))
@@ -121,6 +148,9 @@ impl<'a> Lexer<'a> {
         let mut string = String::new();
         while let Some(c) = self.next_if(|c| c != '\'') {
             string.push(c);
+            if c == '\\' {
+                string.extend(self.next());
+            }
         }
         self.next();
 
@@ -170,18 +200,21 @@ impl<'a> Lexer<'a> {
             '<' => self.token::<OpLes>("<"),
             '=' => self.token::<OpEqual>("="),
             '!' => self.token::<OpNot>("!"),
+            '&' => self.token::<OpRef>("&"),
             // '%' => self.op_token("%"),
+            '.' if self.matched('.') => self.token::<CtrlDotDot>(".."),
             '.' => self.token::<CtrlDot>("."),
             ',' => self.token::<CtrlComma>(","),
             '(' => self.token::<CtrlLParan>("("),
             ')' => self.token::<CtrlRParan>(")"),
             '{' => self.token::<CtrlLBrace>("{"),
             '}' => self.token::<CtrlRBrace>("}"),
-            '[' => self.token::<CtrlLBracet>("{"),
-            ']' => self.token::<CtrlRBracet>("}"),
+            '[' => self.token::<CtrlLBracet>("["),
+            ']' => self.token::<CtrlRBracet>("]"),
+            ':' if self.matched(':') => self.token::<CtrlColonColon>("::"),
             ':' => self.token::<CtrlColon>(":"),
             ';' => self.token::<CtrlSemiColon>(";"),
-            // 'λ' => self.op_token("λ"),
+            '|' => self.token::<CtrlPipe>("|"),
             '\n' | '\r' | ' ' | '\0' => {
                 let ch = self.next()?;
                 self.span.reset(Some(self.last_chr_len));
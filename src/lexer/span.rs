@@ -32,7 +32,7 @@ impl Span {
         if ch == '\0' {
             return;
         }
-        let len = ch.to_string().as_bytes().len();
+        let len = ch.to_string().len();
         if ch == '\n' {
             self.row_end += 1;
             self.col_end = 0;
@@ -60,10 +60,10 @@ impl From<(Span, Span)> for Span {
         let idx_start: usize = x.idx_start;
         let idx_end: usize = y.idx_end;
         let row_start: usize = x.row_start;
-        let col_start: usize = y.col_start;
-        let row_end: usize = x.row_end;
+        let col_start: usize = x.col_start;
+        let row_end: usize = y.row_end;
         let col_end: usize = y.col_end;
-        Self::new(idx_start, idx_end, row_start, row_end, col_start, col_end)
+        Self::new(idx_start, idx_end, row_start, col_start, row_end, col_end)
     }
 }
 
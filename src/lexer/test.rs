@@ -47,3 +47,30 @@ macro_rules! snapshot {
 snapshot!(binary, "../../snapshots/binary.a");
 snapshot!(ifelse, "../../snapshots/ifelse.a");
 snapshot!(max, "../../snapshots/max.a");
+snapshot!(array, "../../snapshots/array.a");
+snapshot!(pointer, "../../snapshots/pointer.a");
+snapshot!(string, "../../snapshots/string.a");
+snapshot!(print, "../../snapshots/print.a");
+snapshot!(args, "../../snapshots/args.a");
+snapshot!(syscall, "../../snapshots/syscall.a");
+snapshot!(recursion, "../../snapshots/recursion.a");
+snapshot!(scope, "../../snapshots/scope.a");
+snapshot!(globals, "../../snapshots/globals.a");
+snapshot!(generics, "../../snapshots/generics.a");
+snapshot!(traits, "../../snapshots/traits.a");
+snapshot!(methods, "../../snapshots/methods.a");
+snapshot!(structs, "../../snapshots/structs.a");
+snapshot!(fn_values, "../../snapshots/fn_values.a");
+snapshot!(closures, "../../snapshots/closures.a");
+snapshot!(floats, "../../snapshots/floats.a");
+snapshot!(casts, "../../snapshots/casts.a");
+snapshot!(bools, "../../snapshots/bools.a");
+snapshot!(overflow, "../../snapshots/overflow.a");
+snapshot!(mul_overflow, "../../snapshots/mul_overflow.a");
+snapshot!(panics, "../../snapshots/panics.a");
+snapshot!(loops, "../../snapshots/loops.a");
+snapshot!(extern_fn, "../../snapshots/extern.a");
+snapshot!(export, "../../snapshots/export.a");
+snapshot!(abi, "../../snapshots/abi.a");
+snapshot!(modules, "../../snapshots/modules/main.a");
+snapshot!(pub_fn, "../../snapshots/modules/math.a");
@@ -1,11 +1,12 @@
 use std::process::Command;
 
-// use semantic_analysis::{SemanticAnalysisVisitor, SymbolTableBuilder};
-
+mod builtins;
 mod ir;
 mod lexer;
+mod module;
 mod parse;
-// mod semantic_analysis;
+mod semantic_analysis;
+mod stdlib;
 mod symbol_table;
 mod x86_64_linux;
 
@@ -18,6 +19,9 @@ Usage: a <inputfile>.a [<flags>*]
         -dast | --debug-ast     print out ast created by compiler
         -dir  | --debug-ir      print out ir code created by compiler
         -dasm | --debug-asm     print out assembly code created by compiler
+        -oc   | --overflow-checks  panic when an int +, - or * overflows
+        -c    | --object        emit a relocatable ELF object to link against C with `cc`, exporting
+                                every `pub extern fn`
 ";
 
 fn print_output<T>(output: bool) -> impl FnOnce(T) -> Result<T, Vec<String>>
@@ -57,72 +61,74 @@ fn compile(flags: Flags) -> Result<(), Vec<String>> {
         .and_then(print_output(flags.debug_tokens))
         .and_then(parse::parse)
         .and_then(print_output(flags.debug_ast))
-        // FIXME: this needs to return a Result
-        // .map(|ast| {
-        //     let mut symbol_table_builder = SymbolTableBuilder::default();
-        //     symbol_table_builder.visit(&ast);
-        //     let symbol_table = symbol_table_builder.build();
-        //
-        //     (ast, symbol_table)
-        // })
-        // .and_then(semantic_analysis::create_symbol_table)
-        .and_then(ir::code_gen)
+        .map_err(|errors| {
+            errors
+                .iter()
+                .map(|error| format!("{}: {error}", flags.filename))
+                .collect::<Vec<_>>()
+        })
+        .and_then(|ast| module::link(&flags.filename, ast))
+        .and_then(semantic_analysis::check)
+        .and_then(|program| ir::code_gen(program, flags.overflow_checks))
         .and_then(print_output(flags.debug_ir))
-        .and_then(x86_64_linux::compile_ir_code)
+        .and_then(|ir| x86_64_linux::compile_ir_code(ir, flags.object))
         .and_then(print_output(flags.debug_asm))
         .and_then(x86_64_linux::instruction_to_string)
         .and_then(print_output_asm(flags.debug_asm))
         .map(|asm| (flags.filename, asm))
-        .and_then(write_asm_to_file)
+        .and_then(write_asm_to_file(flags.object))
         .and_then(compile_asm_with_fasm)
         .map_err(print_error_message)
 }
-fn start_func_assembly() -> String {
-    use x86_64_linux::{Instruction, X86Reg64};
-    [
-        Instruction::DefLabel("_start".into()),
-        Instruction::Call("main".into()),
-        Instruction::MoveReg(X86Reg64::RDI.into(), X86Reg64::RAX.into()),
-        Instruction::MoveImm(X86Reg64::RAX.into(), 60),
-        Instruction::Syscall,
-    ]
-    .iter()
-    .map(ToString::to_string)
-    .collect()
+/// An object is entered through the C `main` the code has when the program has a `main`.
+fn start_func_assembly(object: bool) -> String {
+    let start = match object {
+        true => vec![],
+        false => x86_64_linux::runtime::start(),
+    };
+    start.iter().map(ToString::to_string).collect()
 }
 
-fn write_asm_to_file((filename, asm_code): (String, String)) -> Result<String, Vec<String>> {
-    let Some((filename, _)) = filename.split_once('.') else {
-        eprintln!("file name has no extension");
-        std::process::exit(1);
-    };
+/// `object` writes a relocatable object for `fasm` to assemble, otherwise an executable.
+fn write_asm_to_file(object: bool) -> impl FnOnce((String, String)) -> Result<String, Vec<String>> {
+    move |(filename, asm_code)| {
+        let Some((filename, _)) = filename.split_once('.') else {
+            eprintln!("file name has no extension");
+            std::process::exit(1);
+        };
 
-    let asm_file = format!("{filename}.asm");
-    let header = if cfg!(target_os = "windows") {
-        "format pe64 gui"
-    } else {
-        "format ELF64 executable 3
-segment readable executable
+        let asm_file = format!("{filename}.asm");
+        // NOTE: the empty `.note.GNU-stack` section tells the linker the stack is not executable.
+        let header = if object {
+            "format ELF64
+section '.note.GNU-stack'
+section '.text' executable
 "
-    };
-    let footer = if cfg!(target_os = "windows") {
-        ""
-    } else {
-        "segment readable writable"
-    };
-    let code = format!(
-        "{header}
-entry _start__
-{asm_code}
-
+        } else if cfg!(target_os = "windows") {
+            "format pe64 gui
+entry _start__"
+        } else {
+            "format ELF64 executable 3
+segment readable executable
+entry _start__"
+        };
+        // NOTE: the generated code goes last as it switches to the data segments, ending in the
+        // writable one the runtime data is added to.
+        let code = format!(
+            "{header}
 {}
-{footer}",
-        start_func_assembly()
-    );
-
-    std::fs::write(&asm_file, code)
-        .map(|_| asm_file)
-        .map_err(|e| vec![e.to_string()])
+{}
+{asm_code}
+{}",
+            start_func_assembly(object),
+            x86_64_linux::runtime::runtime_assembly(),
+            x86_64_linux::runtime::runtime_data(),
+        );
+
+        std::fs::write(&asm_file, code)
+            .map(|_| asm_file)
+            .map_err(|e| vec![e.to_string()])
+    }
 }
 
 fn compile_asm_with_fasm(asm_file: String) -> Result<(), Vec<String>> {
@@ -161,6 +167,8 @@ struct Flags {
     pub debug_ast: bool,
     pub debug_ir: bool,
     pub debug_asm: bool,
+    pub overflow_checks: bool,
+    pub object: bool,
 }
 
 impl Flags {
@@ -169,6 +177,8 @@ impl Flags {
         let mut debug_ast = false;
         let mut debug_ir = false;
         let mut debug_asm = false;
+        let mut overflow_checks = false;
+        let mut object = false;
         let Some(filename) = std::env::args().nth(1) else {
             return Err("No file given to parse".into());
         };
@@ -178,6 +188,8 @@ impl Flags {
                 "-dast" | "--debug-ast" => debug_ast = true,
                 "-dir" | "--debug-ir" => debug_ir = true,
                 "-dasm" | "--debug-asm" => debug_asm = true,
+                "-oc" | "--overflow-checks" => overflow_checks = true,
+                "-c" | "--object" => object = true,
                 "-h" | "--help" => return Err(HELP_MESSAGE.into()),
                 i => return Err(format!("'{i}' Unknow argument given")),
             }
@@ -188,6 +200,8 @@ impl Flags {
             debug_ast,
             debug_ir,
             debug_asm,
+            overflow_checks,
+            object,
         })
     }
 }
@@ -200,10 +214,8 @@ fn main() {
             std::process::exit(1);
         }
     };
-    if let Err(errs) = compile(flags) {
-        for i in errs.into_iter() {
-            eprintln!("{i}");
-        }
+    // NOTE: `compile` has printed the errors already.
+    if compile(flags).is_err() {
         std::process::exit(1);
     }
 }
@@ -0,0 +1,61 @@
+use crate::lexer::Span;
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleError {
+    NotFound(String, PathBuf, Span),
+    DuplicateUse(String, Span),
+    Cycle(String, Span),
+    UnknownModule(String, Span),
+    UnknownFunction(String, String, Span),
+    UnknownGlobal(String, String, Span),
+    UnknownType(String, String, Span),
+    DefinedInStd(String, Span),
+    /// What kind of item it is, its name and the module it is in.
+    Private(&'static str, String, String, Span),
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(name, path, span) => write!(
+                f,
+                "{span:?} module '{name}' not found, expected it at '{}'",
+                path.display()
+            ),
+            Self::DuplicateUse(name, span) => {
+                write!(f, "{span:?} module '{name}' is already in use")
+            }
+            Self::Cycle(name, span) => {
+                write!(f, "{span:?} module '{name}' ends up using itself")
+            }
+            Self::UnknownModule(name, span) => write!(f, "{span:?} unknown module '{name}'"),
+            Self::UnknownFunction(name, module, span) => {
+                write!(f, "{span:?} no function '{name}' in module '{module}'")
+            }
+            Self::UnknownGlobal(name, module, span) => {
+                write!(
+                    f,
+                    "{span:?} no const or static '{name}' in module '{module}'"
+                )
+            }
+            Self::UnknownType(name, module, span) => {
+                write!(
+                    f,
+                    "{span:?} no struct or trait '{name}' in module '{module}'"
+                )
+            }
+            Self::DefinedInStd(name, span) => write!(
+                f,
+                "{span:?} '{name}' is already defined in std, give it another name"
+            ),
+            Self::Private(kind, name, module, span) => {
+                write!(
+                    f,
+                    "{span:?} {kind} '{name}' in module '{module}' is private"
+                )
+            }
+        }
+    }
+}
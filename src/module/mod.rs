@@ -0,0 +1,650 @@
+mod error;
+#[cfg(test)]
+mod test;
+pub use error::ModuleError;
+
+use crate::lexer;
+use crate::parse::{
+    self, ArrayLen, Expr, ExprBlock, ExprVar, Generic, Item, ItemConst, ItemFn, ItemStatic,
+    ItemStruct, ItemTrait, TypeMut,
+};
+use crate::stdlib;
+use crate::symbol_table::{Scope, Symbol, SymbolData, SymbolTable, TypeName};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A file and the modules it pulls in with `use`.
+#[derive(Debug)]
+pub struct Module {
+    /// `["bar"]` for `bar.a`, empty for the file given to the compiler. A module used by more
+    /// than one other module is loaded once and has the same path for all of them.
+    path: Vec<String>,
+    file: PathBuf,
+    items: Vec<Item>,
+    symbol_table: SymbolTable,
+    /// The modules pulled in with `use` by the name they are used with, an index into the
+    /// modules of the program.
+    children: HashMap<String, usize>,
+}
+
+impl Module {
+    /// Name an item declared in this module is known by everywhere else.
+    fn qualify(&self, name: &str) -> String {
+        self.path
+            .iter()
+            .map(String::as_str)
+            .chain([name])
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+
+    /// Any function, const or static declared in this module.
+    fn item(&self, name: &str) -> Option<&Item> {
+        self.items
+            .iter()
+            .find(|item| item.name().is_some_and(|ident| ident.value == name))
+    }
+
+    fn function(&self, name: &str) -> Option<&ItemFn> {
+        self.items
+            .iter()
+            .filter_map(Item::as_fn)
+            .find(|item_fn| item_fn.name.value == name)
+    }
+
+    /// Name a struct or trait declared in this module is known by everywhere else, the std ones
+    /// are known by their own name like the builtin types.
+    fn qualify_type(&self, name: &str) -> String {
+        match self.is_std() {
+            true => name.to_string(),
+            false => self.qualify(name),
+        }
+    }
+
+    fn is_std(&self) -> bool {
+        self.file == Path::new(stdlib::FILE)
+    }
+
+    /// A `pub` function, const or static of std a module sees by its own name when it does not
+    /// declare anything by that name, `write` for `std::write`.
+    fn prelude<'a>(&self, std: Option<&'a Module>, name: &str) -> Option<&'a Module> {
+        let std = std.filter(|_| self.item(name).is_none())?;
+        std.item(name)
+            .filter(|item| item.is_pub())
+            .filter(|item| matches!(item, Item::Const(_) | Item::Static(_) | Item::Fn(_)))
+            .map(|_| std)
+    }
+
+    /// A struct or trait declared in this module.
+    fn type_item(&self, name: &str) -> Option<&Item> {
+        self.item(name)
+            .filter(|item| matches!(item, Item::Struct(_) | Item::Trait(_)))
+    }
+
+    /// The module `path` points at, starting from this one.
+    fn child<'a>(
+        &'a self,
+        modules: &'a [Module],
+        path: &[parse::Ident],
+    ) -> Result<&'a Module, ModuleError> {
+        path.iter().try_fold(self, |module, name| {
+            module
+                .children
+                .get(&name.value)
+                .map(|&index| &modules[index])
+                .ok_or_else(|| ModuleError::UnknownModule(name.value.clone(), name.span))
+        })
+    }
+}
+
+/// Loads every module reachable from `file` and std and flattens them into one program where
+/// the functions, structs and traits of a module are named by its path, `foo::bar`.
+pub fn link(
+    file: impl AsRef<Path>,
+    ast: (Vec<Item>, SymbolTable),
+) -> Result<(Vec<Item>, SymbolTable), Vec<String>> {
+    let file = file.as_ref();
+    let root = canonical(file);
+    let dir = root.parent().unwrap_or(Path::new("")).to_path_buf();
+    let (std_items, std_table) = stdlib::parse()?;
+    let std = Module {
+        path: vec![stdlib::MODULE.into()],
+        file: stdlib::FILE.into(),
+        items: std_items,
+        symbol_table: std_table,
+        children: HashMap::new(),
+    };
+    let mut loader = Loader {
+        dir,
+        modules: vec![std],
+        loaded: HashMap::new(),
+        loading: vec![root.clone()],
+    };
+    let root = loader.load(file, root, vec![], ast)?;
+    let modules = loader.modules;
+    let std = &modules[STD];
+    let mut errors = vec![];
+    // NOTE: the file given to the compiler is not in a module, its structs and traits have the
+    // same names as the std ones.
+    for item in modules[root].items.iter() {
+        let (Item::Struct(ItemStruct { name, .. }) | Item::Trait(ItemTrait { name, .. })) = item
+        else {
+            continue;
+        };
+        if std.type_item(&name.value).is_some() {
+            let error = ModuleError::DefinedInStd(name.value.clone(), name.span);
+            errors.push(in_file(&modules[root].file, error));
+        }
+    }
+    let mut structs = HashSet::new();
+    for module in modules.iter() {
+        for item in module.items.iter() {
+            if let Item::Struct(item_struct) = item {
+                structs.insert(module.qualify_type(&item_struct.name.value));
+            }
+        }
+    }
+    let mut items = vec![];
+    let mut symbol_table = SymbolTable::new();
+    // NOTE: the file given to the compiler is loaded last, it comes first in the program and
+    // std last.
+    for module in modules.iter().rev() {
+        let mut module_errors = vec![];
+        flatten(
+            module,
+            &modules,
+            &structs,
+            &mut items,
+            &mut symbol_table,
+            &mut module_errors,
+        );
+        errors.extend(
+            module_errors
+                .iter()
+                .map(|error| in_file(&module.file, error)),
+        );
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok((items, symbol_table))
+}
+
+/// The index of std in the modules of the program, it is loaded before any other.
+const STD: usize = 0;
+
+/// Spans don't know which file they are from so the errors say it instead.
+fn in_file(file: &Path, error: impl fmt::Display) -> String {
+    format!("{}: {error}", file.display())
+}
+
+/// The same file reached through different paths is the same module.
+fn canonical(file: &Path) -> PathBuf {
+    std::fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf())
+}
+
+struct Loader {
+    /// The directory of the file given to the compiler, the path of a module is where its file
+    /// is from there.
+    dir: PathBuf,
+    modules: Vec<Module>,
+    /// The index of every module loaded so far by its canonical file.
+    loaded: HashMap<PathBuf, usize>,
+    /// The modules being loaded, to catch one that ends up using itself.
+    loading: Vec<PathBuf>,
+}
+
+impl Loader {
+    fn load(
+        &mut self,
+        file: &Path,
+        canonical_file: PathBuf,
+        path: Vec<String>,
+        (items, symbol_table): (Vec<Item>, SymbolTable),
+    ) -> Result<usize, Vec<String>> {
+        let dir = file.parent().unwrap_or(Path::new(""));
+        let mut children = HashMap::new();
+        for item in items.iter() {
+            let Item::Use(item_use) = item else {
+                continue;
+            };
+            let name = &item_use.name;
+            if children.contains_key(&name.value) {
+                let error = ModuleError::DuplicateUse(name.value.clone(), name.span);
+                return Err(vec![in_file(file, error)]);
+            }
+            let child_file = dir.join(format!("{}.a", name.value));
+            let child_canonical = canonical(&child_file);
+            if self.loading.contains(&child_canonical) {
+                let error = ModuleError::Cycle(name.value.clone(), name.span);
+                return Err(vec![in_file(file, error)]);
+            }
+            if let Some(&index) = self.loaded.get(&child_canonical) {
+                children.insert(name.value.clone(), index);
+                continue;
+            }
+            let src = std::fs::read_to_string(&child_file).map_err(|_| {
+                let error =
+                    ModuleError::NotFound(name.value.clone(), child_file.clone(), name.span);
+                vec![in_file(file, error)]
+            })?;
+            let ast = lexer::lex(src).and_then(parse::parse).map_err(|errors| {
+                errors
+                    .iter()
+                    .map(|error| in_file(&child_file, error))
+                    .collect::<Vec<_>>()
+            })?;
+            let child_path = self.module_path(&child_canonical);
+            self.loading.push(child_canonical.clone());
+            let index = self.load(&child_file, child_canonical, child_path, ast)?;
+            self.loading.pop();
+            children.insert(name.value.clone(), index);
+        }
+        // NOTE: a module that uses one named `std` names that one by `std::`.
+        children.entry(stdlib::MODULE.to_string()).or_insert(STD);
+        self.modules.push(Module {
+            path,
+            file: file.to_path_buf(),
+            items,
+            symbol_table,
+            children,
+        });
+        let index = self.modules.len() - 1;
+        self.loaded.insert(canonical_file, index);
+        Ok(index)
+    }
+
+    /// `["foo", "bar"]` for `foo/bar.a`.
+    fn module_path(&self, file: &Path) -> Vec<String> {
+        let file = file.strip_prefix(&self.dir).unwrap_or(file);
+        file.with_extension("")
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy().into_owned())
+            .collect()
+    }
+}
+
+fn flatten(
+    module: &Module,
+    modules: &[Module],
+    structs: &HashSet<String>,
+    items: &mut Vec<Item>,
+    symbol_table: &mut SymbolTable,
+    errors: &mut Vec<ModuleError>,
+) {
+    for item in module.items.iter() {
+        let mut item = item.clone();
+        let mut resolver = Resolver {
+            module,
+            modules,
+            std: Some(&modules[STD]).filter(|_| !module.is_std()),
+            structs,
+            locals: vec![],
+            errors,
+        };
+        match &mut item {
+            Item::Use(_) => continue,
+            Item::Fn(item_fn) => {
+                item_fn.name.value = module.qualify(&item_fn.name.value);
+                item_fn.file = Some(module.file.display().to_string());
+                resolver.item_fn(item_fn);
+            }
+            // NOTE: an extern function is named by the symbol it is linked against.
+            Item::Extern(_) => {}
+            Item::Struct(ItemStruct { name, generics, .. }) => {
+                name.value = module.qualify_type(&name.value);
+                resolver.generics(generics);
+            }
+            Item::Trait(ItemTrait { name, .. }) => name.value = module.qualify_type(&name.value),
+            Item::Impl(item_impl) => {
+                resolver.generics(&mut item_impl.generics);
+                if let Some((trait_name, _)) = item_impl.of_trait.as_mut() {
+                    resolver.type_name(trait_name);
+                }
+                for item_fn in item_impl.fns.iter_mut() {
+                    item_fn.file = Some(module.file.display().to_string());
+                    resolver.item_fn(item_fn);
+                }
+            }
+            Item::Const(ItemConst { name, expr, .. })
+            | Item::Static(ItemStatic { name, expr, .. }) => {
+                name.value = module.qualify(&name.value);
+                resolver.expr(expr);
+            }
+        }
+        // NOTE: a const used as the length of an array is named by its path like in an
+        // expression.
+        item.for_each_type(&mut |ty| match ty {
+            TypeMut::Type(ty) => {
+                ty.for_each_name(&mut |name| resolver.type_name(name));
+                ty.for_each_len(&mut |len| resolver.len(len));
+            }
+            TypeMut::Len(len) => resolver.len(len),
+            TypeMut::Struct(name) => resolver.type_name(name),
+        });
+        items.push(item);
+    }
+    let scope = |scope: &Scope| match scope {
+        Scope::Global if module.path.is_empty() => Scope::Global,
+        Scope::Global => Scope::Module(module.path.join("::")),
+        Scope::Function(name) => Scope::Function(module.qualify(name)),
+        Scope::Module(_) | Scope::Block(_) | Scope::Type(_) => scope.clone(),
+    };
+    for (symbol, data) in module.symbol_table.iter() {
+        let symbol = Symbol {
+            scope: scope(&symbol.scope),
+            name: symbol.name.clone(),
+        };
+        let data = SymbolData {
+            scope: scope(&data.scope),
+            ..data.clone()
+        };
+        symbol_table.insert(symbol, data);
+    }
+}
+
+/// Rewrites every name used in an item to the path it is declared under.
+struct Resolver<'a> {
+    module: &'a Module,
+    modules: &'a [Module],
+    /// Where the names a module does not declare come from, none for std itself.
+    std: Option<&'a Module>,
+    structs: &'a HashSet<String>,
+    /// Variables in scope, they shadow the consts and statics of the module.
+    locals: Vec<String>,
+    errors: &'a mut Vec<ModuleError>,
+}
+
+impl Resolver<'_> {
+    fn item_fn(&mut self, item_fn: &mut ItemFn) {
+        self.generics(&mut item_fn.generics);
+        for param in item_fn.params.iter() {
+            self.locals.push(param.name.value.clone());
+        }
+        self.block(&mut item_fn.block);
+        self.locals.clear();
+    }
+
+    fn block(&mut self, block: &mut ExprBlock) {
+        let len = self.locals.len();
+        for stmt in block.stmts.iter_mut() {
+            self.expr(&mut stmt.stmt);
+        }
+        self.locals.truncate(len);
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Call(call) => {
+                // NOTE: a variable holding a function shadows the function of the same name.
+                match call.caller.as_mut() {
+                    Expr::Var(var)
+                        if !(var.path.is_empty() && self.locals.contains(&var.name.value)) =>
+                    {
+                        let (module, modules) = (self.module, self.modules);
+                        if let Err(error) = resolve_fn(module, modules, self.std, self.structs, var)
+                        {
+                            self.errors.push(error);
+                        }
+                    }
+                    caller => self.expr(caller),
+                }
+                for arg in call.args.iter_mut() {
+                    self.expr(arg);
+                }
+            }
+            Expr::MethodCall(call) => {
+                self.expr(&mut call.receiver);
+                for arg in call.args.iter_mut() {
+                    self.expr(arg);
+                }
+            }
+            Expr::Var(var) => {
+                if let Err(error) = self.var(var) {
+                    self.errors.push(error);
+                }
+            }
+            Expr::Lit(_) => {}
+            Expr::Binary(binary) => {
+                self.expr(&mut binary.left);
+                self.expr(&mut binary.right);
+            }
+            Expr::Let(expr_let) => {
+                self.expr(&mut expr_let.expr);
+                self.locals.push(expr_let.name.value.clone());
+            }
+            Expr::If(expr_if) => {
+                self.expr(&mut expr_if.cond);
+                self.block(&mut expr_if.then_branch);
+                if let Some((_, else_branch)) = expr_if.else_branch.as_mut() {
+                    self.expr(else_branch);
+                }
+            }
+            Expr::For(expr_for) => {
+                self.expr(&mut expr_for.iter);
+                if let Some((_, end)) = expr_for.end.as_mut() {
+                    self.expr(end);
+                }
+                let len = self.locals.len();
+                self.locals.push(expr_for.name.value.clone());
+                self.block(&mut expr_for.body);
+                self.locals.truncate(len);
+            }
+            Expr::Block(block) => self.block(block),
+            Expr::Return(ret) => self.expr(&mut ret.expr),
+            Expr::Array(array) => {
+                for elem in array.elems.iter_mut() {
+                    self.expr(elem);
+                }
+            }
+            Expr::ArrayRepeat(repeat) => self.expr(&mut repeat.expr),
+            Expr::Index(index) => {
+                self.expr(&mut index.expr);
+                self.expr(&mut index.index);
+            }
+            Expr::Assign(assign) => {
+                self.expr(&mut assign.target);
+                self.expr(&mut assign.value);
+            }
+            Expr::Unary(unary) => self.expr(&mut unary.expr),
+            Expr::Struct(expr_struct) => {
+                for (_, value) in expr_struct.fields.iter_mut() {
+                    self.expr(value);
+                }
+            }
+            Expr::Field(field) => self.expr(&mut field.expr),
+            Expr::Cast(cast) => self.expr(&mut cast.expr),
+            Expr::Closure(closure) => {
+                let len = self.locals.len();
+                for param in closure.params.iter() {
+                    self.locals.push(param.name.value.clone());
+                }
+                self.expr(&mut closure.body);
+                self.locals.truncate(len);
+            }
+        }
+    }
+
+    /// A variable is a local, a const, static or function of this module or one named by its
+    /// path.
+    fn var(&self, var: &mut ExprVar) -> Result<(), ModuleError> {
+        let ExprVar { path, name } = var;
+        if path.is_empty() {
+            let is_global = self
+                .module
+                .item(&name.value)
+                .is_some_and(|item| matches!(item, Item::Const(_) | Item::Static(_) | Item::Fn(_)));
+            if self.locals.contains(&name.value) {
+                return Ok(());
+            }
+            if is_global {
+                name.value = self.module.qualify(&name.value);
+            } else if let Some(std) = self.module.prelude(self.std, &name.value) {
+                name.value = std.qualify(&name.value);
+            }
+            return Ok(());
+        }
+        let target = self.module.child(self.modules, path)?;
+        let module_name = target.path.join("::");
+        match target.item(&name.value) {
+            Some(item @ (Item::Const(_) | Item::Static(_) | Item::Fn(_))) if !item.is_pub() => {
+                let kind = match item {
+                    Item::Const(_) => "const",
+                    Item::Static(_) => "static",
+                    _ => "function",
+                };
+                Err(ModuleError::Private(
+                    kind,
+                    name.value.clone(),
+                    module_name,
+                    name.span,
+                ))
+            }
+            Some(Item::Const(_) | Item::Static(_) | Item::Fn(_)) => {
+                name.value = target.qualify(&name.value);
+                path.clear();
+                Ok(())
+            }
+            _ => Err(ModuleError::UnknownGlobal(
+                name.value.clone(),
+                module_name,
+                name.span,
+            )),
+        }
+    }
+
+    fn generics(&mut self, generics: &mut [Generic]) {
+        for bound in generics
+            .iter_mut()
+            .flat_map(|generic| generic.bounds.iter_mut())
+        {
+            self.type_name(bound);
+        }
+    }
+
+    fn type_name(&mut self, name: &mut parse::Ident) {
+        if let Err(error) = resolve_type(self.module, self.modules, name) {
+            self.errors.push(error);
+        }
+    }
+
+    fn len(&self, len: &mut ArrayLen) {
+        if let ArrayLen::Const(name) = len {
+            if matches!(self.module.item(&name.value), Some(Item::Const(_))) {
+                name.value = self.module.qualify(&name.value);
+            }
+        }
+    }
+}
+
+/// A struct or trait is one of this module or one named by its path, `geometry::Point`.
+/// Anything else is a builtin type, a type parameter or comes from std.
+fn resolve_type(
+    module: &Module,
+    modules: &[Module],
+    name: &mut parse::Ident,
+) -> Result<(), ModuleError> {
+    let Some((path, last)) = name.value.rsplit_once("::") else {
+        if module.type_item(&name.value).is_some() {
+            name.value = module.qualify_type(&name.value);
+        }
+        return Ok(());
+    };
+    let path = path
+        .split("::")
+        .map(|part| parse::Ident::new(part, name.span))
+        .collect::<Vec<_>>();
+    let target = module.child(modules, &path)?;
+    let module_name = target.path.join("::");
+    match target.type_item(last) {
+        None => Err(ModuleError::UnknownType(
+            last.to_string(),
+            module_name,
+            name.span,
+        )),
+        Some(item) if !item.is_pub() => {
+            let kind = match item {
+                Item::Struct(_) => "struct",
+                _ => "trait",
+            };
+            Err(ModuleError::Private(
+                kind,
+                last.to_string(),
+                module_name,
+                name.span,
+            ))
+        }
+        Some(_) => {
+            name.value = target.qualify_type(last);
+            Ok(())
+        }
+    }
+}
+
+fn resolve_fn(
+    module: &Module,
+    modules: &[Module],
+    std: Option<&Module>,
+    structs: &HashSet<String>,
+    var: &mut ExprVar,
+) -> Result<(), ModuleError> {
+    let ExprVar { path, name } = var;
+    if path.is_empty() {
+        // NOTE: anything else not declared in this module is a builtin.
+        if module.function(&name.value).is_some() {
+            name.value = module.qualify(&name.value);
+        } else if let Some(std) = module.prelude(std, &name.value) {
+            name.value = std.qualify(&name.value);
+        }
+        return Ok(());
+    }
+    // NOTE: `u64::zero()` and `Point::new()` call an associated function of a type.
+    if let [ty] = path.as_mut_slice() {
+        if module.type_item(&ty.value).is_some() {
+            ty.value = module.qualify_type(&ty.value);
+            return Ok(());
+        }
+        let is_type = ty.value == "Self"
+            || structs.contains(&ty.value)
+            || !matches!(TypeName::from(ty.value.as_str()), TypeName::Custom(_));
+        if is_type && !module.children.contains_key(&ty.value) {
+            return Ok(());
+        }
+    }
+    // NOTE: `geometry::Point::new()` names the type by the module it is declared in.
+    if let [modules_path @ .., ty] = path.as_slice() {
+        if !modules_path.is_empty()
+            && module
+                .child(modules, modules_path)?
+                .type_item(&ty.value)
+                .is_some()
+        {
+            let names = path.iter().map(|name| name.value.as_str());
+            let mut ty = parse::Ident::new(names.collect::<Vec<_>>().join("::"), ty.span);
+            resolve_type(module, modules, &mut ty)?;
+            *path = vec![ty];
+            return Ok(());
+        }
+    }
+    let target = module.child(modules, path)?;
+    let module_name = target.path.join("::");
+    match target.function(&name.value) {
+        None => Err(ModuleError::UnknownFunction(
+            name.value.clone(),
+            module_name,
+            name.span,
+        )),
+        Some(item_fn) if !item_fn.is_pub() => Err(ModuleError::Private(
+            "function",
+            name.value.clone(),
+            module_name,
+            name.span,
+        )),
+        Some(_) => {
+            name.value = target.qualify(&name.value);
+            path.clear();
+            Ok(())
+        }
+    }
+}
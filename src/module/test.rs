@@ -0,0 +1,96 @@
+use super::link;
+use crate::lexer::lex;
+use crate::parse::parse;
+use crate::{ir, semantic_analysis};
+
+fn load(name: &str) -> Result<String, Vec<String>> {
+    let file = format!("{}/snapshots/modules/{name}.a", env!("CARGO_MANIFEST_DIR"));
+    let src = std::fs::read_to_string(&file).unwrap();
+    let ast = lex(src).and_then(parse).unwrap();
+    let (ir_code, _) = link(&file, ast)
+        .and_then(semantic_analysis::check)
+        .and_then(|program| ir::code_gen(program, false))?;
+    Ok(ir_code.iter().map(ToString::to_string).collect())
+}
+
+#[test]
+fn modules() {
+    let result = load("main").unwrap();
+    let mut settings = insta::Settings::clone_current();
+    settings.set_snapshot_path("testdata/output/");
+    settings.bind(|| {
+        insta::assert_snapshot!(result);
+    });
+}
+
+#[test]
+fn private_fn() {
+    let errors = load("private").unwrap_err();
+    assert_eq!(errors.len(), 7);
+    assert!(errors[0].contains("function 'double' in module 'math' is private"));
+    assert!(errors[1].contains("no function 'sub' in module 'math'"));
+    assert!(errors[2].contains("unknown module 'maths'"));
+    assert!(errors[3].contains("const 'TWO' in module 'math' is private"));
+    assert!(errors[4].contains("no const or static 'THREE' in module 'math'"));
+    assert!(errors[5].contains("struct 'Counter' in module 'math' is private"));
+    assert!(errors[6].contains("no struct or trait 'Scale' in module 'math'"));
+}
+
+#[test]
+fn module_not_found() {
+    let errors = load("not_found").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("module 'nowhere' not found"));
+}
+
+#[test]
+fn cyclic_use() {
+    let errors = load("cycle").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("module 'cycle' ends up using itself"));
+}
+
+#[test]
+fn panic_location() {
+    let result = load("panics").unwrap();
+    assert!(result.contains("modules/ratio.a:2:10: attempt to divide by zero"));
+    assert!(result.contains("modules/panics.a:4:17: attempt to divide by zero"));
+}
+
+#[test]
+fn diamond_use() {
+    let result = load("diamond").unwrap();
+    // NOTE: `left` and `right` both use `counter`, it is loaded once.
+    assert_eq!(result.matches("static counter::COUNT").count(), 1);
+    assert!(!result.contains("left::counter"));
+    assert!(result.contains("function shapes::Area$shapes::Square$area"));
+    assert!(result.contains("function shapes::Area$Square$area"));
+}
+
+#[test]
+fn std_names() {
+    let result = load("std_names").unwrap();
+    // NOTE: the program's own `free`, `exit` and `heap_map` sit next to the std ones.
+    assert!(result.contains("function heap_map("));
+    assert!(result.contains("function std::heap_map("));
+    assert!(result.contains("call std::write("));
+    assert!(result.contains("call heap_map("));
+}
+
+#[test]
+fn std_clash() {
+    let errors = load("std_clash").unwrap_err();
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].contains("'Vec' is already defined in std"));
+    assert!(errors[1].contains("function 'heap_map' in module 'std' is private"));
+    assert!(errors[2].contains("const 'HEAP_CHUNK' in module 'std' is private"));
+}
+
+#[test]
+fn error_in_used_file() {
+    let errors = load("file_errors").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(
+        errors[0].contains("modules/broken.a: (1,9)->(1,24) unknown variable 'undefined_thing'")
+    );
+}
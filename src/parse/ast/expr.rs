@@ -1,4 +1,7 @@
-use super::{keyword, Ident, Lit, Op};
+use super::{
+    keyword, ArrayLen, CtrlDot, CtrlDotDot, CtrlLBrace, CtrlLBracet, CtrlPipe, CtrlRBrace,
+    CtrlRBracet, Ident, Lit, Op, Param, Type, TypeMut,
+};
 use crate::lexer::{Span, Token};
 use std::fmt;
 
@@ -7,16 +10,109 @@ pub enum Expr {
     Lit(ExprLit),
     Binary(ExprBinary),
     Call(ExprCall),
+    MethodCall(ExprMethodCall),
     Var(ExprVar),
     Let(ExprLet),
     If(ExprIf),
+    For(ExprFor),
     Block(ExprBlock),
     Return(ExprReturn),
+    Array(ExprArray),
+    ArrayRepeat(ExprArrayRepeat),
+    Index(ExprIndex),
+    Assign(ExprAssign),
+    Unary(ExprUnary),
+    Struct(ExprStruct),
+    Field(ExprField),
+    Closure(ExprClosure),
+    Cast(ExprCast),
 }
 
 impl Expr {
-    pub fn is_call(&self) -> bool {
-        matches!(self, Self::Call(_))
+    /// Hands every type, `[expr; N]` length and struct literal name in the expression to `f`.
+    pub fn for_each_type(&mut self, f: &mut dyn FnMut(TypeMut)) {
+        match self {
+            Self::Lit(_) | Self::Var(_) => {}
+            Self::Binary(ExprBinary { left, right, .. }) => {
+                left.for_each_type(f);
+                right.for_each_type(f);
+            }
+            Self::Call(ExprCall { caller, args, .. }) => {
+                caller.for_each_type(f);
+                args.iter_mut().for_each(|arg| arg.for_each_type(f));
+            }
+            Self::MethodCall(ExprMethodCall { receiver, args, .. }) => {
+                receiver.for_each_type(f);
+                args.iter_mut().for_each(|arg| arg.for_each_type(f));
+            }
+            Self::Let(ExprLet { ty, expr, .. }) => {
+                if let Some(ty) = ty {
+                    f(TypeMut::Type(ty));
+                }
+                expr.for_each_type(f);
+            }
+            Self::If(ExprIf {
+                cond,
+                then_branch,
+                else_branch,
+                ..
+            }) => {
+                cond.for_each_type(f);
+                then_branch.for_each_type(f);
+                if let Some((_, else_branch)) = else_branch {
+                    else_branch.for_each_type(f);
+                }
+            }
+            Self::For(ExprFor {
+                iter, end, body, ..
+            }) => {
+                iter.for_each_type(f);
+                if let Some((_, end)) = end {
+                    end.for_each_type(f);
+                }
+                body.for_each_type(f);
+            }
+            Self::Block(block) => block.for_each_type(f),
+            Self::Return(ExprReturn { expr, .. })
+            | Self::Unary(ExprUnary { expr, .. })
+            | Self::Field(ExprField { expr, .. }) => expr.for_each_type(f),
+            Self::Array(ExprArray { elems, .. }) => {
+                elems.iter_mut().for_each(|elem| elem.for_each_type(f));
+            }
+            Self::ArrayRepeat(ExprArrayRepeat { expr, len, .. }) => {
+                expr.for_each_type(f);
+                f(TypeMut::Len(len));
+            }
+            Self::Index(ExprIndex { expr, index, .. }) => {
+                expr.for_each_type(f);
+                index.for_each_type(f);
+            }
+            Self::Assign(ExprAssign { target, value, .. }) => {
+                target.for_each_type(f);
+                value.for_each_type(f);
+            }
+            Self::Struct(ExprStruct { name, fields, .. }) => {
+                f(TypeMut::Struct(name));
+                fields
+                    .iter_mut()
+                    .for_each(|(_, expr)| expr.for_each_type(f));
+            }
+            Self::Closure(ExprClosure {
+                params, ret, body, ..
+            }) => {
+                params
+                    .iter_mut()
+                    .for_each(|param| f(TypeMut::Type(&mut param.kind)));
+                if let Some(ret) = ret {
+                    f(TypeMut::Type(ret));
+                }
+                body.for_each_type(f);
+            }
+            Self::Cast(ExprCast { expr, ty, .. }) => {
+                expr.for_each_type(f);
+                f(TypeMut::Type(ty));
+            }
+        }
     }
 }
 
@@ -26,11 +122,22 @@ impl fmt::Display for Expr {
             Self::Lit(elit) => write!(f, "{elit}"),
             Self::Binary(ebin) => write!(f, "{ebin}"),
             Self::Call(ecall) => write!(f, "{ecall}"),
+            Self::MethodCall(i) => write!(f, "{i}"),
             Self::Var(evar) => write!(f, "{evar}"),
             Self::Let(elet) => write!(f, "{elet}"),
             Self::If(i) => write!(f, "{i}"),
+            Self::For(i) => write!(f, "{i}"),
             Self::Block(i) => write!(f, "{i}"),
             Self::Return(i) => write!(f, "{i}"),
+            Self::Array(i) => write!(f, "{i}"),
+            Self::ArrayRepeat(i) => write!(f, "{i}"),
+            Self::Index(i) => write!(f, "{i}"),
+            Self::Assign(i) => write!(f, "{i}"),
+            Self::Unary(i) => write!(f, "{i}"),
+            Self::Struct(i) => write!(f, "{i}"),
+            Self::Field(i) => write!(f, "{i}"),
+            Self::Closure(i) => write!(f, "{i}"),
+            Self::Cast(i) => write!(f, "{i}"),
         }
     }
 }
@@ -41,11 +148,22 @@ impl Expr {
             Self::Lit(i) => i.span(),
             Self::Binary(i) => i.span(),
             Self::Call(i) => i.span(),
+            Self::MethodCall(i) => i.span(),
             Self::Var(i) => i.span(),
             Self::Let(i) => i.span(),
             Self::If(i) => i.span(),
+            Self::For(i) => i.span(),
             Self::Block(i) => i.span(),
             Self::Return(i) => i.span(),
+            Self::Array(i) => i.span(),
+            Self::ArrayRepeat(i) => i.span(),
+            Self::Index(i) => i.span(),
+            Self::Assign(i) => i.span(),
+            Self::Unary(i) => i.span(),
+            Self::Struct(i) => i.span(),
+            Self::Field(i) => i.span(),
+            Self::Closure(i) => i.span(),
+            Self::Cast(i) => i.span(),
         }
     }
 }
@@ -58,6 +176,14 @@ impl From<super::LitInt> for Expr {
     }
 }
 
+impl From<super::LitFloat> for Expr {
+    fn from(value: super::LitFloat) -> Self {
+        Self::Lit(ExprLit {
+            lit: Lit::from(value),
+        })
+    }
+}
+
 impl From<super::LitBool> for Expr {
     fn from(value: super::LitBool) -> Self {
         Self::Lit(ExprLit {
@@ -106,6 +232,12 @@ impl From<ExprBinary> for Expr {
     }
 }
 
+impl From<ExprMethodCall> for Expr {
+    fn from(expr: ExprMethodCall) -> Self {
+        Self::MethodCall(expr)
+    }
+}
+
 impl From<ExprCall> for Expr {
     fn from(expr: ExprCall) -> Self {
         Self::Call(expr)
@@ -124,6 +256,12 @@ impl From<ExprIf> for Expr {
     }
 }
 
+impl From<ExprFor> for Expr {
+    fn from(expr: ExprFor) -> Self {
+        Self::For(expr)
+    }
+}
+
 impl From<ExprBlock> for Expr {
     fn from(expr: ExprBlock) -> Self {
         Self::Block(expr)
@@ -136,6 +274,60 @@ impl From<ExprReturn> for Expr {
     }
 }
 
+impl From<ExprArray> for Expr {
+    fn from(expr: ExprArray) -> Self {
+        Self::Array(expr)
+    }
+}
+
+impl From<ExprArrayRepeat> for Expr {
+    fn from(expr: ExprArrayRepeat) -> Self {
+        Self::ArrayRepeat(expr)
+    }
+}
+
+impl From<ExprIndex> for Expr {
+    fn from(expr: ExprIndex) -> Self {
+        Self::Index(expr)
+    }
+}
+
+impl From<ExprAssign> for Expr {
+    fn from(expr: ExprAssign) -> Self {
+        Self::Assign(expr)
+    }
+}
+
+impl From<ExprUnary> for Expr {
+    fn from(expr: ExprUnary) -> Self {
+        Self::Unary(expr)
+    }
+}
+
+impl From<ExprStruct> for Expr {
+    fn from(expr: ExprStruct) -> Self {
+        Self::Struct(expr)
+    }
+}
+
+impl From<ExprField> for Expr {
+    fn from(expr: ExprField) -> Self {
+        Self::Field(expr)
+    }
+}
+
+impl From<ExprClosure> for Expr {
+    fn from(expr: ExprClosure) -> Self {
+        Self::Closure(expr)
+    }
+}
+
+impl From<ExprCast> for Expr {
+    fn from(expr: ExprCast) -> Self {
+        Self::Cast(expr)
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ExprLit {
     pub lit: Lit,
@@ -164,6 +356,7 @@ impl ExprLit {
 pub struct ExprLet {
     pub let_token: keyword::Let,
     pub name: Ident,
+    pub ty: Option<Type>,
     pub eq_token: Op,
     pub expr: Box<Expr>,
 }
@@ -173,10 +366,14 @@ impl fmt::Display for ExprLet {
         let Self {
             let_token,
             name,
+            ty,
             eq_token,
             expr,
         } = &self;
-        write!(f, "({let_token} {name} {eq_token} {expr})")
+        match ty {
+            Some(ty) => write!(f, "({let_token} {name}: {ty} {eq_token} {expr})"),
+            None => write!(f, "({let_token} {name} {eq_token} {expr})"),
+        }
     }
 }
 
@@ -184,12 +381,14 @@ impl ExprLet {
     pub fn _new(
         let_token: super::keyword::Let,
         name: Ident,
+        ty: Option<Type>,
         eq_token: Op,
         expr: Box<Expr>,
     ) -> Self {
         Self {
             let_token,
             name,
+            ty,
             eq_token,
             expr,
         }
@@ -233,6 +432,43 @@ impl From<(Expr, Expr, Op)> for ExprBinary {
     }
 }
 
+/// `receiver.name(args)`, calls the method of whichever trait the type of the receiver
+/// implements it in.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExprMethodCall {
+    pub receiver: Box<Expr>,
+    pub dot: super::CtrlDot,
+    pub name: Ident,
+    pub left_paran: super::CtrlLParan,
+    pub args: Vec<Expr>,
+    pub right_paran: super::CtrlRParan,
+}
+
+impl fmt::Display for ExprMethodCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            receiver,
+            name,
+            args,
+            ..
+        } = &self;
+        let args = args.iter().fold(String::new(), |mut acc, i| {
+            acc += &i.to_string();
+            acc += ", ";
+            acc
+        });
+        write!(f, "({receiver}.{name} ({args}))")
+    }
+}
+
+impl ExprMethodCall {
+    pub fn span(&self) -> Span {
+        let start = self.receiver.span();
+        let end = self.right_paran.span();
+        Span::from((start, end))
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ExprCall {
     pub caller: Box<Expr>,
@@ -276,19 +512,24 @@ impl ExprCall {
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ExprVar {
+    /// Modules leading up to the name in `foo::bar`.
+    pub path: Vec<Ident>,
     pub name: Ident,
 }
 
 impl fmt::Display for ExprVar {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let Self { name, .. } = &self;
+        let Self { path, name } = &self;
+        for module in path.iter() {
+            write!(f, "{module}::")?;
+        }
         write!(f, "{name}")
     }
 }
 
 impl ExprVar {
     pub fn new(name: Ident) -> Self {
-        Self { name }
+        Self { path: vec![], name }
     }
 
     pub fn span(&self) -> Span {
@@ -347,6 +588,40 @@ impl ExprIf {
     }
 }
 
+/// `for i in 0..n { ... }` counting up to `n` or `for x in v { ... }` over the items of a `Vec`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExprFor {
+    pub for_token: keyword::For,
+    pub name: Ident,
+    pub in_token: keyword::In,
+    pub iter: Box<Expr>,
+    /// The end of a range, `iter` is where it starts.
+    pub end: Option<(CtrlDotDot, Box<Expr>)>,
+    pub body: ExprBlock,
+}
+
+impl fmt::Display for ExprFor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            name,
+            iter,
+            end,
+            body,
+            ..
+        } = self;
+        match end {
+            Some((_, end)) => write!(f, "for {name} in {iter}..{end} {{\n{body}\n}};"),
+            None => write!(f, "for {name} in {iter} {{\n{body}\n}};"),
+        }
+    }
+}
+
+impl ExprFor {
+    pub fn span(&self) -> Span {
+        Span::from((self.for_token.span(), self.body.span()))
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ExprBlock {
     pub left_brace: super::CtrlLBrace,
@@ -354,6 +629,14 @@ pub struct ExprBlock {
     pub stmts: Vec<super::Statement>,
 }
 
+impl ExprBlock {
+    pub fn for_each_type(&mut self, f: &mut dyn FnMut(TypeMut)) {
+        for stmt in self.stmts.iter_mut() {
+            stmt.stmt.for_each_type(f);
+        }
+    }
+}
+
 impl std::fmt::Display for ExprBlock {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let Self { stmts, .. } = &self;
@@ -413,3 +696,286 @@ impl std::fmt::Display for ExprReturn {
         write!(f, "{ret} {expr}")
     }
 }
+
+/// `[a, b, c]`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExprArray {
+    pub left_bracet: CtrlLBracet,
+    pub elems: Vec<Expr>,
+    pub right_bracet: CtrlRBracet,
+}
+
+impl ExprArray {
+    pub fn new(left_bracet: CtrlLBracet, elems: Vec<Expr>, right_bracet: CtrlRBracet) -> Self {
+        Self {
+            left_bracet,
+            elems,
+            right_bracet,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        Span::from((self.left_bracet.span(), self.right_bracet.span()))
+    }
+}
+
+impl std::fmt::Display for ExprArray {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let elems = self
+            .elems
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "[{elems}]")
+    }
+}
+
+/// `[expr; N]`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExprArrayRepeat {
+    pub left_bracet: CtrlLBracet,
+    pub expr: Box<Expr>,
+    pub len: ArrayLen,
+    pub right_bracet: CtrlRBracet,
+}
+
+impl ExprArrayRepeat {
+    pub fn new(
+        left_bracet: CtrlLBracet,
+        expr: Expr,
+        len: ArrayLen,
+        right_bracet: CtrlRBracet,
+    ) -> Self {
+        Self {
+            left_bracet,
+            expr: Box::new(expr),
+            len,
+            right_bracet,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        Span::from((self.left_bracet.span(), self.right_bracet.span()))
+    }
+}
+
+impl std::fmt::Display for ExprArrayRepeat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { expr, len, .. } = self;
+        write!(f, "[{expr}; {len}]")
+    }
+}
+
+/// `expr[index]`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExprIndex {
+    pub expr: Box<Expr>,
+    pub left_bracet: CtrlLBracet,
+    pub index: Box<Expr>,
+    pub right_bracet: CtrlRBracet,
+}
+
+impl ExprIndex {
+    pub fn new(
+        expr: Expr,
+        left_bracet: CtrlLBracet,
+        index: Expr,
+        right_bracet: CtrlRBracet,
+    ) -> Self {
+        Self {
+            expr: Box::new(expr),
+            left_bracet,
+            index: Box::new(index),
+            right_bracet,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        Span::from((self.expr.span(), self.right_bracet.span()))
+    }
+}
+
+impl std::fmt::Display for ExprIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { expr, index, .. } = self;
+        write!(f, "({expr}[{index}])")
+    }
+}
+
+/// `target = value`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExprAssign {
+    pub target: Box<Expr>,
+    pub eq_token: Op,
+    pub value: Box<Expr>,
+}
+
+impl ExprAssign {
+    pub fn new(target: Expr, eq_token: Op, value: Expr) -> Self {
+        Self {
+            target: Box::new(target),
+            eq_token,
+            value: Box::new(value),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        Span::from((self.target.span(), self.value.span()))
+    }
+}
+
+impl std::fmt::Display for ExprAssign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            target,
+            eq_token,
+            value,
+        } = self;
+        write!(f, "({eq_token} {target} {value})")
+    }
+}
+
+/// `&x`, `&mut x` or `*p`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExprUnary {
+    pub op: Op,
+    pub mutable: Option<keyword::Mut>,
+    pub expr: Box<Expr>,
+}
+
+impl ExprUnary {
+    pub fn new(op: Op, mutable: Option<keyword::Mut>, expr: Expr) -> Self {
+        Self {
+            op,
+            mutable,
+            expr: Box::new(expr),
+        }
+    }
+
+    pub fn is_ref(&self) -> bool {
+        matches!(self.op, Op::Ref(_))
+    }
+
+    pub fn is_deref(&self) -> bool {
+        matches!(self.op, Op::Mul(_))
+    }
+
+    pub fn span(&self) -> Span {
+        Span::from((self.op.span(), self.expr.span()))
+    }
+}
+
+impl std::fmt::Display for ExprUnary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { op, mutable, expr } = self;
+        match mutable {
+            Some(_) => write!(f, "({op}mut {expr})"),
+            None => write!(f, "({op} {expr})"),
+        }
+    }
+}
+
+/// `Point { x: 1, y: 2 }`, a value of a struct with every one of its fields given.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExprStruct {
+    pub name: Ident,
+    pub left_brace: CtrlLBrace,
+    pub fields: Vec<(Ident, Expr)>,
+    pub right_brace: CtrlRBrace,
+}
+
+impl ExprStruct {
+    pub fn span(&self) -> Span {
+        Span::from((self.name.span, self.right_brace.span))
+    }
+}
+
+impl std::fmt::Display for ExprStruct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { name, fields, .. } = self;
+        let fields = fields.iter().fold(String::new(), |mut acc, (field, expr)| {
+            acc += &format!("({field}: {expr})");
+            acc
+        });
+        write!(f, "({name} {{{fields}}})")
+    }
+}
+
+/// `expr.name`, a field of a struct or of the struct a pointer points to.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExprField {
+    pub expr: Box<Expr>,
+    pub dot: CtrlDot,
+    pub name: Ident,
+}
+
+impl ExprField {
+    pub fn span(&self) -> Span {
+        Span::from((self.expr.span(), self.name.span))
+    }
+}
+
+impl std::fmt::Display for ExprField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}.{})", self.expr, self.name)
+    }
+}
+
+/// `|x: u64, y| -> u64 x + y + offset`, a function value that can use the locals around it.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExprClosure {
+    pub left_pipe: CtrlPipe,
+    pub params: Vec<Param>,
+    pub right_pipe: CtrlPipe,
+    pub ret: Option<Type>,
+    pub body: Box<Expr>,
+}
+
+impl ExprClosure {
+    pub fn span(&self) -> Span {
+        Span::from((self.left_pipe.span, self.body.span()))
+    }
+}
+
+impl std::fmt::Display for ExprClosure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            params, ret, body, ..
+        } = self;
+        let params = params.iter().map(ToString::to_string).collect::<String>();
+        match ret {
+            Some(ret) => write!(f, "(|{params}| -> {ret} {body})"),
+            None => write!(f, "(|{params}| {body})"),
+        }
+    }
+}
+
+/// `x as f64`, a value converted to another numeric type.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExprCast {
+    pub expr: Box<Expr>,
+    pub keyword_as: keyword::As,
+    pub ty: Type,
+}
+
+impl ExprCast {
+    pub fn new(expr: Expr, keyword_as: keyword::As, ty: Type) -> Self {
+        Self {
+            expr: Box::new(expr),
+            keyword_as,
+            ty,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        Span::from((self.expr.span(), self.ty.span()))
+    }
+}
+
+impl std::fmt::Display for ExprCast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { expr, ty, .. } = self;
+        write!(f, "({expr} as {ty})")
+    }
+}
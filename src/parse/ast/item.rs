@@ -1,44 +1,275 @@
 use crate::lexer::{Span, Token};
 
-use super::{ExprBlock, Ident, Param, Type};
+use super::{ArrayLen, Expr, ExprBlock, Ident, LitStr, Param, Type, TypeMut};
 use std::fmt;
 
+// NOTE: almost every item is a function so boxing them would not save anything.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Item {
     Fn(ItemFn),
+    Extern(ItemExtern),
+    Use(ItemUse),
+    Const(ItemConst),
+    Static(ItemStatic),
+    Trait(ItemTrait),
+    Impl(ItemImpl),
+    Struct(ItemStruct),
+}
+
+impl Item {
+    /// Hands every type, `[expr; N]` length and struct literal name in the item to `f`, the
+    /// name of a struct or trait it declares or implements is not one of them.
+    pub fn for_each_type(&mut self, f: &mut dyn FnMut(TypeMut)) {
+        match self {
+            Self::Fn(item_fn) => item_fn.for_each_type(f),
+            Self::Extern(ItemExtern {
+                params, ret_type, ..
+            }) => fn_sig_types(params, ret_type, f),
+            Self::Use(_) => {}
+            Self::Const(ItemConst { ty, expr, .. }) | Self::Static(ItemStatic { ty, expr, .. }) => {
+                f(TypeMut::Type(ty));
+                expr.for_each_type(f);
+            }
+            Self::Trait(item_trait) => {
+                for TraitFn {
+                    params, ret_type, ..
+                } in item_trait.fns.iter_mut()
+                {
+                    fn_sig_types(params, ret_type, f);
+                }
+            }
+            Self::Impl(item_impl) => {
+                f(TypeMut::Type(&mut item_impl.ty));
+                item_impl
+                    .fns
+                    .iter_mut()
+                    .for_each(|item_fn| item_fn.for_each_type(f));
+            }
+            Self::Struct(item_struct) => {
+                for field in item_struct.fields.iter_mut() {
+                    f(TypeMut::Type(&mut field.kind));
+                }
+            }
+        }
+    }
+
+    /// Hands every array length written in the item to `f`.
+    pub fn for_each_len(&mut self, f: &mut dyn FnMut(&mut ArrayLen)) {
+        self.for_each_type(&mut |ty| match ty {
+            TypeMut::Type(ty) => ty.for_each_len(f),
+            TypeMut::Len(len) => f(len),
+            TypeMut::Struct(_) => {}
+        });
+    }
+}
+
+fn fn_sig_types(params: &mut [Param], ret: &mut Option<Type>, f: &mut dyn FnMut(TypeMut)) {
+    for param in params.iter_mut() {
+        f(TypeMut::Type(&mut param.kind));
+    }
+    if let Some(ret) = ret {
+        f(TypeMut::Type(ret));
+    }
 }
 
 impl fmt::Display for Item {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Fn(item_fn) => write!(f, "{item_fn}"),
+            Self::Extern(item_extern) => write!(f, "{item_extern}"),
+            Self::Use(item_use) => write!(f, "{item_use}"),
+            Self::Const(item_const) => write!(f, "{item_const}"),
+            Self::Static(item_static) => write!(f, "{item_static}"),
+            Self::Trait(item_trait) => write!(f, "{item_trait}"),
+            Self::Impl(item_impl) => write!(f, "{item_impl}"),
+            Self::Struct(item_struct) => write!(f, "{item_struct}"),
+        }
+    }
+}
+
+impl Item {
+    pub fn as_fn(&self) -> Option<&ItemFn> {
+        match self {
+            Self::Fn(item_fn) => Some(item_fn),
+            _ => None,
+        }
+    }
+
+    /// What the item is declared as, `None` for a `use` or an `impl`.
+    pub fn name(&self) -> Option<&Ident> {
+        match self {
+            Self::Fn(item_fn) => Some(&item_fn.name),
+            Self::Extern(item_extern) => Some(&item_extern.name),
+            Self::Use(_) | Self::Impl(_) => None,
+            Self::Const(item_const) => Some(&item_const.name),
+            Self::Static(item_static) => Some(&item_static.name),
+            Self::Trait(item_trait) => Some(&item_trait.name),
+            Self::Struct(item_struct) => Some(&item_struct.name),
+        }
+    }
+
+    pub fn is_pub(&self) -> bool {
+        match self {
+            Self::Fn(item_fn) => item_fn.is_pub(),
+            Self::Extern(_) | Self::Use(_) | Self::Impl(_) => false,
+            Self::Const(item_const) => item_const.visibility.is_some(),
+            Self::Static(item_static) => item_static.visibility.is_some(),
+            Self::Trait(item_trait) => item_trait.visibility.is_some(),
+            Self::Struct(item_struct) => item_struct.visibility.is_some(),
         }
     }
 }
 
+/// `const N: u64 = 10;` is worked out while compiling and every use of it is replaced by the
+/// value.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ItemConst {
+    pub visibility: Option<super::keyword::Pub>,
+    pub keyword_const: super::keyword::Const,
+    pub name: Ident,
+    pub ty: Type,
+    pub expr: Expr,
+}
+
+impl ItemConst {
+    pub fn new(
+        visibility: Option<super::keyword::Pub>,
+        keyword_const: super::keyword::Const,
+        name: Ident,
+        ty: Type,
+        expr: Expr,
+    ) -> Self {
+        Self {
+            visibility,
+            keyword_const,
+            name,
+            ty,
+            expr,
+        }
+    }
+}
+
+impl fmt::Display for ItemConst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            visibility,
+            name,
+            ty,
+            expr,
+            ..
+        } = &self;
+        let visibility = visibility.map(|_| "pub ").unwrap_or_default();
+        write!(f, "({visibility}const {name}: {ty} = {expr})")
+    }
+}
+
+/// `static N: u64 = 10;` lives in the data segment for as long as the program runs, its
+/// initial value is worked out like the one of a `const`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ItemStatic {
+    pub visibility: Option<super::keyword::Pub>,
+    pub keyword_static: super::keyword::Static,
+    pub name: Ident,
+    pub ty: Type,
+    pub expr: Expr,
+}
+
+impl ItemStatic {
+    pub fn new(
+        visibility: Option<super::keyword::Pub>,
+        keyword_static: super::keyword::Static,
+        name: Ident,
+        ty: Type,
+        expr: Expr,
+    ) -> Self {
+        Self {
+            visibility,
+            keyword_static,
+            name,
+            ty,
+            expr,
+        }
+    }
+}
+
+impl fmt::Display for ItemStatic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            visibility,
+            name,
+            ty,
+            expr,
+            ..
+        } = &self;
+        let visibility = visibility.map(|_| "pub ").unwrap_or_default();
+        write!(f, "({visibility}static {name}: {ty} = {expr})")
+    }
+}
+
+/// `use foo;` pulls in the module in `foo.a` next to the file it is in.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ItemUse {
+    pub keyword_use: super::keyword::Use,
+    pub name: Ident,
+}
+
+impl ItemUse {
+    pub fn new(keyword_use: super::keyword::Use, name: Ident) -> Self {
+        Self { keyword_use, name }
+    }
+}
+
+impl fmt::Display for ItemUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(use {})", self.name)
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ItemFn {
+    pub visibility: Option<super::keyword::Pub>,
     pub keyword_fn: super::keyword::Fn,
     pub name: Ident,
+    /// Type parameters, `T` in `fn max<T>(a: T, b: T) -> T`.
+    pub generics: Vec<Generic>,
     pub params: Vec<Param>,
     pub block: ExprBlock,
     pub ret_type: Option<Type>,
+    /// The file it is written in, filled in once the modules are linked.
+    pub file: Option<String>,
+    /// `pub extern "C" fn`, exported to C under its own name.
+    pub keyword_extern: Option<super::keyword::Extern>,
+    /// The calling convention it is exported with, `"C"` when it is left out.
+    pub abi: Option<LitStr>,
 }
 
 impl ItemFn {
+    pub fn for_each_type(&mut self, f: &mut dyn FnMut(TypeMut)) {
+        fn_sig_types(&mut self.params, &mut self.ret_type, f);
+        self.block.for_each_type(f);
+    }
+
     pub fn new(
+        visibility: Option<super::keyword::Pub>,
         keyword_fn: super::keyword::Fn,
         name: Ident,
+        generics: Vec<Generic>,
         params: Vec<Param>,
         block: ExprBlock,
         ret_type: Option<Type>,
     ) -> Self {
         Self {
+            visibility,
             keyword_fn,
             name,
+            generics,
             params,
             block,
             ret_type,
+            file: None,
+            keyword_extern: None,
+            abi: None,
         }
     }
 
@@ -47,22 +278,222 @@ impl ItemFn {
         let end = self.block.span();
         Span::from((start, end))
     }
+
+    pub fn is_pub(&self) -> bool {
+        self.visibility.is_some()
+    }
+
+    pub fn is_generic(&self) -> bool {
+        !self.generics.is_empty()
+    }
+
+    pub fn is_export(&self) -> bool {
+        self.keyword_extern.is_some()
+    }
 }
 
 impl fmt::Display for ItemFn {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let Self {
+            visibility,
             name,
+            generics,
             params,
             block,
             ret_type,
+            keyword_extern,
             ..
         } = &self;
+        let generics = fmt_generics(generics);
         let ret = ret_type
             .as_ref()
             .map(ToString::to_string)
             .unwrap_or("NULL".into());
         let params = params.iter().map(ToString::to_string).collect::<String>();
-        write!(f, "(func {name} <{ret}> ({params}){block})")
+        let visibility = visibility.map(|_| "pub ").unwrap_or_default();
+        let export = keyword_extern.map(|_| "extern ").unwrap_or_default();
+        write!(
+            f,
+            "({visibility}{export}func {name}{generics} <{ret}> ({params}){block})"
+        )
+    }
+}
+
+/// `extern "C" fn puts(s: *u8) -> i32;`, a function defined outside of the program that it is
+/// linked against. It is called the way System V says a C function is.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ItemExtern {
+    pub keyword_extern: super::keyword::Extern,
+    /// The calling convention, `"C"` when it is left out.
+    pub abi: Option<LitStr>,
+    pub keyword_fn: super::keyword::Fn,
+    pub name: Ident,
+    pub params: Vec<Param>,
+    pub ret_type: Option<Type>,
+}
+
+impl fmt::Display for ItemExtern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            abi,
+            name,
+            params,
+            ret_type,
+            ..
+        } = &self;
+        let abi = abi
+            .as_ref()
+            .map(|abi| format!("\"{abi}\" "))
+            .unwrap_or_default();
+        let ret = ret_type
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or("NULL".into());
+        let params = params.iter().map(ToString::to_string).collect::<String>();
+        write!(f, "(extern {abi}func {name} <{ret}> ({params}))")
+    }
+}
+
+/// A type parameter and the traits every type it stands for has to implement, `T: Show`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Generic {
+    pub name: Ident,
+    pub bounds: Vec<Ident>,
+}
+
+impl fmt::Display for Generic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { name, bounds } = self;
+        if bounds.is_empty() {
+            return write!(f, "{name}");
+        }
+        let bounds = bounds.iter().map(ToString::to_string);
+        write!(f, "{name}: {}", bounds.collect::<Vec<_>>().join(" + "))
+    }
+}
+
+/// `<T: Show, U>`, nothing when there are no type parameters.
+fn fmt_generics(generics: &[Generic]) -> String {
+    match generics.is_empty() {
+        true => String::new(),
+        false => {
+            let names = generics.iter().map(ToString::to_string);
+            format!("<{}>", names.collect::<Vec<_>>().join(", "))
+        }
+    }
+}
+
+/// `trait Show { fn show(self); }`, the methods a type has to have to implement it. Inside of
+/// it `Self` is the type implementing it.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ItemTrait {
+    pub visibility: Option<super::keyword::Pub>,
+    pub keyword_trait: super::keyword::Trait,
+    pub name: Ident,
+    pub fns: Vec<TraitFn>,
+}
+
+impl fmt::Display for ItemTrait {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            visibility,
+            name,
+            fns,
+            ..
+        } = &self;
+        let visibility = visibility.map(|_| "pub ").unwrap_or_default();
+        let fns = fns.iter().map(ToString::to_string).collect::<String>();
+        write!(f, "({visibility}trait {name} {fns})")
+    }
+}
+
+/// A method of a trait, without a body.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TraitFn {
+    pub keyword_fn: super::keyword::Fn,
+    pub name: Ident,
+    pub params: Vec<Param>,
+    pub ret_type: Option<Type>,
+}
+
+impl fmt::Display for TraitFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            name,
+            params,
+            ret_type,
+            ..
+        } = &self;
+        let ret = ret_type
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or("NULL".into());
+        let params = params.iter().map(ToString::to_string).collect::<String>();
+        write!(f, "(func {name} <{ret}> ({params}))")
+    }
+}
+
+/// `impl Show for u64 { ... }`, the methods of a trait for one type, or `impl u64 { ... }` for
+/// methods and associated functions of the type itself.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ItemImpl {
+    pub keyword_impl: super::keyword::Impl,
+    /// `T` in `impl<T> Vec<T>`, each one is an argument of the type in the same order.
+    pub generics: Vec<Generic>,
+    pub of_trait: Option<(Ident, super::keyword::For)>,
+    pub ty: Type,
+    pub fns: Vec<ItemFn>,
+}
+
+impl ItemImpl {
+    pub fn trait_name(&self) -> Option<&str> {
+        self.of_trait.as_ref().map(|(name, _)| name.value.as_str())
+    }
+}
+
+impl fmt::Display for ItemImpl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            generics,
+            of_trait,
+            ty,
+            fns,
+            ..
+        } = &self;
+        let generics = fmt_generics(generics);
+        let of_trait = of_trait
+            .as_ref()
+            .map(|(name, _)| format!("{name} for "))
+            .unwrap_or_default();
+        let fns = fns.iter().map(ToString::to_string).collect::<String>();
+        write!(f, "(impl{generics} {of_trait}{ty} {fns})")
+    }
+}
+
+/// `struct Point { x: u64, y: u64 }`, a type made of named fields. Its values live in memory
+/// and are copied field by field when they are assigned, passed or returned.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ItemStruct {
+    pub visibility: Option<super::keyword::Pub>,
+    pub keyword_struct: super::keyword::Struct,
+    pub name: Ident,
+    /// Type parameters, `T` in `struct Vec<T>`.
+    pub generics: Vec<Generic>,
+    pub fields: Vec<Param>,
+}
+
+impl fmt::Display for ItemStruct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            visibility,
+            name,
+            generics,
+            fields,
+            ..
+        } = &self;
+        let visibility = visibility.map(|_| "pub ").unwrap_or_default();
+        let generics = fmt_generics(generics);
+        let fields = fields.iter().map(ToString::to_string).collect::<String>();
+        write!(f, "({visibility}struct {name}{generics} {fields})")
     }
 }
@@ -25,11 +25,19 @@ macro_rules! keyword {
     };
 }
 keyword!(Use);
+keyword!(Pub);
+keyword!(Const);
+keyword!(Static);
+keyword!(Trait);
+keyword!(Impl);
+keyword!(For);
+keyword!(In);
 keyword!(Let);
+keyword!(Mut);
 keyword!(Struct);
-keyword!(True);
-keyword!(False);
 keyword!(Fn);
+keyword!(Extern);
 keyword!(If);
 keyword!(Else);
 keyword!(Return);
+keyword!(As);
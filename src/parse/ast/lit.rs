@@ -6,6 +6,7 @@ use std::fmt;
 
 pub enum Lit {
     Int(LitInt),
+    Float(LitFloat),
     Bool(LitBool),
     Str(LitStr),
     Char(LitChar),
@@ -15,6 +16,7 @@ impl fmt::Display for Lit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Int(i) => write!(f, "{i}"),
+            Self::Float(i) => write!(f, "{i}"),
             Self::Bool(i) => write!(f, "{i}"),
             Self::Str(i) => write!(f, "{i}"),
             Self::Char(i) => write!(f, "{i}"),
@@ -26,6 +28,7 @@ impl Lit {
     pub fn span(&self) -> Span {
         match self {
             Self::Int(i) => i.span,
+            Self::Float(i) => i.span,
             Self::Bool(i) => i.span,
             Self::Str(i) => i.span,
             Self::Char(i) => i.span,
@@ -34,11 +37,88 @@ impl Lit {
 }
 
 token!(LitInt);
+token!(LitFloat);
 token!(LitBool);
 token!(LitStr);
 token!(LitChar);
 
 from_token!(Lit, Int, LitInt);
+from_token!(Lit, Float, LitFloat);
 from_token!(Lit, Bool, LitBool);
 from_token!(Lit, Str, LitStr);
 from_token!(Lit, Char, LitChar);
+
+impl LitStr {
+    /// The contents with escape sequences like `\n` replaced.
+    pub fn unescape(&self) -> String {
+        unescape(&self.value)
+    }
+
+    /// The first escape sequence that does not stand for anything, like `\x`.
+    pub fn invalid_escape(&self) -> Option<String> {
+        invalid_escape(&self.value)
+    }
+}
+
+impl LitChar {
+    /// `None` unless the literal holds exactly one character.
+    pub fn to_char(&self) -> Option<char> {
+        let value = unescape(&self.value);
+        let mut chars = value.chars();
+        let c = chars.next()?;
+        chars.next().is_none().then_some(c)
+    }
+
+    pub fn invalid_escape(&self) -> Option<String> {
+        invalid_escape(&self.value)
+    }
+}
+
+fn escape(c: char) -> Option<char> {
+    match c {
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        '0' => Some('\0'),
+        '\\' | '"' | '\'' => Some(c),
+        _ => None,
+    }
+}
+
+fn invalid_escape(value: &str) -> Option<String> {
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            continue;
+        }
+        match chars.next() {
+            Some(c) if escape(c).is_some() => {}
+            Some(c) => return Some(format!("\\{c}")),
+            None => return Some("\\".into()),
+        }
+    }
+    None
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        // NOTE: an invalid escape is reported in semantic analysis, it is kept as it is.
+        match chars.next() {
+            Some(c) => match escape(c) {
+                Some(c) => result.push(c),
+                None => {
+                    result.push('\\');
+                    result.push(c);
+                }
+            },
+            None => result.push('\\'),
+        }
+    }
+    result
+}
@@ -5,10 +5,15 @@ mod lit;
 
 use crate::lexer::Span;
 pub use expr::{
-    Expr, ExprBinary, ExprBlock, ExprCall, ExprIf, ExprLet, ExprLit, ExprReturn, ExprVar,
+    Expr, ExprArray, ExprArrayRepeat, ExprAssign, ExprBinary, ExprBlock, ExprCall, ExprCast,
+    ExprClosure, ExprField, ExprFor, ExprIf, ExprIndex, ExprLet, ExprLit, ExprMethodCall,
+    ExprReturn, ExprStruct, ExprUnary, ExprVar,
 };
-pub use item::{Item, ItemFn};
-pub use lit::{Lit, LitBool, LitChar, LitInt, LitStr};
+pub use item::{
+    Generic, Item, ItemConst, ItemExtern, ItemFn, ItemImpl, ItemStatic, ItemStruct, ItemTrait,
+    ItemUse, TraitFn,
+};
+pub use lit::{Lit, LitBool, LitChar, LitFloat, LitInt, LitStr};
 
 #[macro_export]
 macro_rules! token {
@@ -90,6 +95,7 @@ token!(OpGeq);
 token!(OpLeq);
 token!(OpNeq);
 token!(OpNot);
+token!(OpRef);
 token!(OpEqual);
 token!(OpEqualEqual);
 
@@ -105,6 +111,7 @@ pub enum Op {
     Leq(OpLeq),
     Neq(OpNeq),
     Not(OpNot),
+    Ref(OpRef),
     Equal(OpEqual),
     EqualEqual(OpEqualEqual),
 }
@@ -122,6 +129,7 @@ impl std::fmt::Display for Op {
             Self::Leq(op) => write!(f, "{op}"),
             Self::Neq(op) => write!(f, "{op}"),
             Self::Not(op) => write!(f, "{op}"),
+            Self::Ref(op) => write!(f, "{op}"),
             Self::Equal(op) => write!(f, "{op}"),
             Self::EqualEqual(op) => write!(f, "{op}"),
         }
@@ -138,15 +146,51 @@ from_token!(Op, Geq, OpGeq);
 from_token!(Op, Leq, OpLeq);
 from_token!(Op, Neq, OpNeq);
 from_token!(Op, Not, OpNot);
+from_token!(Op, Ref, OpRef);
 from_token!(Op, Equal, OpEqual);
 from_token!(Op, EqualEqual, OpEqualEqual);
 
+impl Op {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Add(op) => op.span,
+            Self::Sub(op) => op.span,
+            Self::Mul(op) => op.span,
+            Self::Div(op) => op.span,
+            Self::Grt(op) => op.span,
+            Self::Les(op) => op.span,
+            Self::Geq(op) => op.span,
+            Self::Leq(op) => op.span,
+            Self::Neq(op) => op.span,
+            Self::Not(op) => op.span,
+            Self::Ref(op) => op.span,
+            Self::Equal(op) => op.span,
+            Self::EqualEqual(op) => op.span,
+        }
+    }
+
+    /// `<`, `>`, `<=`, `>=`, `==` and `!=`, they give a `bool`.
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Self::Grt(_)
+                | Self::Les(_)
+                | Self::Geq(_)
+                | Self::Leq(_)
+                | Self::Neq(_)
+                | Self::EqualEqual(_)
+        )
+    }
+}
+
 token!(CtrlStar);
 token!(CtrlSlash);
 token!(CtrlSemiColon);
 token!(CtrlColon);
+token!(CtrlColonColon);
 token!(CtrlComma);
 token!(CtrlDot);
+token!(CtrlDotDot);
 token!(CtrlLBrace);
 token!(CtrlRBrace);
 token!(CtrlLBracet);
@@ -155,6 +199,7 @@ token!(CtrlLParan);
 token!(CtrlRParan);
 token!(CtrlRightArrow);
 token!(CtrlThickRightArrow);
+token!(CtrlPipe);
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Ctrl {
@@ -162,8 +207,10 @@ pub enum Ctrl {
     Slash(CtrlSlash),                     // /
     SemiColon(CtrlSemiColon),             // ;
     Colon(CtrlColon),                     // :
+    ColonColon(CtrlColonColon),           // ::
     Comma(CtrlComma),                     // ,
     Dot(CtrlDot),                         // .
+    DotDot(CtrlDotDot),                   // ..
     LBrace(CtrlLBrace),                   // {
     RBrace(CtrlRBrace),                   // }
     LBracet(CtrlLBracet),                 // [
@@ -172,6 +219,7 @@ pub enum Ctrl {
     RParan(CtrlRParan),                   // )
     RightArrow(CtrlRightArrow),           // ->
     ThickRightArrow(CtrlThickRightArrow), // =>
+    Pipe(CtrlPipe),                       // |
 }
 
 impl std::fmt::Display for Ctrl {
@@ -181,8 +229,10 @@ impl std::fmt::Display for Ctrl {
             Self::Slash(ctrl) => write!(f, "{ctrl}"),
             Self::SemiColon(ctrl) => write!(f, "{ctrl}"),
             Self::Colon(ctrl) => write!(f, "{ctrl}"),
+            Self::ColonColon(ctrl) => write!(f, "{ctrl}"),
             Self::Comma(ctrl) => write!(f, "{ctrl}"),
             Self::Dot(ctrl) => write!(f, "{ctrl}"),
+            Self::DotDot(ctrl) => write!(f, "{ctrl}"),
             Self::LBrace(ctrl) => write!(f, "{ctrl}"),
             Self::RBrace(ctrl) => write!(f, "{ctrl}"),
             Self::LBracet(ctrl) => write!(f, "{ctrl}"),
@@ -191,6 +241,7 @@ impl std::fmt::Display for Ctrl {
             Self::RParan(ctrl) => write!(f, "{ctrl}"),
             Self::RightArrow(ctrl) => write!(f, "{ctrl}"),
             Self::ThickRightArrow(ctrl) => write!(f, "{ctrl}"),
+            Self::Pipe(ctrl) => write!(f, "{ctrl}"),
         }
     }
 }
@@ -199,8 +250,10 @@ from_token!(Ctrl, Star, CtrlStar);
 from_token!(Ctrl, Slash, CtrlSlash);
 from_token!(Ctrl, SemiColon, CtrlSemiColon);
 from_token!(Ctrl, Colon, CtrlColon);
+from_token!(Ctrl, ColonColon, CtrlColonColon);
 from_token!(Ctrl, Comma, CtrlComma);
 from_token!(Ctrl, Dot, CtrlDot);
+from_token!(Ctrl, DotDot, CtrlDotDot);
 from_token!(Ctrl, LBrace, CtrlLBrace);
 from_token!(Ctrl, RBrace, CtrlRBrace);
 from_token!(Ctrl, LBracet, CtrlLBracet);
@@ -209,6 +262,7 @@ from_token!(Ctrl, LParan, CtrlLParan);
 from_token!(Ctrl, RParan, CtrlRParan);
 from_token!(Ctrl, RightArrow, CtrlRightArrow);
 from_token!(Ctrl, ThickRightArrow, CtrlThickRightArrow);
+from_token!(Ctrl, Pipe, CtrlPipe);
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Statement {
@@ -224,17 +278,273 @@ impl std::fmt::Display for Statement {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Type(pub Ident);
+pub enum Type {
+    Name(Ident),
+    /// A generic struct with the types its type parameters stand for, `Vec<u64>`.
+    Generic(TypeGeneric),
+    Array(TypeArray),
+    Ptr(TypePtr),
+    Fn(TypeFn),
+    Closure(TypeClosure),
+}
+
+/// A place in the ast that names a type, handed out by the `for_each_type` of an item or an
+/// expression.
+pub enum TypeMut<'a> {
+    Type(&'a mut Type),
+    /// The `N` of `[expr; N]`, the lengths in a type are found through the type.
+    Len(&'a mut ArrayLen),
+    /// The struct a struct literal makes.
+    Struct(&'a mut Ident),
+}
+
+impl Type {
+    /// Hands every struct or type parameter named in the type to `f`.
+    pub fn for_each_name(&mut self, f: &mut dyn FnMut(&mut Ident)) {
+        match self {
+            Self::Name(name) => f(name),
+            Self::Generic(generic) => {
+                f(&mut generic.name);
+                generic.args.iter_mut().for_each(|ty| ty.for_each_name(f));
+            }
+            Self::Array(array) => array.elem.for_each_name(f),
+            Self::Ptr(ptr) => ptr.elem.for_each_name(f),
+            Self::Fn(TypeFn { params, ret, .. })
+            | Self::Closure(TypeClosure { params, ret, .. }) => {
+                params.iter_mut().for_each(|ty| ty.for_each_name(f));
+                if let Some(ret) = ret {
+                    ret.for_each_name(f);
+                }
+            }
+        }
+    }
+
+    /// Hands every array length in the type to `f`.
+    pub fn for_each_len(&mut self, f: &mut dyn FnMut(&mut ArrayLen)) {
+        match self {
+            Self::Name(_) => {}
+            Self::Generic(generic) => generic.args.iter_mut().for_each(|ty| ty.for_each_len(f)),
+            Self::Array(array) => {
+                array.elem.for_each_len(f);
+                f(&mut array.len);
+            }
+            Self::Ptr(ptr) => ptr.elem.for_each_len(f),
+            Self::Fn(TypeFn { params, ret, .. })
+            | Self::Closure(TypeClosure { params, ret, .. }) => {
+                params.iter_mut().for_each(|ty| ty.for_each_len(f));
+                if let Some(ret) = ret {
+                    ret.for_each_len(f);
+                }
+            }
+        }
+    }
+}
+
 impl std::fmt::Display for Type {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let Self(ident) = &self;
-        write!(f, "({ident})")
+        match self {
+            Self::Name(ident) => write!(f, "({ident})"),
+            Self::Generic(generic) => write!(f, "{generic}"),
+            Self::Array(array) => write!(f, "{array}"),
+            Self::Ptr(ptr) => write!(f, "{ptr}"),
+            Self::Fn(func) => write!(f, "{func}"),
+            Self::Closure(closure) => write!(f, "{closure}"),
+        }
+    }
+}
+
+impl Type {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Name(ident) => ident.span,
+            Self::Generic(generic) => generic.span(),
+            Self::Array(array) => array.span(),
+            Self::Ptr(ptr) => ptr.span(),
+            Self::Fn(func) => func.span(),
+            Self::Closure(closure) => closure.span(),
+        }
     }
 }
 
 impl From<&Ident> for Type {
     fn from(value: &Ident) -> Self {
-        Self(value.clone())
+        Self::Name(value.clone())
+    }
+}
+
+/// `Name<T, U>`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TypeGeneric {
+    pub name: Ident,
+    pub args: Vec<Type>,
+    pub right_angle: Op,
+}
+
+impl std::fmt::Display for TypeGeneric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { name, args, .. } = &self;
+        let args = args
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "({name}<{args}>)")
+    }
+}
+
+impl TypeGeneric {
+    pub fn span(&self) -> Span {
+        Span::from((self.name.span, self.right_angle.span()))
+    }
+}
+
+/// `[T; N]`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TypeArray {
+    pub left_bracet: CtrlLBracet,
+    pub elem: Box<Type>,
+    pub len: ArrayLen,
+    pub right_bracet: CtrlRBracet,
+}
+
+impl std::fmt::Display for TypeArray {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { elem, len, .. } = &self;
+        write!(f, "([{elem}; {len}])")
+    }
+}
+
+impl TypeArray {
+    pub fn span(&self) -> Span {
+        Span::from((self.left_bracet.span, self.right_bracet.span))
+    }
+}
+
+/// The `N` of `[T; N]` and `[expr; N]`, a number or the name of a `const`. A `const` is
+/// replaced by its value before the program is checked.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ArrayLen {
+    Lit(LitInt),
+    Const(Ident),
+}
+
+impl std::fmt::Display for ArrayLen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lit(lit) => write!(f, "{lit}"),
+            Self::Const(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl ArrayLen {
+    /// How many elements the array has, a `const` that was not replaced yet has none.
+    pub fn value(&self) -> usize {
+        match self {
+            Self::Lit(lit) => lit.parse::<usize>().unwrap_or_default(),
+            Self::Const(_) => 0,
+        }
+    }
+}
+
+/// `&T`, `&mut T` or `*T`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TypePtr {
+    pub sigil: Op,
+    pub mutable: Option<keyword::Mut>,
+    pub elem: Box<Type>,
+}
+
+impl std::fmt::Display for TypePtr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            sigil,
+            mutable,
+            elem,
+        } = &self;
+        match mutable {
+            Some(_) => write!(f, "({sigil}mut {elem})"),
+            None => write!(f, "({sigil}{elem})"),
+        }
+    }
+}
+
+impl TypePtr {
+    /// Raw pointers and `&mut` references can be written through.
+    pub fn is_mutable(&self) -> bool {
+        self.mutable.is_some() || matches!(self.sigil, Op::Mul(_))
+    }
+
+    pub fn span(&self) -> Span {
+        Span::from((self.sigil.span(), self.elem.span()))
+    }
+}
+
+/// `fn(T, U) -> R`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TypeFn {
+    pub keyword_fn: keyword::Fn,
+    pub params: Vec<Type>,
+    pub right_paran: CtrlRParan,
+    pub ret: Option<Box<Type>>,
+}
+
+impl std::fmt::Display for TypeFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { params, ret, .. } = &self;
+        let params = params
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        match ret {
+            Some(ret) => write!(f, "(fn({params}) -> {ret})"),
+            None => write!(f, "(fn({params}))"),
+        }
+    }
+}
+
+impl TypeFn {
+    pub fn span(&self) -> Span {
+        let end = match &self.ret {
+            Some(ret) => ret.span(),
+            None => self.right_paran.span,
+        };
+        Span::from((self.keyword_fn.0, end))
+    }
+}
+
+/// `|T, U| -> R`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TypeClosure {
+    pub left_pipe: CtrlPipe,
+    pub params: Vec<Type>,
+    pub right_pipe: CtrlPipe,
+    pub ret: Option<Box<Type>>,
+}
+
+impl std::fmt::Display for TypeClosure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { params, ret, .. } = &self;
+        let params = params
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        match ret {
+            Some(ret) => write!(f, "(|{params}| -> {ret})"),
+            None => write!(f, "(|{params}|)"),
+        }
+    }
+}
+
+impl TypeClosure {
+    pub fn span(&self) -> Span {
+        let end = match &self.ret {
+            Some(ret) => ret.span(),
+            None => self.right_pipe.span,
+        };
+        Span::from((self.left_pipe.span, end))
     }
 }
 
@@ -252,12 +562,12 @@ impl std::fmt::Display for Param {
     }
 }
 
-impl From<(&Ident, &Ident)> for Param {
-    fn from((name, kind): (&Ident, &Ident)) -> Self {
-        let span = Span::from((name.span, kind.span));
+impl From<(&Ident, &Type)> for Param {
+    fn from((name, kind): (&Ident, &Type)) -> Self {
+        let span = Span::from((name.span, kind.span()));
         Self {
             name: name.clone(),
-            kind: kind.into(),
+            kind: kind.clone(),
             span,
         }
     }
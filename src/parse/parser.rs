@@ -1,8 +1,13 @@
 use super::{
-    keyword, Ctrl, CtrlColon, CtrlComma, CtrlLBrace, CtrlLParan, CtrlRBrace, CtrlRParan,
-    CtrlRightArrow, CtrlSemiColon, Expr, ExprBinary, ExprBlock, ExprCall, ExprIf, ExprLet,
-    ExprReturn, Ident, Item, ItemFn, LitBool, LitChar, LitInt, LitStr, Op, OpAdd, OpDiv, OpEqual,
-    OpEqualEqual, OpGeq, OpGrt, OpLeq, OpLes, OpMul, OpNeq, OpSub, Param, Statement, Type,
+    keyword, ArrayLen, Ctrl, CtrlColon, CtrlColonColon, CtrlComma, CtrlDot, CtrlDotDot, CtrlLBrace,
+    CtrlLBracet, CtrlLParan, CtrlPipe, CtrlRBrace, CtrlRBracet, CtrlRParan, CtrlRightArrow,
+    CtrlSemiColon, Expr, ExprArray, ExprArrayRepeat, ExprAssign, ExprBinary, ExprBlock, ExprCall,
+    ExprCast, ExprClosure, ExprField, ExprFor, ExprIf, ExprIndex, ExprLet, ExprMethodCall,
+    ExprReturn, ExprStruct, ExprUnary, ExprVar, Generic, Ident, Item, ItemConst, ItemExtern,
+    ItemFn, ItemImpl, ItemStatic, ItemStruct, ItemTrait, ItemUse, LitBool, LitChar, LitFloat,
+    LitInt, LitStr, Op, OpAdd, OpDiv, OpEqual, OpEqualEqual, OpGeq, OpGrt, OpLeq, OpLes, OpMul,
+    OpNeq, OpRef, OpSub, Param, Statement, TraitFn, Type, TypeArray, TypeClosure, TypeFn,
+    TypeGeneric, TypePtr,
 };
 use crate::symbol_table::{Scope, Symbol, SymbolData, SymbolTable, SymbolType, TypeName};
 
@@ -14,6 +19,10 @@ pub struct Parser {
     errors: Vec<String>,
     symbol_table: SymbolTable,
     scope: Vec<Scope>,
+    blocks: usize,
+    /// Set while parsing the condition of an `if` or the range of a `for`, where a `{` after a
+    /// name starts the body and not a struct literal.
+    no_struct_lit: bool,
 }
 
 // declaration
@@ -23,6 +32,7 @@ pub struct Parser {
 // comparison
 // term
 // factor
+// cast
 // unary
 // primary
 
@@ -33,6 +43,8 @@ impl Parser {
             errors: vec![],
             symbol_table: SymbolTable::new(),
             scope: vec![Scope::default()],
+            blocks: 0,
+            no_struct_lit: false,
         }
     }
 
@@ -133,10 +145,272 @@ impl Parser {
     }
 
     fn declaration(&mut self) -> PResult<Item> {
-        self.item_fn()
+        if let Some(keyword_use) = self.stream.next_if::<keyword::Use>().cloned() {
+            return self.item_use(keyword_use);
+        }
+        let visibility = self.stream.next_if::<keyword::Pub>().cloned();
+        if let Some(keyword_const) = self.stream.next_if::<keyword::Const>().cloned() {
+            let (name, ty, expr) = self.global(SymbolType::Constant)?;
+            return Ok(Item::Const(ItemConst::new(
+                visibility,
+                keyword_const,
+                name,
+                ty,
+                expr,
+            )));
+        }
+        if let Some(keyword_static) = self.stream.next_if::<keyword::Static>().cloned() {
+            let (name, ty, expr) = self.global(SymbolType::Static)?;
+            return Ok(Item::Static(ItemStatic::new(
+                visibility,
+                keyword_static,
+                name,
+                ty,
+                expr,
+            )));
+        }
+        if let Some(keyword_trait) = self.stream.next_if::<keyword::Trait>().cloned() {
+            return self.item_trait(visibility, keyword_trait);
+        }
+        if let Some(keyword_impl) = self.stream.next_if::<keyword::Impl>().cloned() {
+            return self.item_impl(keyword_impl);
+        }
+        if let Some(keyword_struct) = self.stream.next_if::<keyword::Struct>().cloned() {
+            return self.item_struct(visibility, keyword_struct);
+        }
+        if let Some(keyword_extern) = self.stream.next_if::<keyword::Extern>().cloned() {
+            return self.item_extern(visibility, keyword_extern);
+        }
+        self.item_fn(visibility)
     }
 
-    fn item_fn(&mut self) -> PResult<Item> {
+    /// `extern fn` ending in `;` is a function linked in from C, one with a body is exported to C.
+    fn item_extern(
+        &mut self,
+        visibility: Option<keyword::Pub>,
+        keyword_extern: keyword::Extern,
+    ) -> PResult<Item> {
+        let abi = self.stream.next_if::<LitStr>().cloned();
+        let keyword_fn = self
+            .stream
+            .next_if::<keyword::Fn>()
+            .cloned()
+            .ok_or::<String>("expected fn".into())?;
+        let name = self
+            .stream
+            .next_if::<Ident>()
+            .cloned()
+            .ok_or::<String>("expected a ident".into())?;
+        let func_scope = self.current_scope();
+        self.scope.push(Scope::Function(name.value()));
+        let params = self.params()?;
+        let ret_type = self.ret_type()?;
+        // NOTE: declared before the body so it can call itself.
+        self.symbol_table.insert(
+            Symbol {
+                name: name.value(),
+                scope: func_scope.clone(),
+            },
+            SymbolData {
+                ty: SymbolType::Function,
+                scope: func_scope,
+                type_name: ret_type
+                    .as_ref()
+                    .map(TypeName::from)
+                    .unwrap_or(TypeName::Null),
+                span: name.span(),
+            },
+        );
+        if self.stream.is_peek_a::<CtrlLBrace>() {
+            let block = self.block()?;
+            self.scope.pop();
+            let mut item_fn = ItemFn::new(
+                visibility,
+                keyword_fn,
+                name,
+                vec![],
+                params,
+                block,
+                ret_type,
+            );
+            item_fn.keyword_extern = Some(keyword_extern);
+            item_fn.abi = abi;
+            return Ok(Item::Fn(item_fn));
+        }
+        self.scope.pop();
+        self.stream
+            .next_if::<CtrlSemiColon>()
+            .ok_or::<String>("extern functions end in ';' or a body".into())?;
+        Ok(Item::Extern(ItemExtern {
+            keyword_extern,
+            abi,
+            keyword_fn,
+            name,
+            params,
+            ret_type,
+        }))
+    }
+
+    fn item_trait(
+        &mut self,
+        visibility: Option<keyword::Pub>,
+        keyword_trait: keyword::Trait,
+    ) -> PResult<Item> {
+        let name = self
+            .stream
+            .next_if::<Ident>()
+            .cloned()
+            .ok_or::<String>("expected a ident".into())?;
+        self.stream
+            .next_if::<CtrlLBrace>()
+            .ok_or::<String>("expected '{'".into())?;
+        let mut fns = vec![];
+        while let Some(keyword_fn) = self.stream.next_if::<keyword::Fn>().cloned() {
+            let fn_name = self
+                .stream
+                .next_if::<Ident>()
+                .cloned()
+                .ok_or::<String>("expected a ident".into())?;
+            self.scope.push(Scope::Function(format!(
+                "{}::{}",
+                name.value, fn_name.value
+            )));
+            let params = self.params()?;
+            self.scope.pop();
+            let ret_type = self.ret_type()?;
+            self.stream
+                .next_if::<CtrlSemiColon>()
+                .ok_or::<String>("trait methods end in ';'".into())?;
+            fns.push(TraitFn {
+                keyword_fn,
+                name: fn_name,
+                params,
+                ret_type,
+            });
+        }
+        self.stream
+            .next_if::<CtrlRBrace>()
+            .ok_or::<String>("expected '}'".into())?;
+        Ok(Item::Trait(ItemTrait {
+            visibility,
+            keyword_trait,
+            name,
+            fns,
+        }))
+    }
+
+    fn item_struct(
+        &mut self,
+        visibility: Option<keyword::Pub>,
+        keyword_struct: keyword::Struct,
+    ) -> PResult<Item> {
+        let name = self
+            .stream
+            .next_if::<Ident>()
+            .cloned()
+            .ok_or::<String>("expected a ident".into())?;
+        let generics = self.generics()?;
+        self.stream
+            .next_if::<CtrlLBrace>()
+            .ok_or::<String>("expected '{'".into())?;
+        let mut fields = vec![];
+        while let Some(field) = self.stream.next_if::<Ident>().cloned() {
+            self.stream
+                .next_if::<CtrlColon>()
+                .ok_or::<String>(format!("expected a type for '{}'", field.value))?;
+            let ty = self.type_()?;
+            fields.push((&field, &ty).into());
+            if self.stream.next_if::<CtrlComma>().is_none() {
+                break;
+            }
+        }
+        self.stream
+            .next_if::<CtrlRBrace>()
+            .ok_or::<String>("expected '}'".into())?;
+        Ok(Item::Struct(ItemStruct {
+            visibility,
+            keyword_struct,
+            name,
+            generics,
+            fields,
+        }))
+    }
+
+    fn item_impl(&mut self, keyword_impl: keyword::Impl) -> PResult<Item> {
+        let generics = self.generics()?;
+        let mut ty = self.type_()?;
+        let mut of_trait = None;
+        if let Some(keyword_for) = self.stream.next_if::<keyword::For>().cloned() {
+            let Type::Name(trait_name) = ty else {
+                return Err("expected a trait before 'for'".into());
+            };
+            of_trait = Some((trait_name, keyword_for));
+            ty = self.type_()?;
+        }
+        self.stream
+            .next_if::<CtrlLBrace>()
+            .ok_or::<String>("expected '{'".into())?;
+        self.scope
+            .push(Scope::Type(TypeName::from(&ty).base().to_string()));
+        let mut fns = vec![];
+        while !self.stream.is_peek_a::<CtrlRBrace>() {
+            let visibility = self.stream.next_if::<keyword::Pub>().cloned();
+            let Item::Fn(item_fn) = self.item_fn(visibility)? else {
+                unreachable!("item_fn only returns functions");
+            };
+            fns.push(item_fn);
+        }
+        self.scope.pop();
+        self.stream
+            .next_if::<CtrlRBrace>()
+            .ok_or::<String>("expected '}'".into())?;
+        Ok(Item::Impl(ItemImpl {
+            keyword_impl,
+            generics,
+            of_trait,
+            ty,
+            fns,
+        }))
+    }
+
+    /// `NAME: Type = expr;` after a `const` or `static`, the type can not be left out.
+    fn global(&mut self, ty: SymbolType) -> PResult<(Ident, Type, Expr)> {
+        let name = self
+            .stream
+            .next_if::<Ident>()
+            .cloned()
+            .ok_or::<String>("expected a ident".into())?;
+        self.stream
+            .next_if::<CtrlColon>()
+            .ok_or::<String>(format!("expected a type for '{}'", name.value))?;
+        let type_ = self.type_()?;
+        self.stream
+            .next_if::<OpEqual>()
+            .ok_or::<String>("expected '='".into())?;
+        let expr = self.expression();
+        self.stream
+            .next_if::<CtrlSemiColon>()
+            .ok_or::<String>(match ty {
+                SymbolType::Static => "static items end in ';'".into(),
+                _ => "const items end in ';'".into(),
+            })?;
+        self.insert_symbol(name.value(), ty, TypeName::from(&type_), name.span());
+        Ok((name, type_, expr))
+    }
+
+    fn item_use(&mut self, keyword_use: keyword::Use) -> PResult<Item> {
+        let name = self
+            .stream
+            .next_if::<Ident>()
+            .cloned()
+            .ok_or::<String>("expected a ident".into())?;
+        self.stream
+            .next_if::<CtrlSemiColon>()
+            .ok_or::<String>("use statements end in ';'".into())?;
+        Ok(Item::Use(ItemUse::new(keyword_use, name)))
+    }
+
+    fn item_fn(&mut self, visibility: Option<keyword::Pub>) -> PResult<Item> {
         let keyword_fn = self
             .stream
             .next_if::<keyword::Fn>()
@@ -155,10 +429,11 @@ impl Parser {
 
         self.scope.push(Scope::Function(name.value()));
 
+        let generics = self.generics()?;
         let params = self.params()?;
         let ret_type = self.ret_type()?;
-        let block = self.block()?;
 
+        // NOTE: declared before the body so it can call itself.
         self.symbol_table.insert(
             Symbol {
                 name: func_name,
@@ -167,27 +442,181 @@ impl Parser {
             SymbolData {
                 ty: func_ty,
                 scope: func_scope.clone(),
-                type_name: ret_type.clone().map(|t| t.into()).unwrap_or(TypeName::Null),
+                type_name: ret_type
+                    .as_ref()
+                    .map(TypeName::from)
+                    .unwrap_or(TypeName::Null),
                 span: func_span,
             },
         );
 
+        let block = self.block()?;
         self.scope.pop();
 
         Ok(Item::Fn(ItemFn::new(
-            keyword_fn, name, params, block, ret_type,
+            visibility, keyword_fn, name, generics, params, block, ret_type,
         )))
     }
 
+    /// `<T: Show, U>` after the name of a function or struct, or right after `impl`.
+    fn generics(&mut self) -> PResult<Vec<Generic>> {
+        if self.op_next_if::<OpLes>().is_none() {
+            return Ok(vec![]);
+        }
+        let mut generics = vec![];
+        while let Some(name) = self.stream.next_if::<Ident>().cloned() {
+            let mut bounds = vec![];
+            if self.stream.next_if::<CtrlColon>().is_some() {
+                loop {
+                    let bound = self
+                        .stream
+                        .next_if::<Ident>()
+                        .cloned()
+                        .ok_or::<String>(format!("expected a trait for '{}'", name.value))?;
+                    bounds.push(self.type_path(bound)?);
+                    if self.op_next_if::<OpAdd>().is_none() {
+                        break;
+                    }
+                }
+            }
+            generics.push(Generic { name, bounds });
+            if self.stream.next_if::<CtrlComma>().is_none() {
+                break;
+            }
+        }
+        self.op_next_if::<OpGrt>()
+            .ok_or::<String>("type parameters end with '>'".into())?;
+        Ok(generics)
+    }
+
     fn ret_type(&mut self) -> PResult<Option<Type>> {
         let Some(_) = self.stream.next_if::<CtrlRightArrow>() else {
             return Ok(None);
         };
-        let Some(t) = self.stream.next_if::<Ident>() else {
-            return Err("expected return type".into());
+        let t = self
+            .type_()
+            .map_err(|_| String::from("expected return type"))?;
+
+        Ok(Some(t))
+    }
+
+    fn type_(&mut self) -> PResult<Type> {
+        if let Some(left_bracet) = self.stream.next_if::<CtrlLBracet>().cloned() {
+            let elem = self.type_()?;
+            self.stream
+                .next_if::<CtrlSemiColon>()
+                .ok_or::<String>("expected ';' in array type".into())?;
+            let len = self
+                .array_len()
+                .ok_or::<String>("expected array length".into())?;
+            let right_bracet = self
+                .stream
+                .next_if::<CtrlRBracet>()
+                .cloned()
+                .ok_or::<String>("expected ']'".into())?;
+            return Ok(Type::Array(TypeArray {
+                left_bracet,
+                elem: Box::new(elem),
+                len,
+                right_bracet,
+            }));
+        }
+        let sigil = match self.op_next_if::<OpRef>() {
+            Some(op) => Some(op),
+            None => self.op_next_if::<OpMul>(),
+        };
+        if let Some(sigil) = sigil {
+            let mutable = match sigil {
+                Op::Ref(_) => self.stream.next_if::<keyword::Mut>().cloned(),
+                _ => None,
+            };
+            let elem = self.type_()?;
+            return Ok(Type::Ptr(TypePtr {
+                sigil,
+                mutable,
+                elem: Box::new(elem),
+            }));
+        }
+        if let Some(keyword_fn) = self.stream.next_if::<keyword::Fn>().cloned() {
+            return self.type_fn(keyword_fn);
+        }
+        if let Some(left_pipe) = self.stream.next_if::<CtrlPipe>().cloned() {
+            return self.type_closure(left_pipe);
+        }
+        let Some(name) = self.stream.next_if::<Ident>().cloned() else {
+            return Err("expected a type".into());
         };
+        let name = self.type_path(name)?;
+        // NOTE: `x as u64 < y` compares, only a struct takes type arguments.
+        let is_struct = matches!(TypeName::from(name.value.as_str()), TypeName::Custom(_));
+        if !is_struct || !self.stream.is_peek_a::<OpLes>() {
+            return Ok((&name).into());
+        }
+        self.op_next_if::<OpLes>();
+        let mut args = vec![];
+        while !self.stream.is_peek_a::<OpGrt>() {
+            args.push(self.type_()?);
+            if self.stream.next_if::<CtrlComma>().is_none() {
+                break;
+            }
+        }
+        let right_angle = self
+            .op_next_if::<OpGrt>()
+            .ok_or::<String>("type arguments end with '>'".into())?;
+        Ok(Type::Generic(TypeGeneric {
+            name,
+            args,
+            right_angle,
+        }))
+    }
+
+    /// `fn(u64, bool) -> u64`, the type of a function value.
+    fn type_fn(&mut self, keyword_fn: keyword::Fn) -> PResult<Type> {
+        self.stream
+            .next_if::<CtrlLParan>()
+            .ok_or::<String>("expected '(' after 'fn' in a type".into())?;
+        let mut params = vec![];
+        while !self.stream.is_peek_a::<CtrlRParan>() {
+            params.push(self.type_()?);
+            if self.stream.next_if::<CtrlComma>().is_none() {
+                break;
+            }
+        }
+        let right_paran = self
+            .stream
+            .next_if::<CtrlRParan>()
+            .cloned()
+            .ok_or::<String>("function types end their params with ')'".into())?;
+        let ret = self.ret_type()?.map(Box::new);
+        Ok(Type::Fn(TypeFn {
+            keyword_fn,
+            params,
+            right_paran,
+            ret,
+        }))
+    }
 
-        Ok(Some(t.into()))
+    /// `|u64, bool| -> u64`, the type of a closure.
+    fn type_closure(&mut self, left_pipe: CtrlPipe) -> PResult<Type> {
+        let mut params = vec![];
+        while !self.stream.is_peek_a::<CtrlPipe>() {
+            params.push(self.type_()?);
+            if self.stream.next_if::<CtrlComma>().is_none() {
+                break;
+            }
+        }
+        let right_pipe = self
+            .stream
+            .next_if::<CtrlPipe>()
+            .cloned()
+            .ok_or::<String>("closure types end their params with '|'".into())?;
+        let ret = self.ret_type()?.map(Box::new);
+        Ok(Type::Closure(TypeClosure {
+            left_pipe,
+            params,
+            right_pipe,
+            ret,
+        }))
     }
 
     fn params(&mut self) -> PResult<Vec<Param>> {
@@ -196,21 +625,30 @@ impl Parser {
             .ok_or::<String>("expected '('".into())?;
         let mut params = vec![];
         while !self.stream.is_peek_a::<CtrlRParan>() {
+            if let Some(param) = self.ref_self()? {
+                params.push(param);
+                self.stream.next_if::<CtrlComma>();
+                continue;
+            }
             let Some(name) = self.stream.next_if::<Ident>().cloned() else {
                 break;
             };
 
-            self.ctrl_next_if::<CtrlColon>()
-                .ok_or::<String>("expected ':' after function param id".into())?;
-
-            let Some(kind) = self.stream.next_if::<Ident>().cloned() else {
-                break;
+            // NOTE: a bare `self` is the value a method is called on.
+            let kind = if name.value == "self" && self.stream.next_if::<CtrlColon>().is_none() {
+                Type::Name(Ident::new(String::from("Self"), name.span()))
+            } else {
+                if name.value != "self" {
+                    self.ctrl_next_if::<CtrlColon>()
+                        .ok_or::<String>("expected ':' after function param id".into())?;
+                }
+                self.type_()?
             };
 
             self.insert_symbol(
                 name.value(),
                 SymbolType::Parameter,
-                kind.clone().into(),
+                (&kind).into(),
                 name.span(),
             );
 
@@ -225,17 +663,46 @@ impl Parser {
         Ok(params)
     }
 
+    /// `&self` or `&mut self`, a method that borrows the value it is called on.
+    fn ref_self(&mut self) -> PResult<Option<Param>> {
+        let Some(sigil) = self.op_next_if::<OpRef>() else {
+            return Ok(None);
+        };
+        let mutable = self.stream.next_if::<keyword::Mut>().cloned();
+        let name = self
+            .stream
+            .next_if::<Ident>()
+            .cloned()
+            .filter(|name| name.value == "self")
+            .ok_or::<String>("expected 'self' after '&'".into())?;
+        let kind = Type::Ptr(TypePtr {
+            sigil,
+            mutable,
+            elem: Box::new(Type::Name(Ident::new(String::from("Self"), name.span()))),
+        });
+        self.insert_symbol(
+            name.value(),
+            SymbolType::Parameter,
+            (&kind).into(),
+            name.span(),
+        );
+        Ok(Some((&name, &kind).into()))
+    }
+
     fn block(&mut self) -> PResult<ExprBlock> {
         let left_brace = self
             .stream
             .next_if::<CtrlLBrace>()
             .cloned()
             .ok_or::<String>("expected '{'".into())?;
+        self.scope.push(Scope::Block(self.blocks));
+        self.blocks += 1;
         let mut stmts = vec![];
         while !self.stream.is_peek_a::<CtrlRBrace>() {
             let stmt = self.statement()?;
             stmts.push(stmt);
         }
+        self.scope.pop();
         let right_brace = self
             .stream
             .next_if::<CtrlRBrace>()
@@ -246,6 +713,8 @@ impl Parser {
 
     fn statement(&mut self) -> PResult<Statement> {
         let stmt = self.let_expression()?;
+        // expression statements may end in a ';'
+        self.stream.next_if::<CtrlSemiColon>();
         let span = stmt.span();
         Ok(Statement { stmt, span })
     }
@@ -259,6 +728,10 @@ impl Parser {
             .next_if::<Ident>()
             .ok_or::<String>("expected a ident".into())?
             .clone();
+        let ty = match self.stream.next_if::<CtrlColon>() {
+            Some(_) => Some(self.type_()?),
+            None => None,
+        };
         let eq_token = self
             .stream
             .next_if::<OpEqual>()
@@ -266,18 +739,22 @@ impl Parser {
             .ok_or::<String>("expected '='".into())?;
         // TODO: probably guess the type of the expression
         let expr = self.expression();
+        self.stream
+            .next_if::<CtrlSemiColon>()
+            .ok_or::<String>("let statements end in ';'".into())?;
 
         self.insert_symbol(
             name.value(),
             SymbolType::Variable,
             // TODO: Get type from expr
-            TypeName::U64,
+            ty.as_ref().map(TypeName::from).unwrap_or(TypeName::U64),
             name.span(),
         );
 
         Ok(ExprLet {
             let_token,
             name,
+            ty,
             eq_token: eq_token.into(),
             expr: expr.into(),
         }
@@ -307,14 +784,59 @@ impl Parser {
             // HACK: this implemention is a bit of a hack with all the funcitons not returning a
             // Result.
             let if_token = self.stream.next_as::<keyword::If>().cloned().unwrap();
-            let cond = Box::new(self.comparison());
+            let cond = Box::new(self.condition());
             let then_branch = self.block().expect("failed to get block");
             let else_branch = self.else_branch();
             return ExprIf::new(if_token, cond, then_branch, else_branch).into();
         }
+        if let Some(for_token) = self.stream.next_if::<keyword::For>().cloned() {
+            return self.for_expression(for_token);
+        }
         self.comparison()
     }
 
+    /// `for name in start..end { ... }` or `for name in expr { ... }` after the `for`.
+    fn for_expression(&mut self, for_token: keyword::For) -> Expr {
+        let Some(name) = self.stream.next_if::<Ident>().cloned() else {
+            // TODO: make this report an error
+            panic!("expected a ident after 'for'");
+        };
+        let Some(in_token) = self.stream.next_if::<keyword::In>().cloned() else {
+            // TODO: make this report an error
+            panic!("expected 'in' after '{}'", name.value);
+        };
+        let iter = Box::new(self.condition());
+        let end = self
+            .stream
+            .next_if::<CtrlDotDot>()
+            .cloned()
+            .map(|dot_dot| (dot_dot, Box::new(self.condition())));
+        self.insert_symbol(
+            name.value(),
+            SymbolType::Variable,
+            TypeName::U64,
+            name.span(),
+        );
+        let body = self.block().expect("failed to get block");
+        ExprFor {
+            for_token,
+            name,
+            in_token,
+            iter,
+            end,
+            body,
+        }
+        .into()
+    }
+
+    /// An expression right before a block, it can not be a struct literal.
+    fn condition(&mut self) -> Expr {
+        let no_struct_lit = std::mem::replace(&mut self.no_struct_lit, true);
+        let expr = self.comparison();
+        self.no_struct_lit = no_struct_lit;
+        expr
+    }
+
     fn else_branch(&mut self) -> Option<(keyword::Else, Box<Expr>)> {
         let keyword_else = self.stream.next_if::<keyword::Else>().cloned()?;
         let block = if self.stream.peek::<keyword::If>().is_some() {
@@ -327,7 +849,16 @@ impl Parser {
     }
 
     fn expression(&mut self) -> Expr {
-        self.if_expression()
+        self.assignment()
+    }
+
+    fn assignment(&mut self) -> Expr {
+        let expr = self.if_expression();
+        let Some(eq_token) = self.op_next_if::<OpEqual>() else {
+            return expr;
+        };
+        let value = self.assignment();
+        ExprAssign::new(expr, eq_token, value).into()
     }
 
     fn comparison(&mut self) -> Expr {
@@ -356,25 +887,101 @@ impl Parser {
     }
 
     fn factor(&mut self) -> Expr {
-        let mut expr = self.call();
+        let mut expr = self.cast();
         while let Some(op) = self.op_next_if::<OpMul>().or(self.op_next_if::<OpDiv>()) {
-            let right = self.call();
+            let right = self.cast();
             expr = Expr::from(ExprBinary::from((expr, right, op)))
         }
         expr
     }
 
+    fn cast(&mut self) -> Expr {
+        let mut expr = self.unary();
+        while let Some(keyword_as) = self.stream.next_if::<keyword::As>().copied() {
+            let ty = self.type_().unwrap_or_else(|error| panic!("{error}"));
+            expr = ExprCast::new(expr, keyword_as, ty).into();
+        }
+        expr
+    }
+
+    fn unary(&mut self) -> Expr {
+        if let Some(op) = self.op_next_if::<OpRef>() {
+            let mutable = self.stream.next_if::<keyword::Mut>().cloned();
+            let expr = self.unary();
+            return ExprUnary::new(op, mutable, expr).into();
+        }
+        if let Some(op) = self.op_next_if::<OpMul>() {
+            let expr = self.unary();
+            return ExprUnary::new(op, None, expr).into();
+        }
+        self.call()
+    }
+
     fn call(&mut self) -> Expr {
         let mut expr = self.primary();
 
-        if let Some(left_paran) = self.stream.next_if::<CtrlLParan>().cloned() {
-            expr = self.finish_call(expr, left_paran);
+        loop {
+            if let Some(left_paran) = self.stream.next_if::<CtrlLParan>().cloned() {
+                expr = self.finish_call(expr, left_paran);
+            } else if let Some(left_bracet) = self.stream.next_if::<CtrlLBracet>().cloned() {
+                expr = self.finish_index(expr, left_bracet);
+            } else if let Some(dot) = self.stream.next_if::<CtrlDot>().cloned() {
+                expr = self.finish_dot(expr, dot);
+            } else {
+                break;
+            }
         }
 
         expr
     }
 
+    fn finish_index(&mut self, expr: Expr, left_bracet: CtrlLBracet) -> Expr {
+        let index = self.expression();
+        let Some(right_bracet) = self.stream.next_if::<CtrlRBracet>().cloned() else {
+            // TODO: make this report an error
+            panic!("expected a right bracet");
+        };
+        ExprIndex::new(expr, left_bracet, index, right_bracet).into()
+    }
+
+    /// `receiver.name(args)` is a method call, without the '(' it is a field.
+    fn finish_dot(&mut self, receiver: Expr, dot: CtrlDot) -> Expr {
+        let Some(name) = self.stream.next_if::<Ident>().cloned() else {
+            // TODO: make this report an error
+            panic!("expected a method or field name after '.'");
+        };
+        let Some(left_paran) = self.stream.next_if::<CtrlLParan>().cloned() else {
+            return ExprField {
+                expr: Box::new(receiver),
+                dot,
+                name,
+            }
+            .into();
+        };
+        let (args, right_paran) = self.args();
+        ExprMethodCall {
+            receiver: Box::new(receiver),
+            dot,
+            name,
+            left_paran,
+            args,
+            right_paran,
+        }
+        .into()
+    }
+
     fn finish_call(&mut self, caller: Expr, left_paran: CtrlLParan) -> Expr {
+        let (args, right_paran) = self.args();
+        Expr::Call(ExprCall::new(
+            Box::new(caller),
+            left_paran,
+            args,
+            right_paran,
+        ))
+    }
+
+    /// The arguments of a call after the '('.
+    fn args(&mut self) -> (Vec<Expr>, CtrlRParan) {
         let mut args = vec![];
         if !self.stream.is_peek_a::<CtrlRParan>() {
             while !self.stream.is_peek_a::<CtrlRParan>() {
@@ -388,17 +995,54 @@ impl Parser {
             // TODO: make this report an error
             panic!("expected a right paran");
         };
-        Expr::Call(ExprCall::new(
-            Box::new(caller),
-            left_paran,
-            args,
-            right_paran,
-        ))
+        (args, right_paran)
+    }
+
+    /// The length of an array is a number or the name of a `const`.
+    fn array_len(&mut self) -> Option<ArrayLen> {
+        if let Some(lit) = self.stream.next_if::<LitInt>().cloned() {
+            return Some(ArrayLen::Lit(lit));
+        }
+        self.stream.next_if::<Ident>().cloned().map(ArrayLen::Const)
+    }
+
+    fn array(&mut self, left_bracet: CtrlLBracet) -> Expr {
+        let mut elems = vec![];
+        while !self.stream.is_peek_a::<CtrlRBracet>() {
+            elems.push(self.expression());
+            if elems.len() == 1 && self.stream.next_if::<CtrlSemiColon>().is_some() {
+                let Some(len) = self.array_len() else {
+                    // TODO: make this report an error
+                    panic!("expected array length");
+                };
+                let Some(right_bracet) = self.stream.next_if::<CtrlRBracet>().cloned() else {
+                    // TODO: make this report an error
+                    panic!("expected a right bracet");
+                };
+                return ExprArrayRepeat::new(left_bracet, elems.remove(0), len, right_bracet)
+                    .into();
+            }
+            if self.stream.next_if::<CtrlComma>().is_none() {
+                break;
+            }
+        }
+        let Some(right_bracet) = self.stream.next_if::<CtrlRBracet>().cloned() else {
+            // TODO: make this report an error
+            panic!("expected a right bracet");
+        };
+        ExprArray::new(left_bracet, elems, right_bracet).into()
     }
 
     fn primary(&mut self) -> Expr {
+        if let Some(left_bracet) = self.stream.next_if::<CtrlLBracet>().cloned() {
+            return self.array(left_bracet);
+        }
+        if let Some(left_pipe) = self.stream.next_if::<CtrlPipe>().cloned() {
+            return self.closure(left_pipe);
+        }
         let Some(expr) = self
             .expr_next_if::<LitInt>()
+            .or(self.expr_next_if::<LitFloat>())
             .or(self.expr_next_if::<LitBool>())
             .or(self.expr_next_if::<LitStr>())
             .or(self.expr_next_if::<LitChar>())
@@ -407,6 +1051,115 @@ impl Parser {
             // TODO: make this report an error
             panic!("unknown expression '{:?}'", self.stream.peek_blind());
         };
-        expr
+        match expr {
+            Expr::Var(var) if self.is_struct_lit() => self.struct_lit(var.name),
+            Expr::Var(var) => {
+                let var = self.path(var);
+                match var.path.first() {
+                    // NOTE: `geometry::Point { .. }` makes a struct declared in a module.
+                    Some(first) if self.is_struct_lit() => {
+                        let path = var.path.iter().chain([&var.name]);
+                        let value = path.map(|name| name.value.as_str()).collect::<Vec<_>>();
+                        let span = Span::from((first.span, var.name.span));
+                        self.struct_lit(Ident::new(value.join("::"), span))
+                    }
+                    _ => var.into(),
+                }
+            }
+            expr => expr,
+        }
+    }
+
+    /// `|x: u64, y| x + y` or `|x| -> u64 { ... }` after the first '|'.
+    fn closure(&mut self, left_pipe: CtrlPipe) -> Expr {
+        let mut params = vec![];
+        while let Some(name) = self.stream.next_if::<Ident>().cloned() {
+            // NOTE: a param without a type is a u64, like every integer literal.
+            let kind = match self.stream.next_if::<CtrlColon>() {
+                Some(_) => self.type_().unwrap_or_else(|error| panic!("{error}")),
+                None => Type::Name(Ident::new("u64", name.span)),
+            };
+            params.push((&name, &kind).into());
+            if self.stream.next_if::<CtrlComma>().is_none() {
+                break;
+            }
+        }
+        let Some(right_pipe) = self.stream.next_if::<CtrlPipe>().cloned() else {
+            // TODO: make this report an error
+            panic!("closures end their params with '|'");
+        };
+        let ret = self.ret_type().unwrap_or_else(|error| panic!("{error}"));
+        let body = match self.stream.is_peek_a::<CtrlLBrace>() {
+            true => Expr::Block(self.block().expect("failed to get block")),
+            false => self.expression(),
+        };
+        ExprClosure {
+            left_pipe,
+            params,
+            right_pipe,
+            ret,
+            body: Box::new(body),
+        }
+        .into()
+    }
+
+    fn is_struct_lit(&self) -> bool {
+        !self.no_struct_lit && self.stream.is_peek_a::<CtrlLBrace>()
+    }
+
+    /// `Name { field: expr, ... }` after the name.
+    fn struct_lit(&mut self, name: Ident) -> Expr {
+        let Some(left_brace) = self.stream.next_if::<CtrlLBrace>().cloned() else {
+            unreachable!("checked by is_struct_lit");
+        };
+        let mut fields = vec![];
+        while let Some(field) = self.stream.next_if::<Ident>().cloned() {
+            if self.stream.next_if::<CtrlColon>().is_none() {
+                // TODO: make this report an error
+                panic!("expected ':' after '{}'", field.value);
+            }
+            fields.push((field, self.expression()));
+            if self.stream.next_if::<CtrlComma>().is_none() {
+                break;
+            }
+        }
+        let Some(right_brace) = self.stream.next_if::<CtrlRBrace>().cloned() else {
+            // TODO: make this report an error
+            panic!("expected a '}}' after the fields of '{}'", name.value);
+        };
+        ExprStruct {
+            name,
+            left_brace,
+            fields,
+            right_brace,
+        }
+        .into()
+    }
+
+    /// `foo::Bar` after `foo`, a struct or trait named by the module it is declared in. The path
+    /// stays part of the name until the modules are linked.
+    fn type_path(&mut self, mut name: Ident) -> PResult<Ident> {
+        while self.stream.next_if::<CtrlColonColon>().is_some() {
+            let next = self
+                .stream
+                .next_if::<Ident>()
+                .cloned()
+                .ok_or::<String>("expected a name after '::'".into())?;
+            let span = Span::from((name.span, next.span));
+            name = Ident::new(format!("{}::{}", name.value, next.value), span);
+        }
+        Ok(name)
+    }
+
+    /// `foo::bar::baz` where everything before the last name is a module.
+    fn path(&mut self, mut var: ExprVar) -> ExprVar {
+        while self.stream.next_if::<CtrlColonColon>().is_some() {
+            let Some(name) = self.stream.next_if::<Ident>().cloned() else {
+                // TODO: make this report an error
+                panic!("expected a ident after '::'");
+            };
+            var.path.push(std::mem::replace(&mut var.name, name));
+        }
+        var
     }
 }
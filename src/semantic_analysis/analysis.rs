@@ -1,181 +1,1684 @@
-// use crate::lexer::Span;
-use crate::parse::ast::{ExprBinary, ExprCall, ExprLit, ExprReturn, ExprVar, Ident};
-use crate::parse::{Expr, ExprBlock, Item, ItemFn, Lit, Param, Statement};
-
-use super::{Function, Scope, Symbol, SymbolTable, Type, Variable};
-
-// pub type SemanticErrorList = Vec<SemanticError>;
-//
-// #[derive(Debug, Clone)]
-// pub enum SemanticError {
-//     UndefinedSymbol(String, Span),
-// }
-type Ast = &[Item];
-
-pub fn create_symbol_table(ast: Ast) -> SymbolTable {
-    SymbolTableBuilder::default()
+use super::{method_name, FnSig, Global, SelfParam, SemanticError, Structs, Traits};
+use crate::builtins::Builtin;
+use crate::lexer::Span;
+use crate::parse::{
+    Expr, ExprArray, ExprArrayRepeat, ExprAssign, ExprBinary, ExprBlock, ExprCall, ExprCast,
+    ExprClosure, ExprField, ExprFor, ExprIf, ExprIndex, ExprLet, ExprLit, ExprMethodCall,
+    ExprReturn, ExprStruct, ExprUnary, ExprVar, Ident, Item, ItemExtern, ItemFn, ItemImpl, Lit,
+    LitFloat, LitInt, LitStr, Op, Param, Statement, Type,
+};
+use crate::stdlib;
+use crate::symbol_table::TypeName;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The name of the function a call is in and the span of the call.
+pub type CallSite = (String, Span);
+
+/// What code generation needs to know about the calls that is only worked out while checking.
+#[derive(Debug, Default)]
+pub struct Calls {
+    /// Types the type parameters of a generic function are called with.
+    pub generic: HashMap<CallSite, Vec<TypeName>>,
+    pub method: HashMap<CallSite, Method>,
+    /// Every closure by the function it is made in and its span.
+    pub closures: HashMap<CallSite, Closure>,
+    /// Number literals that are not a `u64` or an `f64`, literals of generic structs and array
+    /// literals, by their span. They take the type of where they are used.
+    pub lits: HashMap<CallSite, TypeName>,
+}
+
+/// What a closure takes from the function it is made in.
+#[derive(Debug, Clone)]
+pub struct Closure {
+    /// In the order they are first used in the body.
+    pub captures: Vec<Capture>,
+    pub ret: TypeName,
+}
+
+/// A local a closure uses, it is copied into the closure unless the closure writes to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    pub name: String,
+    pub ty: TypeName,
+    pub by_ref: bool,
+}
+
+/// A closure being checked, the variables in the scopes before `depth` belong to the functions
+/// around it.
+#[derive(Debug)]
+struct ClosureScope {
+    depth: usize,
+    captures: Vec<Capture>,
+}
+
+/// The impl a method call goes to.
+#[derive(Debug, Clone)]
+pub struct Method {
+    /// `None` for a method of the type itself.
+    pub trait_name: Option<String>,
+    pub ty: TypeName,
+    pub receiver: Receiver,
 }
 
+/// How the value a method is called on is handed to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Receiver {
+    Value,
+    /// Borrowed for a `&self` method.
+    Borrow,
+    /// Read through a pointer for a `self` method.
+    Deref,
+}
+
+/// `None` when the expression is a temporary, otherwise whether the place
+/// it names can be written to.
+type Place = (TypeName, Option<bool>);
+
 pub trait SemanticAnalysisVisitor {
-    fn visit_lit(&mut self, _: &Lit) -> Type;
-    fn visit_expr_binary(&mut self, _: &ExprBinary) -> Type;
-    fn visit_expr_call(&mut self, call: &ExprCall) -> Type;
-    fn visit_expr_var(&mut self, var: &ExprVar) -> Type;
-    fn visit_expr_return(&mut self, var: &ExprReturn) -> Type;
-    fn visit_expr(&mut self, _: &Expr) -> Type;
-    fn visit_stmt(&mut self, _: &Statement);
-    fn visit_block(&mut self, _: &ExprBlock);
-    fn visit_params(&mut self, _: &[Param]);
-    fn visit_item_fn(&mut self, _: &ItemFn);
-    fn visit(&mut self, items: Ast) {
-        for item in items {
+    fn visit_item_fn(&mut self, item_fn: &ItemFn);
+    fn visit_item_impl(&mut self, item_impl: &ItemImpl);
+    fn visit_item_extern(&mut self, item_extern: &ItemExtern);
+    fn visit_params(&mut self, param: &Param);
+    fn visit_lit(&mut self, lit: &Lit) -> TypeName;
+    fn visit_expr_call(&mut self, expr_call: &ExprCall) -> TypeName;
+    fn visit_expr_method_call(&mut self, expr_method_call: &ExprMethodCall) -> TypeName;
+    fn visit_expr_binary(&mut self, expr_binary: &ExprBinary) -> TypeName;
+    fn visit_expr_let(&mut self, expr_let: &ExprLet) -> TypeName;
+    fn visit_expr_if(&mut self, expr_if: &ExprIf) -> TypeName;
+    fn visit_expr_for(&mut self, expr_for: &ExprFor) -> TypeName;
+    fn visit_expr_array(&mut self, expr_array: &ExprArray) -> TypeName;
+    fn visit_expr_array_repeat(&mut self, expr_array_repeat: &ExprArrayRepeat) -> TypeName;
+    fn visit_expr_assign(&mut self, expr_assign: &ExprAssign) -> TypeName;
+    fn visit_expr_unary(&mut self, expr_unary: &ExprUnary) -> TypeName;
+    fn visit_expr_struct(&mut self, expr_struct: &ExprStruct) -> TypeName;
+    fn visit_expr_closure(&mut self, expr_closure: &ExprClosure) -> TypeName;
+    fn visit_expr_cast(&mut self, expr_cast: &ExprCast) -> TypeName;
+    fn visit_expr_return(&mut self, expr_return: &ExprReturn) -> TypeName;
+    fn visit_place(&mut self, expr: &Expr) -> Place;
+    fn enter_scope(&mut self);
+    fn leave_scope(&mut self);
+
+    fn visit_expr(&mut self, expr: &Expr) -> TypeName {
+        match expr {
+            Expr::Lit(ExprLit { lit }) => self.visit_lit(lit),
+            Expr::Binary(ebinary) => self.visit_expr_binary(ebinary),
+            Expr::Call(ecall) => self.visit_expr_call(ecall),
+            Expr::MethodCall(ecall) => self.visit_expr_method_call(ecall),
+            Expr::Let(elet) => self.visit_expr_let(elet),
+            Expr::If(eif) => self.visit_expr_if(eif),
+            Expr::For(efor) => self.visit_expr_for(efor),
+            Expr::Block(eblock) => self.visit_expr_block(eblock),
+            Expr::Return(ereturn) => self.visit_expr_return(ereturn),
+            Expr::Array(earray) => self.visit_expr_array(earray),
+            Expr::ArrayRepeat(erepeat) => self.visit_expr_array_repeat(erepeat),
+            Expr::Assign(eassign) => self.visit_expr_assign(eassign),
+            Expr::Unary(eunary) if eunary.is_ref() => self.visit_expr_unary(eunary),
+            Expr::Struct(estruct) => self.visit_expr_struct(estruct),
+            Expr::Closure(eclosure) => self.visit_expr_closure(eclosure),
+            Expr::Cast(ecast) => self.visit_expr_cast(ecast),
+            Expr::Var(_) | Expr::Index(_) | Expr::Field(_) | Expr::Unary(_) => {
+                self.visit_place(expr).0
+            }
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &Statement) -> TypeName {
+        let Statement { stmt, .. } = stmt;
+        self.visit_expr(stmt)
+    }
+
+    fn visit_expr_block(&mut self, block: &ExprBlock) -> TypeName {
+        self.enter_scope();
+        let mut ty = TypeName::Void;
+        for stmt in block.stmts.iter() {
+            ty = self.visit_stmt(stmt);
+        }
+        self.leave_scope();
+        ty
+    }
+
+    fn visit(&mut self, items: &[Item]) {
+        for item in items.iter() {
             match item {
-                // TODO: two pass for function delcaration
                 Item::Fn(item_fn) => self.visit_item_fn(item_fn),
+                Item::Impl(item_impl) => self.visit_item_impl(item_impl),
+                Item::Extern(item_extern) => self.visit_item_extern(item_extern),
+                // NOTE: consts and statics are checked while working out their values, traits
+                // and structs while they are declared.
+                Item::Use(_)
+                | Item::Const(_)
+                | Item::Static(_)
+                | Item::Trait(_)
+                | Item::Struct(_) => {}
             }
         }
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct SymbolTableBuilder {
-    symbol_table: SymbolTable,
-    scope: Vec<Scope>,
+pub struct TypeChecker<'a> {
+    functions: &'a HashMap<String, FnSig>,
+    globals: &'a HashMap<String, Global>,
+    traits: &'a Traits,
+    structs: &'a Structs,
+    /// Variables in scope, the innermost block last.
+    vars: Vec<HashMap<String, TypeName>>,
+    current_fn: String,
+    /// The file the function being checked is written in, its errors say it.
+    file: Option<String>,
+    /// What the function or closure being checked returns, `Null` when it does not say.
+    ret: TypeName,
+    /// The traits the type parameters of the current function have to implement.
+    bounds: HashMap<String, Vec<String>>,
+    /// What `Self` stands for inside of an `impl`, and the type parameters of a generic function
+    /// while it is checked for the types it is called with.
+    type_args: HashMap<String, TypeName>,
+    /// Generic functions, the types they are called with and where, each is checked once more
+    /// for them.
+    instances: VecDeque<(String, Vec<TypeName>, String)>,
+    /// The closures being checked, the innermost last.
+    closures: Vec<ClosureScope>,
+    calls: Calls,
+    errors: Vec<SemanticError>,
 }
 
-impl SymbolTableBuilder {
-    pub fn build(self) -> SymbolTable {
-        self.symbol_table
+impl<'a> TypeChecker<'a> {
+    pub fn new(
+        functions: &'a HashMap<String, FnSig>,
+        globals: &'a HashMap<String, Global>,
+        traits: &'a Traits,
+        structs: &'a Structs,
+    ) -> Self {
+        Self {
+            functions,
+            globals,
+            traits,
+            structs,
+            vars: vec![],
+            current_fn: String::new(),
+            file: None,
+            ret: TypeName::Null,
+            bounds: HashMap::new(),
+            type_args: HashMap::new(),
+            instances: VecDeque::new(),
+            closures: vec![],
+            calls: Calls::default(),
+            errors: Vec::new(),
+        }
     }
-}
 
-impl SymbolTableBuilder {
-    pub fn current_scope(&self) -> Scope {
-        self.scope.last().copied().unwrap_or_default()
+    pub fn errors(&self) -> &[SemanticError] {
+        &self.errors
     }
-}
 
-impl SemanticAnalysisVisitor for SymbolTableBuilder {
-    fn visit_lit(&mut self, lit: &Lit) -> Type {
-        match lit {
-            Lit::Int(_) => Type::U64,
-            Lit::Bool(_) => todo!(),
-            Lit::Str(_) => todo!(),
-            Lit::Char(_) => todo!(),
+    pub fn into_calls(self) -> Calls {
+        self.calls
+    }
+
+    /// Checks the body of every generic function again for each set of types it is called
+    /// with, `a + b` is fine for a `T` that is a `u64` but not for a `bool`.
+    pub fn check_instances(&mut self, items: &[Item]) {
+        let mut generic_fns = HashMap::new();
+        for item in items.iter() {
+            match item {
+                Item::Fn(item_fn) if !item_fn.generics.is_empty() => {
+                    generic_fns.insert(item_fn.name.value.clone(), (item_fn.clone(), None));
+                }
+                Item::Impl(item_impl) if !item_impl.generics.is_empty() => {
+                    let ty = TypeName::from(&item_impl.ty);
+                    for item_fn in item_impl.fns.iter() {
+                        let item_fn = impl_fn(item_impl, &ty, item_fn);
+                        let name = item_fn.name.value.clone();
+                        generic_fns.insert(name, (item_fn, Some(ty.clone())));
+                    }
+                }
+                _ => {}
+            }
+        }
+        // NOTE: code generation goes by what was recorded for the generic body.
+        let calls = std::mem::take(&mut self.calls);
+        let mut checked = HashSet::new();
+        while let Some((name, type_args, at)) = self.instances.pop_front() {
+            let Some((item_fn, impl_ty)) = generic_fns.get(&name) else {
+                continue;
+            };
+            if !checked.insert((name.clone(), type_args.clone())) {
+                continue;
+            }
+            let params = item_fn
+                .generics
+                .iter()
+                .map(|generic| generic.name.value.clone())
+                .zip(type_args)
+                .collect::<Vec<_>>();
+            self.type_args = params.iter().cloned().collect();
+            if let Some(impl_ty) = impl_ty {
+                let this = impl_ty.substitute(&self.type_args);
+                self.type_args.insert("Self".into(), this);
+            }
+            let reported = self.errors.len();
+            self.visit_item_fn(item_fn);
+            // NOTE: the span of an error in the body does not say which call it is from.
+            let errors = self.errors.split_off(reported);
+            self.errors.extend(errors.into_iter().map(|error| {
+                let name = name.replace('$', "::");
+                SemanticError::InInstance(name, params.clone(), at.clone(), Box::new(error))
+            }));
+        }
+        self.type_args.clear();
+        self.calls = calls;
+    }
+
+    /// A type written in the source, with `Self` and the type parameters filled in.
+    fn type_name(&mut self, ty: &Type) -> TypeName {
+        let type_name = TypeName::from(ty);
+        self.check_type_args(&type_name, ty.span());
+        type_name.substitute(&self.type_args)
+    }
+
+    /// Every struct in `ty` has to be given a type for each of its type parameters.
+    fn check_type_args(&mut self, ty: &TypeName, span: Span) {
+        match ty {
+            TypeName::Custom(name) | TypeName::Generic(name, _) if self.is_struct(ty) => {
+                let args = match ty {
+                    TypeName::Generic(_, args) => args.as_slice(),
+                    _ => &[],
+                };
+                let expected = self.structs[name].generics.len();
+                if expected != args.len() {
+                    let error =
+                        SemanticError::TypeArgCount(name.clone(), expected, args.len(), span);
+                    self.report(error);
+                }
+                for arg in args.iter() {
+                    self.check_type_args(arg, span);
+                }
+            }
+            TypeName::Ptr(_, ty) | TypeName::Array(ty, _) => self.check_type_args(ty, span),
+            TypeName::Fn(params, ret) | TypeName::Closure(params, ret) => {
+                for param in params.iter() {
+                    self.check_type_args(param, span);
+                }
+                self.check_type_args(ret, span);
+            }
+            _ => {}
         }
     }
-    fn visit_expr_binary(&mut self, bin: &ExprBinary) -> Type {
-        let left = self.visit_expr(&bin.left);
-        let right = self.visit_expr(&bin.left);
-        if left != right {
-            todo!("log error")
+
+    /// Whether `ty` is known without the types the current function is called with.
+    fn is_concrete(&self, ty: &TypeName) -> bool {
+        match ty {
+            TypeName::Null => false,
+            TypeName::Custom(_) => !self.is_param(ty),
+            TypeName::Generic(_, args) => args.iter().all(|ty| self.is_concrete(ty)),
+            TypeName::Ptr(_, ty) | TypeName::Array(ty, _) => self.is_concrete(ty),
+            TypeName::Fn(params, ret) | TypeName::Closure(params, ret) => {
+                params.iter().all(|ty| self.is_concrete(ty)) && self.is_concrete(ret)
+            }
+            _ => true,
         }
-        left
     }
-    fn visit_expr_call(&mut self, call: &ExprCall) -> Type {
-        let Expr::Var(ExprVar {
-            name: Ident { value, .. },
-            ..
-        }) = &*call.caller
-        else {
-            panic!("expected Ident found {:?}", call.caller);
+
+    fn is_struct(&self, ty: &TypeName) -> bool {
+        matches!(ty.base(), TypeName::Custom(name) if self.structs.contains_key(&name))
+    }
+
+    /// The type of the field `name` of a struct of type `ty`, with its type parameters filled in.
+    fn field_type(&self, ty: &TypeName, name: &str) -> Option<TypeName> {
+        let (TypeName::Custom(struct_name) | TypeName::Generic(struct_name, _)) = ty else {
+            return None;
+        };
+        let def = self.structs.get(struct_name)?;
+        let field = def.field(name)?;
+        Some(field.substitute(&def.bindings(ty)))
+    }
+
+    /// The copy of a method of a generic struct is checked for the type arguments of `ty` once
+    /// everything else is.
+    fn queue_method(&mut self, label: String, ty: &TypeName, span: Span) {
+        if let TypeName::Generic(_, args) = ty {
+            if self.is_concrete(ty) {
+                let at = self.call_site(span);
+                self.instances.push_back((label, args.clone(), at));
+            }
+        }
+    }
+
+    /// `span` in the function being checked, with the file it is written in.
+    fn call_site(&self, span: Span) -> String {
+        match &self.file {
+            Some(file) => format!("{file}: {span:?}"),
+            None => format!("{span:?}"),
+        }
+    }
+
+    /// Whether `ty` implements the trait, a type parameter does when it is bound by it.
+    fn implements(&self, trait_name: &str, ty: &TypeName) -> bool {
+        match ty {
+            TypeName::Custom(name) if self.bounds.contains_key(name) => {
+                self.bounds[name].iter().any(|bound| bound == trait_name)
+            }
+            _ => self.traits.is_implemented(trait_name, ty),
+        }
+    }
+
+    /// Where a method called `name` on `ty` can come from, `None` for a method of the type itself
+    /// which hides the ones from traits.
+    fn find_method(&self, name: &str, ty: &TypeName) -> Vec<Option<String>> {
+        let inherent = self
+            .traits
+            .inherent
+            .get(&ty.base())
+            .and_then(|sigs| sigs.get(name));
+        if inherent.is_some_and(|sig| sig.receiver().is_some()) {
+            return vec![None];
+        }
+        let mut found = self
+            .traits
+            .methods
+            .iter()
+            .filter(|(trait_name, methods)| {
+                methods.contains_key(name) && self.implements(trait_name, ty)
+            })
+            .map(|(trait_name, _)| trait_name.clone())
+            .collect::<Vec<_>>();
+        found.sort();
+        found.into_iter().map(Some).collect()
+    }
+
+    fn method_sig(&self, trait_name: Option<&str>, ty: &TypeName, name: &str) -> &'a FnSig {
+        match trait_name {
+            Some(trait_name) => &self.traits.methods[trait_name][name],
+            None => &self.traits.inherent[&ty.base()][name],
+        }
+    }
+
+    /// `u64::zero()`, a function declared in an `impl` of the type. The type arguments of a
+    /// generic struct are inferred like those of a generic function, `Self::new()` already has
+    /// them.
+    fn check_assoc_call(
+        &mut self,
+        ty: &Ident,
+        name: &Ident,
+        expr_call: &ExprCall,
+        hint: Option<&TypeName>,
+    ) -> TypeName {
+        let ty = TypeName::from(ty.value.as_str()).substitute(&self.type_args);
+        let sig = self
+            .traits
+            .inherent
+            .get(&ty.base())
+            .and_then(|sigs| sigs.get(&name.value));
+        let Some(sig) = sig else {
+            self.visit_args(&[], &expr_call.args);
+            let error = SemanticError::UnknownFunction(format!("{ty}::{}", name.value), name.span);
+            return self.report(error);
+        };
+        if sig.params.len() != expr_call.args.len() {
+            let error = SemanticError::ArgCount(
+                format!("{ty}::{}", name.value),
+                sig.params.len(),
+                expr_call.args.len(),
+                expr_call.span(),
+            );
+            self.report(error);
+        }
+        let label = method_name(None, &ty, &name.value);
+        if let (TypeName::Custom(_), false) = (&ty, sig.generics.is_empty()) {
+            let params = sig.generics.iter().cloned().map(TypeName::Custom);
+            let this = TypeName::Generic(ty.to_string(), params.collect());
+            let this = HashMap::from([("Self".into(), this)]);
+            let sig = FnSig {
+                params: sig.params.iter().map(|ty| ty.substitute(&this)).collect(),
+                ret: sig.ret.substitute(&this),
+                ..sig.clone()
+            };
+            return self.check_generic_call(&label, &sig, expr_call, hint);
+        }
+        let mut bindings = HashMap::from([("Self".into(), ty.clone())]);
+        if let TypeName::Generic(_, args) = &ty {
+            bindings.extend(sig.generics.iter().cloned().zip(args.iter().cloned()));
+        }
+        self.queue_method(label, &ty, expr_call.span());
+        let params = sig
+            .params
+            .iter()
+            .map(|param| param.substitute(&bindings))
+            .collect::<Vec<_>>();
+        self.visit_args(&params, &expr_call.args);
+        sig.ret.substitute(&bindings)
+    }
+
+    /// Works out the type parameters of a generic function from the arguments it is called with,
+    /// and what is left from `hint` for what it returns.
+    fn check_generic_call(
+        &mut self,
+        name: &str,
+        sig: &FnSig,
+        expr_call: &ExprCall,
+        hint: Option<&TypeName>,
+    ) -> TypeName {
+        // NOTE: literals are checked last, they take the type the other arguments give their
+        // param.
+        let mut types = vec![TypeName::Null; expr_call.args.len()];
+        let mut hints = HashMap::new();
+        for (i, arg) in expr_call.args.iter().enumerate() {
+            if !Self::is_lit(arg) {
+                types[i] = self.visit_expr(arg);
+                if let Some(param) = sig.params.get(i) {
+                    let _ = infer(&sig.generics, param, &types[i], &mut hints, arg.span());
+                }
+            }
+        }
+        let mut returned = HashMap::new();
+        if let Some(hint) = hint {
+            let _ = infer(
+                &sig.generics,
+                &sig.ret,
+                hint,
+                &mut returned,
+                expr_call.span(),
+            );
+        }
+        for (generic, ty) in returned.iter() {
+            hints.entry(generic.clone()).or_insert(ty.clone());
+        }
+        for (i, arg) in expr_call.args.iter().enumerate() {
+            if Self::is_lit(arg) {
+                types[i] = match sig.params.get(i).map(|param| param.substitute(&hints)) {
+                    Some(TypeName::Custom(name)) if sig.generics.contains(&name) => {
+                        self.visit_expr(arg)
+                    }
+                    Some(hint) => self.visit_expr_like(arg, &hint),
+                    None => self.visit_expr(arg),
+                };
+            }
+        }
+        let mut bindings = HashMap::new();
+        let mut inferred = vec![];
+        for (param, ty) in sig.params.iter().zip(types.iter()) {
+            match infer(&sig.generics, param, ty, &mut bindings, expr_call.span()) {
+                Ok(()) => inferred.push(true),
+                Err(error) => {
+                    self.report(error);
+                    inferred.push(false);
+                }
+            }
+        }
+        let mut type_args = vec![];
+        for generic in sig.generics.iter() {
+            let ty = match bindings.get(generic).or(returned.get(generic)) {
+                Some(ty) => ty.clone(),
+                None => {
+                    // NOTE: a method is named by its label, `Vec$new` is written `Vec::new`.
+                    let name = name.replace('$', "::");
+                    let error = SemanticError::CannotInfer(generic.clone(), name, expr_call.span());
+                    self.report(error)
+                }
+            };
+            for bound in sig.bounds[generic].iter() {
+                if ty != TypeName::Null && !self.implements(bound, &ty) {
+                    let error =
+                        SemanticError::MissingImpl(bound.clone(), ty.clone(), expr_call.span());
+                    self.report(error);
+                }
+            }
+            bindings.insert(generic.clone(), ty.clone());
+            type_args.push(ty);
+        }
+        // NOTE: an argument a type parameter was inferred from differently already has an error.
+        let params = sig
+            .params
+            .iter()
+            .zip(inferred)
+            .map(|(param, inferred)| match inferred {
+                true => param.substitute(&bindings),
+                false => TypeName::Null,
+            })
+            .collect::<Vec<_>>();
+        self.expect_args(&params, &types, &expr_call.args);
+        if type_args.iter().all(|ty| self.is_concrete(ty)) {
+            let at = self.call_site(expr_call.span());
+            self.instances
+                .push_back((name.into(), type_args.clone(), at));
+        }
+        let key = (self.current_fn.clone(), expr_call.span());
+        self.calls.generic.insert(key, type_args);
+        sig.ret.substitute(&bindings)
+    }
+
+    /// A generic function or struct can take its type arguments from `hint`, what the call
+    /// returns has to be like it.
+    fn check_call(&mut self, expr_call: &ExprCall, hint: Option<&TypeName>) -> TypeName {
+        let ExprCall { caller, args, .. } = expr_call;
+        let Expr::Var(ExprVar { path, name }) = &**caller else {
+            return self.check_indirect_call(expr_call);
+        };
+        // NOTE: a variable holding a function shadows the function of the same name.
+        if path.is_empty() && self.lookup_var(&name.value).is_some() {
+            return self.check_indirect_call(expr_call);
+        }
+        // NOTE: modules are already resolved, the path that is left names a type.
+        if let [ty] = path.as_slice() {
+            return self.check_assoc_call(ty, name, expr_call, hint);
+        }
+        if let Some(builtin) = Builtin::from_name(&name.value) {
+            let types = self.visit_args(&[], args);
+            return self.check_builtin(builtin, expr_call, &types);
+        }
+        let Some(sig) = self.functions.get(&name.value) else {
+            self.visit_args(&[], args);
+            let error = SemanticError::UnknownFunction(name.value.clone(), name.span);
+            return self.report(error);
+        };
+        if sig.params.len() != args.len() {
+            let error = SemanticError::ArgCount(
+                name.value.clone(),
+                sig.params.len(),
+                args.len(),
+                expr_call.span(),
+            );
+            self.report(error);
+        }
+        if !sig.generics.is_empty() {
+            return self.check_generic_call(&name.value, sig, expr_call, hint);
+        }
+        self.visit_args(&sig.params, args);
+        sig.ret.clone()
+    }
+
+    /// The type arguments of a generic struct come from `hint` when it goes where one is wanted,
+    /// or else from the values of its fields.
+    fn check_struct(&mut self, expr_struct: &ExprStruct, hint: Option<&TypeName>) -> TypeName {
+        let ExprStruct { name, fields, .. } = expr_struct;
+        let Some(def) = self.structs.get(&name.value) else {
+            for (_, expr) in fields.iter() {
+                self.visit_expr(expr);
+            }
+            return self.report(SemanticError::UnknownType(name.value.clone(), name.span));
         };
-        let Some(Symbol::Function(function)) = self.symbol_table.get(value).cloned() else {
-            todo!("log error undefined symbol")
+        let mut bindings = match hint {
+            Some(hint) if hint.base() == TypeName::Custom(name.value.clone()) => def.bindings(hint),
+            _ => HashMap::new(),
         };
+        let mut given = HashSet::new();
+        for (field, expr) in fields.iter() {
+            match def.field(&field.value) {
+                // NOTE: a literal only takes the type of its field once the type arguments are
+                // known, until then it is a `u64` or an `f64`.
+                Some(ty) => {
+                    let expected = ty.substitute(&bindings);
+                    let found = match def.generics.iter().all(|g| bindings.contains_key(g)) {
+                        true => self.visit_expr_like(expr, &expected),
+                        false => self.visit_expr(expr),
+                    };
+                    match infer(&def.generics, &expected, &found, &mut bindings, expr.span()) {
+                        Ok(()) => self.expect(&expected.substitute(&bindings), &found, expr.span()),
+                        Err(error) => {
+                            self.report(error);
+                        }
+                    }
+                }
+                None => {
+                    self.visit_expr(expr);
+                }
+            };
+            if !given.insert(&field.value) {
+                self.report(SemanticError::DuplicateField(
+                    field.value.clone(),
+                    field.span,
+                ));
+                continue;
+            }
+            if def.field(&field.value).is_none() {
+                let ty = TypeName::Custom(name.value.clone());
+                let error = SemanticError::NoField(field.value.clone(), ty, field.span);
+                self.report(error);
+            }
+        }
+        for (field, _) in def.fields.iter() {
+            if !given.contains(field) {
+                let error =
+                    SemanticError::MissingField(field.clone(), name.value.clone(), name.span);
+                self.report(error);
+            }
+        }
+        if def.generics.is_empty() {
+            return TypeName::Custom(name.value.clone());
+        }
+        let mut args = vec![];
+        for generic in def.generics.iter() {
+            let ty = match bindings.get(generic) {
+                Some(ty) => ty.clone(),
+                None => {
+                    let error = SemanticError::CannotInfer(
+                        generic.clone(),
+                        name.value.clone(),
+                        expr_struct.span(),
+                    );
+                    self.report(error)
+                }
+            };
+            args.push(ty);
+        }
+        let ty = TypeName::Generic(name.value.clone(), args);
+        let key = (self.current_fn.clone(), expr_struct.span());
+        self.calls.lits.insert(key, ty.clone());
+        ty
+    }
 
-        if function.params.len() != call.args.len() {
-            todo!("log error wrong number of arguments")
+    fn check_builtin(
+        &mut self,
+        builtin: Builtin,
+        expr_call: &ExprCall,
+        types: &[TypeName],
+    ) -> TypeName {
+        if !builtin.arity().contains(&types.len()) {
+            let error =
+                SemanticError::BuiltinArgCount(builtin.name(), builtin.arity(), expr_call.span());
+            return self.report(error);
+        }
+        for (ty, arg) in types.iter().zip(expr_call.args.iter()) {
+            if builtin.is_print() && self.implements(stdlib::DISPLAY, ty) {
+                let label = method_name(Some(stdlib::DISPLAY), ty, "fmt");
+                self.queue_method(label, ty, arg.span());
+            }
+            let error = match builtin {
+                _ if builtin.is_print() => (matches!(
+                    ty,
+                    TypeName::Array(..)
+                        | TypeName::Ptr(..)
+                        | TypeName::Fn(..)
+                        | TypeName::Closure(..)
+                        | TypeName::F32
+                        | TypeName::F64
+                        | TypeName::Void
+                ) || self.is_struct(ty)
+                    && !self.implements(stdlib::DISPLAY, ty))
+                .then(|| SemanticError::NotPrintable(ty.clone(), arg.span())),
+                // NOTE: a str is handed to the kernel as a pointer to its bytes.
+                Builtin::Syscall => (matches!(
+                    ty,
+                    TypeName::Array(..) | TypeName::F32 | TypeName::F64 | TypeName::Void
+                ) || self.is_struct(ty))
+                .then(|| SemanticError::NotSyscallArg(ty.clone(), arg.span())),
+                Builtin::Len => (!matches!(ty, TypeName::Str | TypeName::Null))
+                    .then(|| SemanticError::NoLen(ty.clone(), arg.span())),
+                Builtin::Panic => (!matches!(ty, TypeName::Str | TypeName::Null))
+                    .then(|| SemanticError::PanicMsg(ty.clone(), arg.span())),
+                Builtin::SizeOfVal => (!matches!(ty, TypeName::Ptr(..) | TypeName::Null))
+                    .then(|| SemanticError::SizeOfNonPointer(ty.clone(), arg.span())),
+                _ => None,
+            };
+            if let Some(error) = error {
+                self.report(error);
+            }
         }
+        builtin.ret_type()
+    }
 
-        for (param, arg) in function.params.iter().zip(call.args.iter()) {
-            let arg_type = self.visit_expr(arg);
-            if param.ty != arg_type {
-                todo!(
-                    "log error type mismatch, expected {:?} found {:?}",
-                    param.ty,
-                    arg_type
-                );
+    /// A call through a function value or a closure, the callee is whatever expression it was
+    /// held in.
+    fn check_indirect_call(&mut self, expr_call: &ExprCall) -> TypeName {
+        let ty = self.visit_expr(&expr_call.caller);
+        match &ty {
+            TypeName::Fn(params, ret) | TypeName::Closure(params, ret) => {
+                if params.len() != expr_call.args.len() {
+                    let error = SemanticError::ArgCount(
+                        ty.to_string(),
+                        params.len(),
+                        expr_call.args.len(),
+                        expr_call.span(),
+                    );
+                    self.report(error);
+                }
+                self.visit_args(params, &expr_call.args);
+                *ret.clone()
+            }
+            _ => {
+                self.visit_args(&[], &expr_call.args);
+                match ty {
+                    TypeName::Null => TypeName::Null,
+                    _ => self.report(SemanticError::NotCallable(ty, expr_call.caller.span())),
+                }
+            }
+        }
+    }
+
+    /// A `let` shadows anything with the same name until the end of its block.
+    fn declare_var(&mut self, name: &str, ty: TypeName) {
+        if let Some(scope) = self.vars.last_mut() {
+            scope.insert(name.into(), ty);
+        }
+    }
+
+    fn lookup_var(&self, name: &str) -> Option<&TypeName> {
+        self.vars.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// The index of the scope a variable was declared in.
+    fn var_depth(&self, name: &str) -> Option<usize> {
+        self.vars.iter().rposition(|scope| scope.contains_key(name))
+    }
+
+    /// Every closure between the variable and its use takes it along.
+    fn capture(&mut self, name: &str, ty: &TypeName) {
+        let Some(depth) = self.var_depth(name) else {
+            return;
+        };
+        for closure in self.closures.iter_mut().filter(|c| depth < c.depth) {
+            if closure.captures.iter().any(|capture| capture.name == name) {
+                continue;
+            }
+            closure.captures.push(Capture {
+                name: name.into(),
+                ty: ty.clone(),
+                by_ref: false,
+            });
+        }
+    }
+
+    /// Whether a value of type `ty` has a closure in it, a pointer is not followed.
+    fn holds_closure(&self, ty: &TypeName, seen: &mut Vec<String>) -> bool {
+        match ty {
+            TypeName::Closure(..) => true,
+            TypeName::Array(ty, _) => self.holds_closure(ty, seen),
+            TypeName::Custom(name) | TypeName::Generic(name, _) if !seen.contains(name) => {
+                let Some(def) = self.structs.get(name) else {
+                    return false;
+                };
+                seen.push(name.clone());
+                let bindings = def.bindings(ty);
+                def.fields
+                    .iter()
+                    .any(|(_, field)| self.holds_closure(&field.substitute(&bindings), seen))
+            }
+            _ => false,
+        }
+    }
+
+    /// Reports a closure in a value of type `ty` that leaves the function it was made in.
+    fn check_escape(&mut self, ty: &TypeName, span: Span) {
+        if self.holds_closure(ty, &mut vec![]) {
+            self.report(SemanticError::EscapingClosure(span));
+        }
+    }
+
+    /// Whether writing to `place` can outlive the current function or closure, only one of its
+    /// own locals does not.
+    fn outlives(&self, place: &Expr) -> bool {
+        let Expr::Var(ExprVar { path, name }) = place else {
+            return true;
+        };
+        match self.var_depth(&name.value) {
+            Some(depth) => {
+                !path.is_empty() || self.closures.last().is_some_and(|c| depth < c.depth)
             }
+            None => true,
         }
+    }
 
-        function.ret
+    /// A captured local that is written to, or borrowed as `&mut`, is captured by reference so
+    /// the function it belongs to sees the change.
+    fn capture_by_ref(&mut self, place: &Expr) {
+        let name = match place {
+            Expr::Var(ExprVar { path, name }) if path.is_empty() => name,
+            Expr::Field(ExprField { expr, .. }) | Expr::Index(ExprIndex { expr, .. }) => {
+                return self.capture_by_ref(expr);
+            }
+            _ => return,
+        };
+        let Some(depth) = self.var_depth(&name.value) else {
+            return;
+        };
+        for closure in self.closures.iter_mut().filter(|c| depth < c.depth) {
+            for capture in closure.captures.iter_mut() {
+                if capture.name == name.value {
+                    capture.by_ref = true;
+                }
+            }
+        }
     }
-    fn visit_expr_var(&mut self, var: &ExprVar) -> Type {
-        let ExprVar { name, .. } = var;
-        let Ident { value, .. } = name;
-        let Some(Symbol::Variable(var)) = self.symbol_table.get(value) else {
-            todo!("log error undefined symbol")
+
+    fn report(&mut self, error: SemanticError) -> TypeName {
+        let error = match &self.file {
+            Some(file) => SemanticError::InFile(file.clone(), Box::new(error)),
+            None => error,
         };
-        var.ty
+        self.errors.push(error);
+        TypeName::Null
     }
-    fn visit_expr_return(&mut self, var: &ExprReturn) -> Type {
-        self.visit_expr(&var.expr)
+
+    /// Whether a value of type `found` can be used where a value of type `expected` goes, a
+    /// `&mut T` can stand in for a `&T`.
+    fn fits(expected: &TypeName, found: &TypeName) -> bool {
+        match (expected, found) {
+            // NOTE: an error was already reported for whatever this came from.
+            (TypeName::Null, _) | (_, TypeName::Null) => true,
+            (TypeName::Ptr(false, expected), TypeName::Ptr(_, found)) => expected == found,
+            _ => expected == found,
+        }
+    }
+
+    /// Reports a value of type `found` at `span` where a value of type `expected` goes.
+    fn expect(&mut self, expected: &TypeName, found: &TypeName, span: Span) {
+        if !Self::fits(expected, found) {
+            let error = SemanticError::TypeMismatch(expected.clone(), found.clone(), span);
+            self.report(error);
+        }
     }
-    fn visit_expr(&mut self, expr: &Expr) -> Type {
+
+    /// Checks `expr` where a value of type `expected` goes.
+    fn visit_expr_as(&mut self, expr: &Expr, expected: &TypeName) -> TypeName {
+        let found = self.visit_expr_like(expr, expected);
+        self.expect(expected, &found, expr.span());
+        found
+    }
+
+    /// Checks `expr` where a value like `hint` is wanted, a number literal takes its type when it
+    /// can.
+    fn visit_expr_like(&mut self, expr: &Expr, hint: &TypeName) -> TypeName {
         match expr {
-            Expr::Lit(ExprLit { lit }) => self.visit_lit(lit),
-            Expr::Binary(bin) => self.visit_expr_binary(bin),
-            Expr::Call(call) => self.visit_expr_call(call),
-            Expr::Var(var) => self.visit_expr_var(var),
-            Expr::Let(elet) => todo!(),
-            Expr::If(_if_expr) => todo!(),
-            Expr::Block(block) => {
-                // HACK: Need to return the last statements type
-                self.visit_block(block);
-                Type::Unit
-            }
-            Expr::Return(ret) => self.visit_expr_return(ret),
-        }
-    }
-    fn visit_stmt(&mut self, stmt: &Statement) {
-        self.visit_expr(&stmt.stmt);
-    }
-    fn visit_block(&mut self, block: &ExprBlock) {
-        self.scope.push(Scope::Block);
-        for stmt in &block.stmts {
-            self.visit_stmt(stmt);
-        }
-        self.scope.pop();
-    }
-
-    fn visit_params(&mut self, params: &[Param]) {
-        self.scope.push(Scope::Param);
-        for param in params {
-            let symbol = Variable {
-                name: param.name.to_string(),
-                scope: self.current_scope(),
-                ty: Type::from(&param.kind),
-                span: param.span,
+            Expr::Lit(ExprLit { lit: Lit::Int(lit) }) if hint.is_int() || self.is_param(hint) => {
+                self.int_lit(lit, hint)
+            }
+            Expr::Lit(ExprLit {
+                lit: Lit::Float(lit),
+            }) if hint.is_float() => self.float_lit(lit, hint),
+            Expr::Binary(expr_binary) => self.check_binary(expr_binary, Some(hint)),
+            Expr::Array(expr_array) => match hint {
+                TypeName::Array(elem, _) => self.check_array(expr_array, Some(elem)),
+                _ => self.visit_expr(expr),
+            },
+            Expr::ArrayRepeat(expr_array_repeat) => match hint {
+                TypeName::Array(elem, _) => self.check_array_repeat(expr_array_repeat, Some(elem)),
+                _ => self.visit_expr(expr),
+            },
+            Expr::Call(expr_call) => self.check_call(expr_call, Some(hint)),
+            Expr::Struct(expr_struct) => self.check_struct(expr_struct, Some(hint)),
+            _ => self.visit_expr(expr),
+        }
+    }
+
+    /// The elements are checked like `elem` when the array goes where one is wanted, otherwise
+    /// the first one decides what the others have to be.
+    fn check_array(&mut self, expr_array: &ExprArray, elem: Option<&TypeName>) -> TypeName {
+        let ExprArray { elems, .. } = expr_array;
+        let mut elem = elem.cloned();
+        for expr in elems.iter() {
+            match &elem {
+                Some(ty) => {
+                    self.visit_expr_as(expr, ty);
+                }
+                None => elem = Some(self.visit_expr(expr)),
+            }
+        }
+        let elem = elem.unwrap_or(TypeName::Null);
+        self.array_lit(expr_array.span(), elem, elems.len())
+    }
+
+    fn check_array_repeat(
+        &mut self,
+        expr_array_repeat: &ExprArrayRepeat,
+        elem: Option<&TypeName>,
+    ) -> TypeName {
+        let ExprArrayRepeat { expr, len, .. } = expr_array_repeat;
+        let elem = match elem {
+            Some(elem) => self.visit_expr_like(expr, elem),
+            None => self.visit_expr(expr),
+        };
+        let len = len.value();
+        self.array_lit(expr_array_repeat.span(), elem, len)
+    }
+
+    /// Records the type of an array literal, it is laid out before its elements are worked out.
+    fn array_lit(&mut self, span: Span, elem: TypeName, len: usize) -> TypeName {
+        let ty = TypeName::Array(Box::new(elem), len);
+        let key = (self.current_fn.clone(), span);
+        self.calls.lits.insert(key, ty.clone());
+        ty
+    }
+
+    /// Whether `expr` is made of nothing but number literals, which get their type from the
+    /// values they meet.
+    fn is_lit(expr: &Expr) -> bool {
+        match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(_) | Lit::Float(_),
+            }) => true,
+            Expr::Binary(ExprBinary { left, right, op }) => {
+                !op.is_comparison() && Self::is_lit(left) && Self::is_lit(right)
+            }
+            _ => false,
+        }
+    }
+
+    fn is_param(&self, ty: &TypeName) -> bool {
+        matches!(ty, TypeName::Custom(name) if self.bounds.contains_key(name))
+    }
+
+    /// An int literal used as a `ty`, it has to fit in one.
+    fn int_lit(&mut self, lit: &LitInt, ty: &TypeName) -> TypeName {
+        let max = match ty {
+            TypeName::U8 => u8::MAX.into(),
+            TypeName::U32 => u32::MAX.into(),
+            TypeName::I32 => i32::MAX as u64,
+            TypeName::I64 => i64::MAX as u64,
+            _ => u64::MAX,
+        };
+        if !lit.parse::<u64>().is_ok_and(|value| value <= max) {
+            let error = SemanticError::LitOutOfRange(lit.value.clone(), ty.clone(), lit.span);
+            return self.report(error);
+        }
+        if *ty != TypeName::U64 {
+            let key = (self.current_fn.clone(), lit.span);
+            self.calls.lits.insert(key, ty.clone());
+        }
+        ty.clone()
+    }
+
+    fn float_lit(&mut self, lit: &LitFloat, ty: &TypeName) -> TypeName {
+        if *ty != TypeName::F64 {
+            let key = (self.current_fn.clone(), lit.span);
+            self.calls.lits.insert(key, ty.clone());
+        }
+        ty.clone()
+    }
+
+    /// Checks the arguments of a call against the params they are passed as, any past the end
+    /// of `params` are only checked themselves.
+    fn visit_args(&mut self, params: &[TypeName], args: &[Expr]) -> Vec<TypeName> {
+        args.iter()
+            .enumerate()
+            .map(|(i, arg)| match params.get(i) {
+                Some(param) => self.visit_expr_as(arg, param),
+                None => self.visit_expr(arg),
+            })
+            .collect()
+    }
+
+    /// The operands are checked with `hint` when the result of the operator goes somewhere
+    /// that wants one, then a literal on either side takes the type of the other.
+    fn check_binary(&mut self, expr_binary: &ExprBinary, hint: Option<&TypeName>) -> TypeName {
+        let ExprBinary { left, right, op } = expr_binary;
+        let comparison = op.is_comparison();
+        let (ty, rhs) = match hint.filter(|_| !comparison) {
+            Some(hint) => {
+                let ty = self.visit_expr_like(left, hint);
+                (ty.clone(), self.visit_expr_like(right, &ty))
+            }
+            None if Self::is_lit(left) && !Self::is_lit(right) => {
+                let rhs = self.visit_expr(right);
+                (self.visit_expr_like(left, &rhs), rhs)
+            }
+            None => {
+                let ty = self.visit_expr(left);
+                (ty.clone(), self.visit_expr_like(right, &ty))
+            }
+        };
+        if ty == TypeName::Null || rhs == TypeName::Null {
+            return TypeName::Null;
+        }
+        // NOTE: two values that could be cast to each other are told to, anything else simply
+        // has the wrong type.
+        if ty != rhs {
+            let scalar = |ty: &TypeName| {
+                ty.is_int() || ty.is_float() || matches!(ty, TypeName::Bool | TypeName::Char)
+            };
+            let error = match scalar(&ty) && scalar(&rhs) {
+                true => SemanticError::MixedTypes(ty, rhs, expr_binary.span()),
+                false => SemanticError::TypeMismatch(ty, rhs, right.span()),
+            };
+            return self.report(error);
+        }
+        let valid = match &ty {
+            _ if ty.is_int() || ty.is_float() => true,
+            TypeName::Bool | TypeName::Ptr(..) => matches!(op, Op::Neq(_) | Op::EqualEqual(_)),
+            TypeName::Char => comparison,
+            // NOTE: what a type parameter can do is up to the types it is called with.
+            TypeName::Custom(_) => self.is_param(&ty),
+            _ => false,
+        };
+        if !valid {
+            let error = SemanticError::InvalidOp(op.to_string(), ty, expr_binary.span());
+            return self.report(error);
+        }
+        match comparison {
+            true => TypeName::Bool,
+            false => ty,
+        }
+    }
+
+    /// Checks the arguments of a call against the params they are passed as.
+    fn expect_args(&mut self, params: &[TypeName], types: &[TypeName], args: &[Expr]) {
+        for (param, (ty, arg)) in params.iter().zip(types.iter().zip(args.iter())) {
+            self.expect(param, ty, arg.span());
+        }
+    }
+
+    /// The signature of a function called from C or calling into it.
+    /// `main` is handed `argc` and pointers to the `argv` and `envp` arrays, it can take any
+    /// number of them from the front.
+    fn check_main_params(&mut self, name: &Ident, params: &[Param]) {
+        if params.len() > 3 {
+            self.report(SemanticError::MainParams(name.span));
+        }
+        let ptr = TypeName::Ptr(true, Box::new(TypeName::U64));
+        let expected = [
+            ("argc", TypeName::U64),
+            ("argv", ptr.clone()),
+            ("envp", ptr),
+        ];
+        for (param, (slot, expected)) in params.iter().zip(expected) {
+            let found = TypeName::from(&param.kind);
+            // NOTE: the arrays are never written to, a shared pointer to them is fine as well.
+            let fits = match (&expected, &found) {
+                (TypeName::Ptr(_, expected), TypeName::Ptr(_, found)) => expected == found,
+                (expected, found) => expected == found,
             };
-            self.symbol_table.insert(symbol.into());
+            if !fits {
+                let error = SemanticError::MainParamType(slot, expected, found, param.kind.span());
+                self.report(error);
+            }
+        }
+    }
+
+    fn check_c_signature(
+        &mut self,
+        abi: &Option<LitStr>,
+        params: &[Param],
+        ret_type: &Option<Type>,
+    ) {
+        if let Some(abi) = abi.as_ref().filter(|abi| abi.value != "C") {
+            self.report(SemanticError::UnknownAbi(abi.value.clone(), abi.span));
+        }
+        // NOTE: C gets every value in a register of its own, a `str` or a struct does not fit.
+        let types = params
+            .iter()
+            .map(|param| &param.kind)
+            .chain(ret_type.iter());
+        for ty in types {
+            let type_name = TypeName::from(ty);
+            if !(type_name.is_int()
+                || type_name.is_float()
+                || matches!(
+                    type_name,
+                    TypeName::Bool | TypeName::Char | TypeName::Ptr(..)
+                ))
+            {
+                self.report(SemanticError::NotCType(type_name, ty.span()));
+            }
         }
-        self.scope.pop();
     }
+}
+
+impl SemanticAnalysisVisitor for TypeChecker<'_> {
     fn visit_item_fn(&mut self, item_fn: &ItemFn) {
-        let symbol = Function {
-            scope: Scope::Global,
-            name: item_fn.name.to_string(),
-            params: item_fn
-                .params
-                .iter()
-                .map(|param| Variable {
-                    name: param.name.to_string(),
-                    scope: Scope::Param,
-                    ty: Type::from(&param.kind),
-                    span: param.span,
-                })
-                .collect(),
-            ret: item_fn
-                .ret_type
-                .as_ref()
-                .map(Type::from)
-                .unwrap_or_default(),
-            span: item_fn.span(),
-        };
-
-        self.symbol_table.insert(symbol.into());
-        self.visit_params(&item_fn.params);
-        self.visit_block(&item_fn.block);
+        let ItemFn {
+            name,
+            generics,
+            params,
+            block,
+            ret_type,
+            abi,
+            ..
+        } = item_fn;
+        self.file = item_fn.file.clone();
+        if name.value == "main" {
+            self.check_main_params(name, params);
+        }
+        if item_fn.is_export() {
+            if !item_fn.is_pub() {
+                self.report(SemanticError::ExportNotPub(name.value.clone(), name.span));
+            }
+            self.check_c_signature(abi, params, ret_type);
+        }
+        // NOTE: the environment of a closure is in the frame of the function making it.
+        if let Some(ty) = ret_type {
+            self.check_escape(&TypeName::from(ty), ty.span());
+        }
+        self.current_fn = name.value.clone();
+        self.ret = ret_type
+            .as_ref()
+            .map(|ty| self.type_name(ty))
+            .unwrap_or(TypeName::Null);
+        // NOTE: a type parameter filled in for an instance is no longer bound by anything.
+        self.bounds = generics
+            .iter()
+            .filter(|g| !self.type_args.contains_key(&g.name.value))
+            .map(|g| {
+                let bounds = g.bounds.iter().map(|b| b.value.clone()).collect();
+                (g.name.value.clone(), bounds)
+            })
+            .collect();
+        self.vars = vec![HashMap::new()];
+        for param in params.iter() {
+            self.visit_params(param);
+        }
+        self.visit_expr_block(block);
+    }
+
+    fn visit_item_extern(&mut self, item_extern: &ItemExtern) {
+        let ItemExtern {
+            abi,
+            params,
+            ret_type,
+            ..
+        } = item_extern;
+        self.file = None;
+        self.check_c_signature(abi, params, ret_type);
+    }
+
+    fn visit_item_impl(&mut self, item_impl: &ItemImpl) {
+        let ItemImpl { ty, fns, .. } = item_impl;
+        let ty = TypeName::from(ty);
+        self.type_args = HashMap::from([("Self".into(), ty.clone())]);
+        for item_fn in fns.iter() {
+            self.visit_item_fn(&impl_fn(item_impl, &ty, item_fn));
+        }
+        self.type_args.clear();
+    }
+
+    fn visit_params(&mut self, param: &Param) {
+        let Param { name, kind, .. } = param;
+        let ty = self.type_name(kind);
+        self.declare_var(&name.value, ty);
+    }
+
+    fn visit_lit(&mut self, lit: &Lit) -> TypeName {
+        match lit {
+            Lit::Int(lit) => self.int_lit(lit, &TypeName::U64),
+            Lit::Float(lit) => self.float_lit(lit, &TypeName::F64),
+            Lit::Bool(_) => TypeName::Bool,
+            Lit::Str(lit) => {
+                if let Some(escape) = lit.invalid_escape() {
+                    self.report(SemanticError::InvalidEscape(escape, lit.span));
+                }
+                TypeName::Str
+            }
+            Lit::Char(lit) => match (lit.invalid_escape(), lit.to_char()) {
+                (Some(escape), _) => self.report(SemanticError::InvalidEscape(escape, lit.span)),
+                (None, Some(_)) => TypeName::Char,
+                (None, None) => self.report(SemanticError::InvalidCharLit(lit.span)),
+            },
+        }
+    }
+
+    fn visit_expr_call(&mut self, expr_call: &ExprCall) -> TypeName {
+        self.check_call(expr_call, None)
+    }
+
+    fn visit_expr_method_call(&mut self, expr_method_call: &ExprMethodCall) -> TypeName {
+        let ExprMethodCall {
+            receiver,
+            name,
+            args,
+            ..
+        } = expr_method_call;
+        let (ty, mutable) = self.visit_place(receiver);
+        if ty == TypeName::Null {
+            self.visit_args(&[], args);
+            return TypeName::Null;
+        }
+        let mut found = self.find_method(&name.value, &ty);
+        let mut self_ty = ty.clone();
+        // NOTE: a method of `T` can be called through a `&T`.
+        if let (true, TypeName::Ptr(_, inner)) = (found.is_empty(), &ty) {
+            found = self.find_method(&name.value, inner);
+            self_ty = *inner.clone();
+        }
+        let trait_name = match found.as_slice() {
+            [] => {
+                self.visit_args(&[], args);
+                let error = SemanticError::NoMethod(name.value.clone(), ty, name.span);
+                return self.report(error);
+            }
+            [trait_name] => trait_name.clone(),
+            [first, second, ..] => {
+                self.visit_args(&[], args);
+                let error = SemanticError::AmbiguousMethod(
+                    name.value.clone(),
+                    first.clone().unwrap_or_default(),
+                    second.clone().unwrap_or_default(),
+                    name.span,
+                );
+                return self.report(error);
+            }
+        };
+        let sig = self.method_sig(trait_name.as_deref(), &self_ty, &name.value);
+        let through_ptr = self_ty != ty;
+        let receiver = match sig.receiver() {
+            Some(SelfParam::Value) if through_ptr => Receiver::Deref,
+            Some(SelfParam::Value) => Receiver::Value,
+            Some(param) if through_ptr => {
+                if param == SelfParam::RefMut
+                    && ty == TypeName::Ptr(false, Box::new(self_ty.clone()))
+                {
+                    self.report(SemanticError::MutBorrowThroughSharedRef(receiver.span()));
+                }
+                Receiver::Value
+            }
+            Some(param) => {
+                // NOTE: a temporary is borrowed from the slot it is written to.
+                match mutable {
+                    None => {}
+                    Some(false) if param == SelfParam::RefMut => {
+                        self.report(SemanticError::MutBorrowThroughSharedRef(receiver.span()));
+                    }
+                    Some(_) if param == SelfParam::RefMut => self.capture_by_ref(receiver),
+                    Some(_) => {}
+                }
+                Receiver::Borrow
+            }
+            None => unreachable!("only methods are found"),
+        };
+        // NOTE: the receiver is the first parameter.
+        let expected = sig.params.len().saturating_sub(1);
+        let mut bindings = HashMap::from([("Self".into(), self_ty.clone())]);
+        if let TypeName::Generic(_, args) = &self_ty {
+            bindings.extend(sig.generics.iter().cloned().zip(args.iter().cloned()));
+        }
+        let label = method_name(trait_name.as_deref(), &self_ty, &name.value);
+        self.queue_method(label, &self_ty, expr_method_call.span());
+        let params = sig
+            .params
+            .iter()
+            .skip(1)
+            .map(|param| param.substitute(&bindings))
+            .collect::<Vec<_>>();
+        self.visit_args(&params, args);
+        let ret = sig.ret.substitute(&bindings);
+        if expected != args.len() {
+            let error = SemanticError::ArgCount(
+                name.value.clone(),
+                expected,
+                args.len(),
+                expr_method_call.span(),
+            );
+            self.report(error);
+        }
+        let key = (self.current_fn.clone(), expr_method_call.span());
+        let method = Method {
+            trait_name,
+            ty: self_ty,
+            receiver,
+        };
+        self.calls.method.insert(key, method);
+        ret
+    }
+
+    fn visit_expr_binary(&mut self, expr_binary: &ExprBinary) -> TypeName {
+        self.check_binary(expr_binary, None)
+    }
+
+    fn visit_expr_let(&mut self, expr_let: &ExprLet) -> TypeName {
+        let ExprLet { name, ty, expr, .. } = expr_let;
+        let ty = match ty.as_ref().map(|ty| self.type_name(ty)) {
+            Some(ty) => {
+                self.visit_expr_as(expr, &ty);
+                ty
+            }
+            None => self.visit_expr(expr),
+        };
+        self.declare_var(&name.value, ty);
+        TypeName::Void
+    }
+
+    fn visit_expr_if(&mut self, expr_if: &ExprIf) -> TypeName {
+        let ExprIf {
+            cond,
+            then_branch,
+            else_branch,
+            ..
+        } = expr_if;
+        match self.visit_expr(cond) {
+            TypeName::Bool | TypeName::Null => {}
+            ty => {
+                self.report(SemanticError::NonBoolCondition(ty, cond.span()));
+            }
+        }
+        let ty = self.visit_expr_block(then_branch);
+        if let Some((_, else_branch)) = else_branch {
+            self.visit_expr(else_branch);
+        }
+        ty
+    }
+
+    fn visit_expr_for(&mut self, expr_for: &ExprFor) -> TypeName {
+        let ExprFor {
+            name,
+            iter,
+            end,
+            body,
+            ..
+        } = expr_for;
+        // NOTE: both ends of a range have the same int type, a `Vec` hands out its items.
+        let item = match end {
+            Some((_, end)) => {
+                let (ty, end_ty) = match Self::is_lit(iter) && !Self::is_lit(end) {
+                    true => {
+                        let end_ty = self.visit_expr(end);
+                        (self.visit_expr_like(iter, &end_ty), end_ty)
+                    }
+                    false => {
+                        let ty = self.visit_expr(iter);
+                        (ty.clone(), self.visit_expr_like(end, &ty))
+                    }
+                };
+                match [ty.clone(), end_ty.clone()]
+                    .into_iter()
+                    .find(|ty| !ty.is_int())
+                {
+                    Some(TypeName::Null) => TypeName::Null,
+                    Some(ty) => self.report(SemanticError::NotIterable(ty, iter.span())),
+                    None if ty != end_ty => {
+                        let error = SemanticError::MixedTypes(ty, end_ty, expr_for.span());
+                        self.report(error)
+                    }
+                    None => ty,
+                }
+            }
+            None => match self.visit_expr(iter) {
+                TypeName::Null => TypeName::Null,
+                ty => match vec_item(&ty) {
+                    Some(item) => item,
+                    None => self.report(SemanticError::NotIterable(ty, iter.span())),
+                },
+            },
+        };
+        self.enter_scope();
+        self.declare_var(&name.value, item);
+        self.visit_expr_block(body);
+        self.leave_scope();
+        TypeName::Void
+    }
+
+    fn visit_expr_array(&mut self, expr_array: &ExprArray) -> TypeName {
+        self.check_array(expr_array, None)
+    }
+
+    fn visit_expr_array_repeat(&mut self, expr_array_repeat: &ExprArrayRepeat) -> TypeName {
+        self.check_array_repeat(expr_array_repeat, None)
+    }
+
+    fn visit_expr_assign(&mut self, expr_assign: &ExprAssign) -> TypeName {
+        let ExprAssign { target, value, .. } = expr_assign;
+        let (ty, mutable) = self.visit_place(target);
+        self.visit_expr_as(value, &ty);
+        self.capture_by_ref(target);
+        // NOTE: a generic body only moves the values it is given, a closure in one was made and
+        // checked by its caller.
+        let instance = self.type_args.keys().any(|name| name != "Self");
+        if !instance && self.outlives(target) {
+            self.check_escape(&ty, expr_assign.span());
+        }
+        match mutable {
+            None => self.report(SemanticError::InvalidAssignTarget(target.span())),
+            Some(false) => self.report(SemanticError::AssignThroughSharedRef(target.span())),
+            Some(true) => ty,
+        }
+    }
+
+    fn visit_expr_unary(&mut self, expr_unary: &ExprUnary) -> TypeName {
+        let (ty, mutable) = self.visit_place(&expr_unary.expr);
+        let want_mut = expr_unary.mutable.is_some();
+        if want_mut {
+            self.capture_by_ref(&expr_unary.expr);
+        }
+        match mutable {
+            None => self.report(SemanticError::AddrOfTemporary(expr_unary.span())),
+            Some(false) if want_mut => {
+                self.report(SemanticError::MutBorrowThroughSharedRef(expr_unary.span()))
+            }
+            Some(_) => TypeName::Ptr(want_mut, Box::new(ty)),
+        }
+    }
+
+    fn visit_expr_struct(&mut self, expr_struct: &ExprStruct) -> TypeName {
+        self.check_struct(expr_struct, None)
+    }
+
+    fn visit_expr_closure(&mut self, expr_closure: &ExprClosure) -> TypeName {
+        let ExprClosure {
+            params, ret, body, ..
+        } = expr_closure;
+        let declared = ret.as_ref().map(|ty| self.type_name(ty));
+        let outer = std::mem::replace(&mut self.ret, declared.clone().unwrap_or(TypeName::Null));
+        self.enter_scope();
+        self.closures.push(ClosureScope {
+            depth: self.vars.len() - 1,
+            captures: vec![],
+        });
+        let params = params
+            .iter()
+            .map(|Param { name, kind, .. }| {
+                let ty = self.type_name(kind);
+                self.declare_var(&name.value, ty.clone());
+                ty
+            })
+            .collect();
+        let body = self.visit_expr(body);
+        let captures = self.closures.pop().map(|c| c.captures).unwrap_or_default();
+        self.leave_scope();
+        self.ret = outer;
+        let ret = declared.unwrap_or(body);
+        self.check_escape(&ret, expr_closure.span());
+        let key = (self.current_fn.clone(), expr_closure.span());
+        let closure = Closure {
+            captures,
+            ret: ret.clone(),
+        };
+        self.calls.closures.insert(key, closure);
+        TypeName::Closure(params, Box::new(ret))
+    }
+
+    fn visit_expr_cast(&mut self, expr_cast: &ExprCast) -> TypeName {
+        let ExprCast { expr, ty, .. } = expr_cast;
+        let from = self.visit_expr(expr);
+        let to = self.type_name(ty);
+        // NOTE: any number can become any other number, a `bool` or `char` only an int.
+        // A pointer is an address, it goes to and from a `u64` or to another pointer. A `str` gives
+        // up the pointer to its bytes.
+        let numeric = |ty: &TypeName| ty.is_int() || ty.is_float();
+        let valid = match (&from, &to) {
+            _ if from == to => true,
+            (TypeName::Bool | TypeName::Char, _) => to.is_int(),
+            (TypeName::Ptr(..), TypeName::Ptr(..) | TypeName::U64)
+            | (TypeName::U64, TypeName::Ptr(..)) => true,
+            (TypeName::Str, TypeName::Ptr(_, elem)) => **elem == TypeName::U8,
+            _ => numeric(&from) && numeric(&to),
+        };
+        if valid {
+            return to;
+        }
+        self.report(SemanticError::InvalidCast(from, to, expr_cast.span()))
+    }
+
+    fn visit_expr_return(&mut self, expr_return: &ExprReturn) -> TypeName {
+        let ExprReturn { expr, .. } = expr_return;
+        // NOTE: a function that does not say what it returns can still hand back its value.
+        match self.ret.clone() {
+            TypeName::Null => {
+                let ty = self.visit_expr(expr);
+                self.check_escape(&ty, expr.span());
+            }
+            expected => {
+                self.visit_expr_as(expr, &expected);
+            }
+        };
+        TypeName::Void
+    }
+
+    fn enter_scope(&mut self) {
+        self.vars.push(HashMap::new());
+    }
+
+    fn leave_scope(&mut self) {
+        self.vars.pop();
+    }
+
+    fn visit_place(&mut self, expr: &Expr) -> Place {
+        match expr {
+            Expr::Var(ExprVar { name, .. }) => match self.lookup_var(&name.value).cloned() {
+                Some(ty) => {
+                    self.capture(&name.value, &ty);
+                    (ty, Some(true))
+                }
+                // NOTE: a const is a value, it has no place to write to or borrow.
+                None if self.globals.contains_key(&name.value) => {
+                    let global = &self.globals[&name.value];
+                    (global.ty.clone(), global.is_static.then_some(true))
+                }
+                // NOTE: a function used as a value is its address, it has no place either.
+                None => match self.functions.get(&name.value) {
+                    Some(sig) if !sig.generics.is_empty() => {
+                        let error = SemanticError::GenericFnValue(name.value.clone(), name.span);
+                        (self.report(error), None)
+                    }
+                    Some(sig) if sig.is_extern => {
+                        let error = SemanticError::ExternFnValue(name.value.clone(), name.span);
+                        (self.report(error), None)
+                    }
+                    Some(sig) => {
+                        let ty = TypeName::Fn(sig.params.clone(), Box::new(sig.ret.clone()));
+                        (ty, None)
+                    }
+                    None => {
+                        let error = SemanticError::UnknownVariable(name.value.clone(), name.span);
+                        (self.report(error), Some(true))
+                    }
+                },
+            },
+            Expr::Unary(unary) if unary.is_deref() => {
+                let inner = &unary.expr;
+                match self.visit_expr(inner) {
+                    TypeName::Ptr(mutable, ty) => (*ty, Some(mutable)),
+                    TypeName::Null => (TypeName::Null, Some(true)),
+                    ty => {
+                        let error = SemanticError::DerefNonPointer(ty, inner.span());
+                        (self.report(error), Some(true))
+                    }
+                }
+            }
+            // NOTE: a field is reached through a pointer to its struct as well.
+            Expr::Field(ExprField {
+                expr: base, name, ..
+            }) => {
+                let (ty, mutable) = match self.visit_place(base) {
+                    (TypeName::Ptr(mutable, ty), _) => (*ty, Some(mutable)),
+                    place => place,
+                };
+                match self.field_type(&ty, &name.value) {
+                    Some(field) => (field, mutable),
+                    None if ty == TypeName::Null => (TypeName::Null, mutable),
+                    None => {
+                        let error = SemanticError::NoField(name.value.clone(), ty, name.span);
+                        (self.report(error), mutable)
+                    }
+                }
+            }
+            Expr::Index(ExprIndex {
+                expr: base, index, ..
+            }) => {
+                let (ty, mutable) = self.visit_place(base);
+                self.visit_expr_as(index, &TypeName::U64);
+                // NOTE: a `Vec` is indexed through the std method that checks the index.
+                if let Some(item) = vec_item(&ty) {
+                    let (vec, mutable) = match ty {
+                        TypeName::Ptr(mutable, vec) => (*vec, Some(mutable)),
+                        vec => (vec, mutable),
+                    };
+                    let label = method_name(None, &vec, stdlib::VEC_INDEX);
+                    self.queue_method(label, &vec, expr.span());
+                    return (item, mutable);
+                }
+                match ty {
+                    TypeName::Array(elem, _) => (*elem, mutable),
+                    TypeName::Ptr(mutable, ty) => match *ty {
+                        TypeName::Array(elem, _) => (*elem, Some(mutable)),
+                        ty => {
+                            let ty = TypeName::Ptr(mutable, Box::new(ty));
+                            let error = SemanticError::IndexNonArray(ty, base.span());
+                            (self.report(error), Some(true))
+                        }
+                    },
+                    TypeName::Null => (TypeName::Null, mutable),
+                    ty => {
+                        let error = SemanticError::IndexNonArray(ty, base.span());
+                        (self.report(error), Some(true))
+                    }
+                }
+            }
+            _ => (self.visit_expr(expr), None),
+        }
+    }
+}
+
+/// Binds the type parameters in `param` to the matching parts of `arg`.
+#[allow(clippy::result_large_err)]
+fn infer(
+    generics: &[String],
+    param: &TypeName,
+    arg: &TypeName,
+    bindings: &mut HashMap<String, TypeName>,
+    span: Span,
+) -> Result<(), SemanticError> {
+    match (param, arg) {
+        // NOTE: the argument already had an error reported for it.
+        (_, TypeName::Null) => Ok(()),
+        (TypeName::Custom(name), _) if generics.contains(name) => match bindings.get(name) {
+            Some(first) if first != arg => Err(SemanticError::GenericMismatch(
+                name.clone(),
+                first.clone(),
+                arg.clone(),
+                span,
+            )),
+            Some(_) => Ok(()),
+            None => {
+                bindings.insert(name.clone(), arg.clone());
+                Ok(())
+            }
+        },
+        (TypeName::Ptr(_, param), TypeName::Ptr(_, arg))
+        | (TypeName::Array(param, _), TypeName::Array(arg, _)) => {
+            infer(generics, param, arg, bindings, span)
+        }
+        (TypeName::Generic(name, params), TypeName::Generic(arg_name, args))
+            if name == arg_name =>
+        {
+            for (param, arg) in params.iter().zip(args.iter()) {
+                infer(generics, param, arg, bindings, span)?;
+            }
+            Ok(())
+        }
+        (TypeName::Fn(params, ret), TypeName::Fn(args, arg_ret))
+        | (TypeName::Closure(params, ret), TypeName::Closure(args, arg_ret)) => {
+            for (param, arg) in params.iter().zip(args.iter()) {
+                infer(generics, param, arg, bindings, span)?;
+            }
+            infer(generics, ret, arg_ret, bindings, span)
+        }
+        _ => Ok(()),
     }
 }
+
+/// What a `Vec` or a pointer to one holds.
+fn vec_item(ty: &TypeName) -> Option<TypeName> {
+    match ty {
+        TypeName::Generic(name, args) if name == stdlib::VEC => args.first().cloned(),
+        TypeName::Ptr(_, ty) => match ty.as_ref() {
+            TypeName::Generic(name, args) if name == stdlib::VEC => args.first().cloned(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A method of an impl named like the label it gets, calls inside of it are found by it. The type
+/// parameters of a generic impl are those of each of its methods.
+fn impl_fn(item_impl: &ItemImpl, ty: &TypeName, item_fn: &ItemFn) -> ItemFn {
+    let mut item_fn = item_fn.clone();
+    item_fn.name.value = method_name(item_impl.trait_name(), ty, &item_fn.name.value);
+    let generics = item_impl.generics.iter().cloned();
+    item_fn.generics = generics.chain(item_fn.generics).collect();
+    item_fn
+}
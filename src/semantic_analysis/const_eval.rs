@@ -0,0 +1,298 @@
+use super::SemanticError;
+use crate::parse::{
+    ArrayLen, Expr, ExprBinary, ExprLit, ExprVar, Ident, Item, ItemConst, ItemStatic, Lit, LitInt,
+    Op, Type,
+};
+use crate::symbol_table::{Symbol, SymbolTable, TypeName};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// A `const` or `static` and the value it starts out with.
+#[derive(Debug, Clone)]
+pub struct Global {
+    pub ty: TypeName,
+    pub value: Value,
+    /// `false` for a `const`, which has no place in memory.
+    pub is_static: bool,
+}
+
+/// What a `const` or `static` holds, a `bool` or `char` is an int.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i128),
+    Float(f64),
+    Str(String),
+}
+
+impl Value {
+    /// The bits of an int, `bool` or `char` the way a register holds it, a signed int is sign
+    /// extended.
+    pub fn bits(&self) -> u64 {
+        match self {
+            Self::Int(value) => *value as u64,
+            Self::Float(value) => value.to_bits(),
+            Self::Str(_) => 0,
+        }
+    }
+
+    /// The type a value of this kind has without anything around it.
+    fn ty(&self) -> TypeName {
+        match self {
+            Self::Int(_) => TypeName::U64,
+            Self::Float(_) => TypeName::F64,
+            Self::Str(_) => TypeName::Str,
+        }
+    }
+
+    /// Whether the value is one of type `ty`, an int has to be in its range.
+    fn fits(&self, ty: &TypeName) -> bool {
+        match (self, ty) {
+            (Self::Int(value), TypeName::U64) => u64::try_from(*value).is_ok(),
+            (Self::Int(value), TypeName::I64) => i64::try_from(*value).is_ok(),
+            (Self::Int(value), TypeName::U32) => u32::try_from(*value).is_ok(),
+            (Self::Int(value), TypeName::I32) => i32::try_from(*value).is_ok(),
+            (Self::Int(value), TypeName::U8) => u8::try_from(*value).is_ok(),
+            (Self::Int(value), TypeName::Bool) => matches!(value, 0 | 1),
+            (Self::Int(value), TypeName::Char) => u32::try_from(*value)
+                .ok()
+                .and_then(char::from_u32)
+                .is_some(),
+            (Self::Float(_), TypeName::F32 | TypeName::F64) => true,
+            (Self::Str(_), TypeName::Str) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Works out the value of every `const` and the initial value of every `static`. Only
+/// literals, other consts and arithmetic on them are allowed.
+pub fn eval_globals(items: &[Item]) -> (HashMap<String, Global>, Vec<SemanticError>) {
+    let mut consts = HashMap::new();
+    for item in items.iter() {
+        if let Item::Const(item_const) = item {
+            consts
+                .entry(item_const.name.value.clone())
+                .or_insert(item_const);
+        }
+    }
+    let mut eval = ConstEval {
+        consts,
+        values: HashMap::new(),
+        evaluating: vec![],
+        errors: vec![],
+    };
+    let mut globals = HashMap::new();
+    for item in items.iter() {
+        let (name, ty, expr, value, is_static) = match item {
+            Item::Const(ItemConst { name, ty, expr, .. }) => {
+                (name, ty, expr, eval.constant(name), false)
+            }
+            Item::Static(ItemStatic { name, ty, expr, .. }) => {
+                (name, ty, expr, eval.expr(expr), true)
+            }
+            _ => continue,
+        };
+        let written = ty;
+        let ty = TypeName::from(ty);
+        if !(ty.is_int()
+            || ty.is_float()
+            || matches!(ty, TypeName::Bool | TypeName::Char | TypeName::Str))
+        {
+            // NOTE: the lengths are not filled in yet, `[u64; N]` would say `[u64; 0]`.
+            let ty = eval.with_lens(written);
+            eval.errors.push(SemanticError::GlobalType(ty, name.span));
+            continue;
+        }
+        // NOTE: the global is still declared when its value is wrong, so its uses are checked.
+        let value = match value {
+            Some(value) if value.fits(&ty) => value,
+            Some(Value::Int(_)) if ty.is_int() => {
+                let error = SemanticError::ConstOverflow(ty.clone(), expr.span());
+                eval.errors.push(error);
+                Value::Int(0)
+            }
+            Some(value) => {
+                let error = SemanticError::TypeMismatch(ty.clone(), value.ty(), expr.span());
+                eval.errors.push(error);
+                value
+            }
+            None => Value::Int(0),
+        };
+        globals.entry(name.value.clone()).or_insert(Global {
+            ty,
+            value,
+            is_static,
+        });
+    }
+    (globals, eval.errors)
+}
+
+/// Replaces every array length that names a `const` with its value, the types of the
+/// functions in the symbol table are worked out again with them.
+pub fn resolve_lens(
+    items: &mut [Item],
+    globals: &HashMap<String, Global>,
+    symbol_table: &mut SymbolTable,
+) -> Vec<SemanticError> {
+    let mut errors = vec![];
+    for item in items.iter_mut() {
+        item.for_each_len(&mut |len| {
+            let ArrayLen::Const(name) = len else {
+                return;
+            };
+            match globals.get(&name.value) {
+                Some(Global {
+                    ty: TypeName::U64,
+                    value: Value::Int(value),
+                    is_static: false,
+                }) => *len = ArrayLen::Lit(LitInt::new(value.to_string(), name.span)),
+                _ => errors.push(SemanticError::ArrayLen(name.value.clone(), name.span)),
+            }
+        });
+        if let Item::Fn(item_fn) = item {
+            if let Some(data) = symbol_table.get_mut(&Symbol::function(&item_fn.name.value)) {
+                data.type_name = item_fn
+                    .ret_type
+                    .as_ref()
+                    .map(TypeName::from)
+                    .unwrap_or(TypeName::Null);
+            }
+        }
+    }
+    errors
+}
+
+struct ConstEval<'a> {
+    consts: HashMap<String, &'a ItemConst>,
+    /// `None` when working out the value failed, so it is only reported once.
+    values: HashMap<String, Option<Value>>,
+    /// Consts whose value is being worked out, to catch one that depends on itself.
+    evaluating: Vec<String>,
+    errors: Vec<SemanticError>,
+}
+
+impl ConstEval<'_> {
+    /// `ty` with the value of every const it uses as an array length.
+    fn with_lens(&mut self, ty: &Type) -> TypeName {
+        let mut ty = ty.clone();
+        ty.for_each_len(&mut |len| {
+            let ArrayLen::Const(name) = len else {
+                return;
+            };
+            if !self.consts.contains_key(&name.value) {
+                return;
+            }
+            if let Some(Value::Int(value)) = self.constant(&name.clone()) {
+                *len = ArrayLen::Lit(LitInt::new(value.to_string(), name.span));
+            }
+        });
+        TypeName::from(&ty)
+    }
+
+    fn constant(&mut self, name: &Ident) -> Option<Value> {
+        if let Some(value) = self.values.get(&name.value) {
+            return value.clone();
+        }
+        if self.evaluating.contains(&name.value) {
+            return self.report(SemanticError::ConstCycle(name.value.clone(), name.span));
+        }
+        let item_const = self.consts[&name.value];
+        self.evaluating.push(name.value.clone());
+        let value = self.expr(&item_const.expr);
+        self.evaluating.pop();
+        self.values.insert(name.value.clone(), value.clone());
+        value
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Option<Value> {
+        match expr {
+            Expr::Lit(ExprLit { lit }) => match lit {
+                Lit::Int(lit) => match lit.parse::<u64>() {
+                    Ok(value) => Some(Value::Int(value.into())),
+                    Err(_) => self.report(SemanticError::ConstOverflow(TypeName::U64, lit.span)),
+                },
+                Lit::Bool(lit) => lit.parse::<bool>().ok().map(|b| Value::Int(b.into())),
+                Lit::Char(lit) => match (lit.invalid_escape(), lit.to_char()) {
+                    (Some(escape), _) => {
+                        self.report(SemanticError::InvalidEscape(escape, lit.span))
+                    }
+                    (None, Some(c)) => Some(Value::Int(u32::from(c).into())),
+                    (None, None) => self.report(SemanticError::InvalidCharLit(lit.span)),
+                },
+                Lit::Float(lit) => {
+                    let value = lit.value.replace('_', "").parse::<f64>().ok()?;
+                    Some(Value::Float(value))
+                }
+                Lit::Str(lit) => match lit.invalid_escape() {
+                    Some(escape) => self.report(SemanticError::InvalidEscape(escape, lit.span)),
+                    None => Some(Value::Str(lit.unescape())),
+                },
+            },
+            Expr::Var(ExprVar { name, .. }) if self.consts.contains_key(&name.value) => {
+                self.constant(name)
+            }
+            Expr::Binary(ExprBinary { left, right, op }) => {
+                let lhs = self.expr(left);
+                let rhs = self.expr(right);
+                match (lhs?, rhs?) {
+                    (Value::Int(lhs), Value::Int(rhs)) => self.int_op(op, lhs, rhs, expr),
+                    (Value::Float(lhs), Value::Float(rhs)) => self.float_op(op, lhs, rhs, expr),
+                    _ => self.report(SemanticError::NotConst(expr.span())),
+                }
+            }
+            _ => self.report(SemanticError::NotConst(expr.span())),
+        }
+    }
+
+    /// Ints are worked out wider than any of their types, whether the result fits is checked
+    /// against the type of the global.
+    fn int_op(&mut self, op: &Op, lhs: i128, rhs: i128, expr: &Expr) -> Option<Value> {
+        let value = match op {
+            Op::Add(_) => lhs.checked_add(rhs),
+            Op::Sub(_) => lhs.checked_sub(rhs),
+            Op::Mul(_) => lhs.checked_mul(rhs),
+            Op::Div(_) if rhs == 0 => {
+                return self.report(SemanticError::ConstDivByZero(expr.span()))
+            }
+            Op::Div(_) => lhs.checked_div(rhs),
+            _ => return self.compare(op, lhs.cmp(&rhs), expr),
+        };
+        match value {
+            Some(value) => Some(Value::Int(value)),
+            None => self.report(SemanticError::ConstOverflow(TypeName::U64, expr.span())),
+        }
+    }
+
+    fn float_op(&mut self, op: &Op, lhs: f64, rhs: f64, expr: &Expr) -> Option<Value> {
+        let value = match op {
+            Op::Add(_) => lhs + rhs,
+            Op::Sub(_) => lhs - rhs,
+            Op::Mul(_) => lhs * rhs,
+            Op::Div(_) => lhs / rhs,
+            _ => match lhs.partial_cmp(&rhs) {
+                Some(ordering) => return self.compare(op, ordering, expr),
+                // NOTE: NaN is not equal to anything, itself included.
+                None => return Some(Value::Int(matches!(op, Op::Neq(_)).into())),
+            },
+        };
+        Some(Value::Float(value))
+    }
+
+    fn compare(&mut self, op: &Op, ordering: Ordering, expr: &Expr) -> Option<Value> {
+        let value = match op {
+            Op::Grt(_) => ordering.is_gt(),
+            Op::Les(_) => ordering.is_lt(),
+            Op::Geq(_) => ordering.is_ge(),
+            Op::Leq(_) => ordering.is_le(),
+            Op::EqualEqual(_) => ordering.is_eq(),
+            Op::Neq(_) => ordering.is_ne(),
+            _ => return self.report(SemanticError::NotConst(expr.span())),
+        };
+        Some(Value::Int(value.into()))
+    }
+
+    fn report(&mut self, error: SemanticError) -> Option<Value> {
+        self.errors.push(error);
+        None
+    }
+}
@@ -0,0 +1,487 @@
+use super::SemanticError;
+use crate::lexer::Span;
+use crate::parse::{
+    Generic, Ident, Item, ItemConst, ItemExtern, ItemFn, ItemImpl, ItemStatic, ItemStruct,
+    ItemTrait, Param, TraitFn, Type,
+};
+use crate::symbol_table::TypeName;
+use std::collections::{HashMap, HashSet};
+
+/// What a call needs to know about the function it calls.
+#[derive(Debug, Clone)]
+pub struct FnSig {
+    /// Type parameters, every one of them is a `TypeName::Custom` in the params.
+    pub generics: Vec<String>,
+    /// The traits each type parameter has to implement.
+    pub bounds: HashMap<String, Vec<String>>,
+    pub params: Vec<TypeName>,
+    pub ret: TypeName,
+    pub span: Span,
+    /// Declared with `extern`, it is linked in from outside of the program.
+    pub is_extern: bool,
+}
+
+impl FnSig {
+    fn new(generics: &[Generic], params: &[Param], ret_type: Option<&Type>, name: &Ident) -> Self {
+        Self {
+            generics: generics.iter().map(|g| g.name.value.clone()).collect(),
+            bounds: generics
+                .iter()
+                .map(|g| {
+                    let bounds = g.bounds.iter().map(|b| b.value.clone()).collect();
+                    (g.name.value.clone(), bounds)
+                })
+                .collect(),
+            params: params
+                .iter()
+                .map(|Param { kind, .. }| kind.into())
+                .collect(),
+            ret: ret_type.map(TypeName::from).unwrap_or(TypeName::Null),
+            span: name.span,
+            is_extern: false,
+        }
+    }
+
+    /// How a method takes the value it is called on, `None` for an associated function.
+    pub fn receiver(&self) -> Option<SelfParam> {
+        let this = TypeName::Custom("Self".into());
+        match self.params.first()? {
+            ty if *ty == this => Some(SelfParam::Value),
+            TypeName::Ptr(mutable, ty) if **ty == this => Some(match mutable {
+                true => SelfParam::RefMut,
+                false => SelfParam::Ref,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// `self`, `&self` or `&mut self`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfParam {
+    Value,
+    Ref,
+    RefMut,
+}
+
+/// Every trait and the types implementing it.
+#[derive(Debug, Default)]
+pub struct Traits {
+    /// The methods of each trait, `Self` is a `TypeName::Custom` in them.
+    pub methods: HashMap<String, HashMap<String, FnSig>>,
+    pub impls: HashSet<(String, TypeName)>,
+    /// Methods and associated functions declared in an `impl` without a trait, by their type.
+    pub inherent: HashMap<TypeName, HashMap<String, FnSig>>,
+}
+
+impl Traits {
+    pub fn is_implemented(&self, trait_name: &str, ty: &TypeName) -> bool {
+        self.impls.contains(&(trait_name.into(), ty.base()))
+    }
+}
+
+/// The fields of a struct in the order they are declared, which is the order they are laid out in.
+#[derive(Debug, Clone)]
+pub struct StructDef {
+    /// Type parameters, every one of them is a `TypeName::Custom` in the fields.
+    pub generics: Vec<String>,
+    pub fields: Vec<(String, TypeName)>,
+    pub span: Span,
+}
+
+impl StructDef {
+    /// What the type parameters stand for in `ty`, the `T` of a `Vec<u64>` is a `u64`.
+    pub fn bindings(&self, ty: &TypeName) -> HashMap<String, TypeName> {
+        let args = match ty {
+            TypeName::Generic(_, args) => args.as_slice(),
+            _ => &[],
+        };
+        self.generics
+            .iter()
+            .cloned()
+            .zip(args.iter().cloned())
+            .collect()
+    }
+
+    pub fn field(&self, name: &str) -> Option<&TypeName> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, ty)| ty)
+    }
+}
+
+/// Every struct by its name.
+pub type Structs = HashMap<String, StructDef>;
+
+/// Label of a method or associated function of `ty`, `u64$double`, or `Show$u64$show` for one
+/// from a trait. The methods of a generic struct are named after the struct alone, `Vec$push`.
+pub fn method_name(trait_name: Option<&str>, ty: &TypeName, method: &str) -> String {
+    let ty = ty.base().mangle();
+    match trait_name {
+        Some(trait_name) => format!("{trait_name}${ty}${method}"),
+        None => format!("{ty}${method}"),
+    }
+}
+
+/// Collects every function before any body is checked, so a function can call
+/// one declared further down and two functions can call each other. Consts and
+/// statics share the names with them.
+pub fn declare(items: &[Item]) -> (HashMap<String, FnSig>, Vec<SemanticError>) {
+    let mut functions = HashMap::<String, FnSig>::new();
+    let mut errors = vec![];
+    for item in items.iter() {
+        let (name, sig) = match item {
+            Item::Fn(ItemFn {
+                name,
+                generics,
+                params,
+                ret_type,
+                ..
+            }) => (name, FnSig::new(generics, params, ret_type.as_ref(), name)),
+            Item::Extern(ItemExtern {
+                name,
+                params,
+                ret_type,
+                ..
+            }) => {
+                let sig = FnSig::new(&[], params, ret_type.as_ref(), name);
+                (
+                    name,
+                    FnSig {
+                        is_extern: true,
+                        ..sig
+                    },
+                )
+            }
+            _ => continue,
+        };
+        if let Some(first) = functions.get(&name.value) {
+            let error = SemanticError::DuplicateFn(name.value.clone(), first.span, name.span);
+            errors.push(error);
+            continue;
+        }
+        functions.insert(name.value.clone(), sig);
+    }
+    let mut globals = HashMap::<String, Span>::new();
+    for item in items.iter() {
+        let (Item::Const(ItemConst { name, .. }) | Item::Static(ItemStatic { name, .. })) = item
+        else {
+            continue;
+        };
+        let first = functions
+            .get(&name.value)
+            .map(|sig| sig.span)
+            .or(globals.get(&name.value).copied());
+        if let Some(first) = first {
+            let error = SemanticError::DuplicateGlobal(name.value.clone(), first, name.span);
+            errors.push(error);
+            continue;
+        }
+        globals.insert(name.value.clone(), name.span);
+    }
+    (functions, errors)
+}
+
+/// Collects every struct and checks that its fields are of types that exist and that it does
+/// not hold itself, which would make it never ending.
+pub fn declare_structs(items: &[Item]) -> (Structs, Vec<SemanticError>) {
+    let mut structs = Structs::new();
+    let mut errors = vec![];
+    for item in items.iter() {
+        let Item::Struct(ItemStruct {
+            name,
+            generics,
+            fields,
+            ..
+        }) = item
+        else {
+            continue;
+        };
+        if let Some(first) = structs.get(&name.value) {
+            let error = SemanticError::DuplicateGlobal(name.value.clone(), first.span, name.span);
+            errors.push(error);
+            continue;
+        }
+        let mut def = StructDef {
+            generics: generics.iter().map(|g| g.name.value.clone()).collect(),
+            fields: vec![],
+            span: name.span,
+        };
+        for Param { name, kind, .. } in fields.iter() {
+            if def.field(&name.value).is_some() {
+                errors.push(SemanticError::DuplicateField(name.value.clone(), name.span));
+                continue;
+            }
+            def.fields.push((name.value.clone(), kind.into()));
+        }
+        structs.insert(name.value.clone(), def);
+    }
+    for item in items.iter() {
+        let Item::Struct(ItemStruct { name, fields, .. }) = item else {
+            continue;
+        };
+        let generics = &structs[&name.value].generics;
+        for Param { kind, .. } in fields.iter() {
+            let ty = TypeName::from(kind);
+            field_type(&structs, generics, &ty, kind.span(), &mut errors);
+        }
+        let ty = TypeName::Custom(name.value.clone());
+        if holds(&structs, &ty, &name.value, &mut vec![]) {
+            errors.push(SemanticError::RecursiveStruct(
+                name.value.clone(),
+                name.span,
+            ));
+        }
+    }
+    (structs, errors)
+}
+
+/// Any name in the type of a field has to be a type parameter of its struct or a struct given
+/// a type for each of its own.
+fn field_type(
+    structs: &Structs,
+    generics: &[String],
+    ty: &TypeName,
+    span: Span,
+    errors: &mut Vec<SemanticError>,
+) {
+    match ty {
+        TypeName::Custom(name) if generics.contains(name) => {}
+        TypeName::Custom(name) | TypeName::Generic(name, _) if !structs.contains_key(name) => {
+            errors.push(SemanticError::UnknownType(name.clone(), span));
+        }
+        TypeName::Custom(name) | TypeName::Generic(name, _) => {
+            let args = match ty {
+                TypeName::Generic(_, args) => args.as_slice(),
+                _ => &[],
+            };
+            let expected = structs[name].generics.len();
+            if expected != args.len() {
+                let error = SemanticError::TypeArgCount(name.clone(), expected, args.len(), span);
+                errors.push(error);
+            }
+            for arg in args.iter() {
+                field_type(structs, generics, arg, span, errors);
+            }
+        }
+        TypeName::Ptr(_, inner) | TypeName::Array(inner, _) => {
+            field_type(structs, generics, inner, span, errors)
+        }
+        _ => {}
+    }
+}
+
+/// Whether a struct of type `ty` holds a `target` by value, directly or in one of its fields.
+fn holds(structs: &Structs, ty: &TypeName, target: &str, seen: &mut Vec<TypeName>) -> bool {
+    let (TypeName::Custom(name) | TypeName::Generic(name, _)) = ty else {
+        return false;
+    };
+    let Some(def) = structs.get(name) else {
+        return false;
+    };
+    let bindings = def.bindings(ty);
+    for (_, field) in def.fields.iter() {
+        let field = field.substitute(&bindings);
+        let (TypeName::Custom(name) | TypeName::Generic(name, _)) = &field else {
+            continue;
+        };
+        if name == target {
+            return true;
+        }
+        if seen.contains(&field) {
+            continue;
+        }
+        seen.push(field.clone());
+        if holds(structs, &field, target, seen) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Collects every trait and checks that each `impl` has exactly the methods of its trait.
+pub fn declare_traits(items: &[Item], structs: &Structs) -> (Traits, Vec<SemanticError>) {
+    let mut traits = Traits::default();
+    let mut spans = HashMap::<String, Span>::new();
+    let mut errors = vec![];
+    for item in items.iter() {
+        let Item::Trait(ItemTrait { name, fns, .. }) = item else {
+            continue;
+        };
+        if let Some(first) = spans.get(&name.value) {
+            errors.push(SemanticError::DuplicateGlobal(
+                name.value.clone(),
+                *first,
+                name.span,
+            ));
+            continue;
+        }
+        spans.insert(name.value.clone(), name.span);
+        let mut methods = HashMap::new();
+        for TraitFn {
+            name: method,
+            params,
+            ret_type,
+            ..
+        } in fns.iter()
+        {
+            let sig = FnSig::new(&[], params, ret_type.as_ref(), method);
+            if sig.receiver().is_none() {
+                errors.push(SemanticError::NoSelf(method.value.clone(), method.span));
+            }
+            methods.insert(method.value.clone(), sig);
+        }
+        traits.methods.insert(name.value.clone(), methods);
+    }
+    for item in items.iter() {
+        let Item::Impl(ItemImpl {
+            generics,
+            of_trait,
+            ty,
+            fns,
+            ..
+        }) = item
+        else {
+            continue;
+        };
+        let span = ty.span();
+        let ty = TypeName::from(ty);
+        let params = match &ty {
+            TypeName::Custom(name) | TypeName::Generic(name, _) => match structs.get(name) {
+                Some(def) => def.generics.len(),
+                None => {
+                    errors.push(SemanticError::UnknownType(name.clone(), span));
+                    continue;
+                }
+            },
+            _ => 0,
+        };
+        // NOTE: the methods of a generic struct are checked and generated once for every type
+        // they are called with, like a generic function.
+        let args = match &ty {
+            TypeName::Generic(_, args) => args.clone(),
+            _ => vec![],
+        };
+        let names = generics
+            .iter()
+            .map(|g| TypeName::Custom(g.name.value.clone()))
+            .collect::<Vec<_>>();
+        if args != names || args.len() != params {
+            errors.push(SemanticError::GenericImpl(ty.base().to_string(), span));
+            continue;
+        }
+        match of_trait {
+            Some((trait_name, _)) => trait_impl(&mut traits, trait_name, ty, fns, &mut errors),
+            None => inherent_impl(&mut traits, generics, ty, fns, &mut errors),
+        }
+    }
+    (traits, errors)
+}
+
+/// Checks that the impl has exactly the methods of its trait.
+fn trait_impl(
+    traits: &mut Traits,
+    trait_name: &Ident,
+    ty: TypeName,
+    fns: &[ItemFn],
+    errors: &mut Vec<SemanticError>,
+) {
+    let Some(methods) = traits.methods.get(&trait_name.value) else {
+        errors.push(SemanticError::UnknownTrait(
+            trait_name.value.clone(),
+            trait_name.span,
+        ));
+        return;
+    };
+    let key = (trait_name.value.clone(), ty.base());
+    if traits.impls.contains(&key) {
+        let error = SemanticError::DuplicateImpl(trait_name.value.clone(), ty, trait_name.span);
+        errors.push(error);
+        return;
+    }
+    let this = HashMap::from([("Self".to_string(), ty.clone())]);
+    for item_fn in fns.iter() {
+        let ItemFn {
+            name,
+            generics,
+            params,
+            ret_type,
+            ..
+        } = item_fn;
+        let Some(expected) = methods.get(&name.value) else {
+            let error =
+                SemanticError::NotInTrait(name.value.clone(), trait_name.value.clone(), name.span);
+            errors.push(error);
+            continue;
+        };
+        let found = FnSig::new(generics, params, ret_type.as_ref(), name);
+        let same_params = expected.params.len() == found.params.len()
+            && expected
+                .params
+                .iter()
+                .zip(found.params.iter())
+                .all(|(e, f)| e.substitute(&this) == f.substitute(&this));
+        if !generics.is_empty()
+            || !same_params
+            || expected.ret.substitute(&this) != found.ret.substitute(&this)
+        {
+            let error = SemanticError::MethodSignature(
+                name.value.clone(),
+                trait_name.value.clone(),
+                name.span,
+            );
+            errors.push(error);
+        }
+    }
+    let mut missing = methods
+        .keys()
+        .filter(|method| !fns.iter().any(|item_fn| &item_fn.name.value == *method))
+        .collect::<Vec<_>>();
+    missing.sort();
+    for method in missing {
+        errors.push(SemanticError::MissingMethod(
+            method.clone(),
+            trait_name.value.clone(),
+            ty.clone(),
+            trait_name.span,
+        ));
+    }
+    traits.impls.insert(key);
+}
+
+/// Methods and associated functions of the type itself, a type can have more than one such impl.
+/// Those of a generic struct take the type parameters of the impl as their own.
+fn inherent_impl(
+    traits: &mut Traits,
+    impl_generics: &[Generic],
+    ty: TypeName,
+    fns: &[ItemFn],
+    errors: &mut Vec<SemanticError>,
+) {
+    let members = traits.inherent.entry(ty.base()).or_default();
+    for item_fn in fns.iter() {
+        let ItemFn {
+            name,
+            generics,
+            params,
+            ret_type,
+            ..
+        } = item_fn;
+        if !generics.is_empty() {
+            errors.push(SemanticError::GenericMethod(name.value.clone(), name.span));
+            continue;
+        }
+        if let Some(first) = members.get(&name.value) {
+            let name = format!("{ty}::{}", name.value);
+            errors.push(SemanticError::DuplicateFn(
+                name,
+                first.span,
+                item_fn.name.span,
+            ));
+            continue;
+        }
+        let sig = FnSig::new(impl_generics, params, ret_type.as_ref(), name);
+        members.insert(name.value.clone(), sig);
+    }
+}
@@ -0,0 +1,339 @@
+use crate::lexer::Span;
+use crate::symbol_table::TypeName;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SemanticError {
+    UnknownVariable(String, Span),
+    AddrOfTemporary(Span),
+    DerefNonPointer(TypeName, Span),
+    AssignThroughSharedRef(Span),
+    MutBorrowThroughSharedRef(Span),
+    InvalidAssignTarget(Span),
+    IndexNonArray(TypeName, Span),
+    InvalidCharLit(Span),
+    /// The escape sequence that does not stand for anything.
+    InvalidEscape(String, Span),
+    BuiltinArgCount(&'static str, RangeInclusive<usize>, Span),
+    MainParams(Span),
+    /// The parameter of `main`, the type it has to be and the type it was given.
+    MainParamType(&'static str, TypeName, TypeName, Span),
+    NotPrintable(TypeName, Span),
+    NotSyscallArg(TypeName, Span),
+    NoLen(TypeName, Span),
+    /// The type of the message given to `panic`.
+    PanicMsg(TypeName, Span),
+    /// The type of what was given to `size_of_val`.
+    SizeOfNonPointer(TypeName, Span),
+    UnknownFunction(String, Span),
+    /// The first definition and then the one clashing with it.
+    DuplicateFn(String, Span, Span),
+    ArgCount(String, usize, usize, Span),
+    /// The first definition and then the one clashing with it.
+    DuplicateGlobal(String, Span, Span),
+    GlobalType(TypeName, Span),
+    NotConst(Span),
+    ConstCycle(String, Span),
+    /// The type the constant was meant to fit in.
+    ConstOverflow(TypeName, Span),
+    /// The name used as the length of an array.
+    ArrayLen(String, Span),
+    ConstDivByZero(Span),
+    /// The type parameter and the two types it was inferred as.
+    GenericMismatch(String, TypeName, TypeName, Span),
+    CannotInfer(String, String, Span),
+    UnknownTrait(String, Span),
+    UnknownType(String, Span),
+    GenericMethod(String, Span),
+    NoSelf(String, Span),
+    DuplicateImpl(String, TypeName, Span),
+    NotInTrait(String, String, Span),
+    MethodSignature(String, String, Span),
+    MissingMethod(String, String, TypeName, Span),
+    NoMethod(String, TypeName, Span),
+    /// The method and two of the traits it could come from.
+    AmbiguousMethod(String, String, String, Span),
+    MissingImpl(String, TypeName, Span),
+    DuplicateField(String, Span),
+    RecursiveStruct(String, Span),
+    /// The field and the type of the value it was looked up on.
+    NoField(String, TypeName, Span),
+    /// The field and the struct it is missing from.
+    MissingField(String, String, Span),
+    NotCallable(TypeName, Span),
+    GenericFnValue(String, Span),
+    /// A closure returned or written somewhere that outlives the frame holding its environment.
+    EscapingClosure(Span),
+    ExternFnValue(String, Span),
+    /// The calling convention given after `extern`.
+    UnknownAbi(String, Span),
+    /// A param or return type of an extern function C has nothing like.
+    NotCType(TypeName, Span),
+    /// An `extern fn` with a body that is left out of the object's symbols.
+    ExportNotPub(String, Span),
+    /// The types on the left and right of a binary operator.
+    MixedTypes(TypeName, TypeName, Span),
+    /// The type of the value and the type it is cast to.
+    InvalidCast(TypeName, TypeName, Span),
+    /// The operator and the type of the values it was used on.
+    InvalidOp(String, TypeName, Span),
+    /// The type a value has to be and the type it is.
+    TypeMismatch(TypeName, TypeName, Span),
+    /// The literal and the type it was used as.
+    LitOutOfRange(String, TypeName, Span),
+    /// The type of the condition of an `if`.
+    NonBoolCondition(TypeName, Span),
+    /// The type of what a `for` loop was given to go over.
+    NotIterable(TypeName, Span),
+    /// The struct, how many type parameters it has and how many types it was given.
+    TypeArgCount(String, usize, usize, Span),
+    /// An impl of a generic struct that is not generic over each of its type parameters.
+    GenericImpl(String, Span),
+    /// An error in a generic function checked for the types it is called with, the function,
+    /// its type parameters with those types and where it is called.
+    InInstance(String, Vec<(String, TypeName)>, String, Box<SemanticError>),
+    /// An error in a function of a linked program, the span does not say which file it is in.
+    InFile(String, Box<SemanticError>),
+}
+
+impl fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownVariable(name, span) => write!(f, "{span:?} unknown variable '{name}'"),
+            Self::AddrOfTemporary(span) => {
+                write!(f, "{span:?} cannot take the address of a temporary value")
+            }
+            Self::DerefNonPointer(ty, span) => {
+                write!(f, "{span:?} cannot dereference a value of type '{ty}'")
+            }
+            Self::AssignThroughSharedRef(span) => {
+                write!(
+                    f,
+                    "{span:?} cannot assign through a '&' reference, use '&mut'"
+                )
+            }
+            Self::MutBorrowThroughSharedRef(span) => {
+                write!(
+                    f,
+                    "{span:?} cannot borrow as '&mut' through a '&' reference"
+                )
+            }
+            Self::InvalidAssignTarget(span) => {
+                write!(f, "{span:?} invalid left-hand side of assignment")
+            }
+            Self::IndexNonArray(ty, span) => {
+                write!(f, "{span:?} cannot index into a value of type '{ty}'")
+            }
+            Self::BuiltinArgCount(name, arity, span) if arity.start() == arity.end() => {
+                write!(f, "{span:?} '{name}' takes {} argument(s)", arity.end())
+            }
+            Self::BuiltinArgCount(name, arity, span) => {
+                write!(
+                    f,
+                    "{span:?} '{name}' takes at most {} argument(s)",
+                    arity.end()
+                )
+            }
+            Self::MainParams(span) => write!(
+                f,
+                "{span:?} 'main' takes at most three parameters: argc, argv and envp"
+            ),
+            Self::MainParamType(param, expected, found, span) => write!(
+                f,
+                "{span:?} the {param} parameter of 'main' has to be a '{expected}' but is a '{found}'"
+            ),
+            Self::NotPrintable(ty, span) => {
+                write!(f, "{span:?} cannot print a value of type '{ty}'")
+            }
+            Self::NotSyscallArg(ty, span) => {
+                write!(
+                    f,
+                    "{span:?} cannot pass a value of type '{ty}' to a syscall"
+                )
+            }
+            Self::NoLen(ty, span) => {
+                write!(f, "{span:?} a value of type '{ty}' has no length")
+            }
+            Self::PanicMsg(ty, span) => {
+                write!(
+                    f,
+                    "{span:?} expected a 'str' message to panic with but found '{ty}'"
+                )
+            }
+            Self::SizeOfNonPointer(ty, span) => {
+                write!(
+                    f,
+                    "{span:?} expected a pointer to the value to take the size of but found '{ty}'"
+                )
+            }
+            Self::UnknownFunction(name, span) => write!(f, "{span:?} unknown function '{name}'"),
+            Self::DuplicateFn(name, first, span) => write!(
+                f,
+                "{span:?} function '{name}' is already defined at {first:?}"
+            ),
+            Self::ArgCount(name, expected, found, span) => write!(
+                f,
+                "{span:?} '{name}' takes {expected} argument(s) but {found} were given"
+            ),
+            Self::DuplicateGlobal(name, first, span) => {
+                write!(f, "{span:?} '{name}' is already defined at {first:?}")
+            }
+            Self::GlobalType(ty, span) => write!(
+                f,
+                "{span:?} a const or static can not be of type '{ty}', only a number, 'bool', 'char' or 'str'"
+            ),
+            Self::NotConst(span) => {
+                write!(f, "{span:?} can not be worked out while compiling")
+            }
+            Self::ConstCycle(name, span) => {
+                write!(f, "{span:?} the value of '{name}' depends on itself")
+            }
+            Self::ConstOverflow(ty, span) => {
+                write!(f, "{span:?} constant does not fit in a '{ty}'")
+            }
+            Self::ArrayLen(name, span) => write!(
+                f,
+                "{span:?} '{name}' is not a 'u64' const to use as the length of an array"
+            ),
+            Self::ConstDivByZero(span) => write!(f, "{span:?} constant divides by zero"),
+            Self::GenericMismatch(generic, first, second, span) => write!(
+                f,
+                "{span:?} '{generic}' is inferred as both '{first}' and '{second}'"
+            ),
+            Self::CannotInfer(generic, name, span) => {
+                write!(
+                    f,
+                    "{span:?} can not infer '{generic}' in the call to '{name}'"
+                )
+            }
+            Self::UnknownTrait(name, span) => write!(f, "{span:?} unknown trait '{name}'"),
+            Self::UnknownType(name, span) => write!(f, "{span:?} unknown type '{name}'"),
+            Self::GenericMethod(name, span) => {
+                write!(f, "{span:?} method '{name}' can not have type parameters")
+            }
+            Self::NoSelf(name, span) => {
+                write!(
+                    f,
+                    "{span:?} '{name}' has to take 'self' as its first parameter"
+                )
+            }
+            Self::DuplicateImpl(name, ty, span) => {
+                write!(f, "{span:?} '{name}' is already implemented for '{ty}'")
+            }
+            Self::NotInTrait(name, trait_name, span) => {
+                write!(f, "{span:?} '{name}' is not a method of '{trait_name}'")
+            }
+            Self::MethodSignature(name, trait_name, span) => write!(
+                f,
+                "{span:?} '{name}' does not match its declaration in '{trait_name}'"
+            ),
+            Self::MissingMethod(name, trait_name, ty, span) => write!(
+                f,
+                "{span:?} the impl of '{trait_name}' for '{ty}' is missing '{name}'"
+            ),
+            Self::NoMethod(name, ty, span) => {
+                write!(f, "{span:?} no method '{name}' on a value of type '{ty}'")
+            }
+            Self::AmbiguousMethod(name, first, second, span) => {
+                write!(f, "{span:?} '{name}' could be from '{first}' or '{second}'")
+            }
+            Self::MissingImpl(name, ty, span) => {
+                write!(
+                    f,
+                    "{span:?} the trait '{name}' is not implemented for '{ty}'"
+                )
+            }
+            Self::InvalidCharLit(span) => {
+                write!(f, "{span:?} character literals hold exactly one character")
+            }
+            Self::InvalidEscape(escape, span) => {
+                write!(f, "{span:?} invalid escape '{escape}' in literal")
+            }
+            Self::DuplicateField(name, span) => {
+                write!(f, "{span:?} field '{name}' appears more than once")
+            }
+            Self::RecursiveStruct(name, span) => {
+                write!(f, "{span:?} '{name}' holds itself, put it behind a pointer")
+            }
+            Self::NoField(name, ty, span) => {
+                write!(f, "{span:?} no field '{name}' on a value of type '{ty}'")
+            }
+            Self::MissingField(name, struct_name, span) => {
+                write!(f, "{span:?} '{struct_name}' is missing the field '{name}'")
+            }
+            Self::NotCallable(ty, span) => {
+                write!(f, "{span:?} a value of type '{ty}' cannot be called")
+            }
+            Self::GenericFnValue(name, span) => {
+                write!(
+                    f,
+                    "{span:?} generic function '{name}' cannot be used as a value"
+                )
+            }
+            Self::EscapingClosure(span) => write!(
+                f,
+                "{span:?} a closure cannot outlive the function that made it, its captures live there"
+            ),
+            Self::ExternFnValue(name, span) => {
+                write!(f, "{span:?} extern function '{name}' can only be called")
+            }
+            Self::UnknownAbi(abi, span) => {
+                write!(
+                    f,
+                    "{span:?} unknown calling convention '{abi}', only \"C\" is supported"
+                )
+            }
+            Self::NotCType(ty, span) => write!(
+                f,
+                "{span:?} a '{ty}' can not be passed to or returned from an extern function"
+            ),
+            Self::ExportNotPub(name, span) => write!(
+                f,
+                "{span:?} extern function '{name}' is exported to C and has to be 'pub'"
+            ),
+            Self::MixedTypes(lhs, rhs, span) => write!(
+                f,
+                "{span:?} can not mix '{lhs}' and '{rhs}', convert one of them with 'as'"
+            ),
+            Self::InvalidCast(from, to, span) => {
+                write!(f, "{span:?} can not cast a '{from}' to '{to}'")
+            }
+            Self::InvalidOp(op, ty, span) => {
+                write!(f, "{span:?} can not use '{op}' on a '{ty}'")
+            }
+            Self::TypeMismatch(expected, found, span) => {
+                write!(f, "{span:?} expected '{expected}' but found '{found}'")
+            }
+            Self::LitOutOfRange(lit, ty, span) => {
+                write!(f, "{span:?} literal '{lit}' does not fit in a '{ty}'")
+            }
+            Self::NonBoolCondition(ty, span) => {
+                write!(f, "{span:?} expected a 'bool' condition but found '{ty}'")
+            }
+            Self::NotIterable(ty, span) => {
+                write!(
+                    f,
+                    "{span:?} can not loop over a value of type '{ty}', only a range of ints or a 'Vec'"
+                )
+            }
+            Self::TypeArgCount(name, expected, found, span) => write!(
+                f,
+                "{span:?} '{name}' takes {expected} type argument(s) but {found} were given"
+            ),
+            Self::GenericImpl(name, span) => write!(
+                f,
+                "{span:?} an impl of '{name}' has to declare its type parameters and pass them in the same order"
+            ),
+            Self::InInstance(name, params, at, error) => {
+                let params = params
+                    .iter()
+                    .map(|(param, ty)| format!("{param} = {ty}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "{error}, in '{name}' called with {params} at {at}")
+            }
+            Self::InFile(file, error) => write!(f, "{file}: {error}"),
+        }
+    }
+}
@@ -1,127 +1,46 @@
 mod analysis;
+mod const_eval;
+mod declare;
+mod error;
 #[cfg(test)]
 mod test;
-pub use analysis::SemanticAnalysisVisitor;
-pub use analysis::SymbolTableBuilder;
-
-use crate::lexer::Span;
-use crate::parse::ast;
-use std::collections::HashMap;
-use std::fmt;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Type {
-    Unit,
-    U64,
-}
-
-impl Default for Type {
-    fn default() -> Self {
-        Self::Unit
-    }
-}
-
-impl From<&ast::Type> for Type {
-    fn from(ast::Type(ast::Ident { value, .. }): &ast::Type) -> Self {
-        match value.as_str() {
-            "()" => Self::Unit,
-            "u64" => Self::U64,
-            _ => unimplemented!("{value:?}"),
-        }
-    }
-}
-
-impl Type {
-    pub fn _size(&self) -> usize {
-        match self {
-            Self::Unit => 0,
-            Self::U64 => 8,
-        }
-    }
-}
-
-impl fmt::Display for Type {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::Unit => write!(f, "()"),
-            Self::U64 => write!(f, "u64"),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Scope {
-    Global,
-    Block,
-    Param,
-}
-
-impl Default for Scope {
-    fn default() -> Self {
-        Self::Global
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Symbol {
-    Variable(Variable),
-    Function(Function),
-}
-
-macro_rules! into_symbol {
-    ($name:ident) => {
-        impl From<$name> for Symbol {
-            fn from(value: $name) -> Self {
-                Self::$name(value)
-            }
-        }
-    };
-}
-
-into_symbol!(Variable);
-into_symbol!(Function);
-
-impl Symbol {
-    pub fn name(&self) -> &str {
-        match self {
-            Self::Variable(v) => &v.name,
-            Self::Function(f) => &f.name,
-        }
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Variable {
-    pub scope: Scope,
-    pub name: String,
-    pub ty: Type,
-    pub span: Span,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Function {
-    pub scope: Scope,
-    pub name: String,
-    pub params: Vec<Variable>,
-    pub ret: Type,
-    pub span: Span,
-}
-
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub struct SymbolTable {
-    pub symbols: HashMap<String, Symbol>,
-}
-
-impl SymbolTable {
-    pub fn insert(&mut self, symbol: Symbol) {
-        self.symbols.insert(symbol.name().to_string(), symbol);
-    }
-
-    pub fn get(&self, name: &str) -> Option<&Symbol> {
-        self.symbols.get(name)
-    }
-
-    pub fn _delete(&mut self, name: &str) {
-        self.symbols.remove(name);
-    }
+pub use analysis::{Calls, Capture, Method, Receiver, SemanticAnalysisVisitor, TypeChecker};
+pub use const_eval::{eval_globals, resolve_lens, Global, Value};
+pub use declare::{
+    declare, declare_structs, declare_traits, method_name, FnSig, SelfParam, Structs, Traits,
+};
+pub use error::SemanticError;
+
+use crate::parse::Item;
+use crate::symbol_table::SymbolTable;
+
+/// Walks the ast after parsing and reports everything that is syntactically
+/// fine but does not make sense, before any code is generated for it.
+/// Also hands back the types every call to a generic function was inferred with, the trait
+/// every method call goes to and what every closure captures.
+pub fn check(
+    (mut ast, mut symbol_table): (Vec<Item>, SymbolTable),
+) -> Result<(Vec<Item>, SymbolTable, Calls), Vec<String>> {
+    let (globals, global_errors) = eval_globals(&ast);
+    // NOTE: every type is read after this, so an array with a const length knows its length.
+    let len_errors = resolve_lens(&mut ast, &globals, &mut symbol_table);
+    let (functions, mut errors) = declare(&ast);
+    errors.extend(global_errors);
+    errors.extend(len_errors);
+    let (structs, struct_errors) = declare_structs(&ast);
+    errors.extend(struct_errors);
+    let (traits, trait_errors) = declare_traits(&ast, &structs);
+    errors.extend(trait_errors);
+    let mut checker = TypeChecker::new(&functions, &globals, &traits, &structs);
+    checker.visit(&ast);
+    // NOTE: a generic body with errors of its own would report them again for every instance.
+    if checker.errors().is_empty() {
+        checker.check_instances(&ast);
+    }
+    errors.extend_from_slice(checker.errors());
+    if !errors.is_empty() {
+        return Err(errors.iter().map(ToString::to_string).collect());
+    }
+    let calls = checker.into_calls();
+    Ok((ast, symbol_table, calls))
 }
@@ -1,25 +1,899 @@
-use super::{Scope, SemanticAnalysisVisitor, Symbol, SymbolTableBuilder, Type};
+use super::check;
 use crate::lexer::lex;
 use crate::parse::parse;
+
+fn errors(src: &str) -> Vec<String> {
+    lex(src)
+        .and_then(parse)
+        .and_then(check)
+        .err()
+        .unwrap_or_default()
+}
+
+#[test]
+fn pointer_ok() {
+    let src = "
+    fn set(p: &mut u64) {
+        *p = 3;
+    }
+    fn main() {
+        let x = 1;
+        let r = &x;
+        set(&mut x);
+        return *r;
+    }
+    ";
+    assert_eq!(errors(src), Vec::<String>::new());
+}
+
+#[test]
+fn addr_of_temporary() {
+    let errors = errors("fn main() { let p = &5; return *p; }");
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("cannot take the address of a temporary"));
+}
+
+#[test]
+fn assign_through_shared_ref() {
+    let errors = errors("fn main() { let x = 1; let p = &x; *p = 2; return x; }");
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("cannot assign through a '&' reference"));
+}
+
+#[test]
+fn deref_non_pointer() {
+    let errors = errors("fn main() { let x = 1; return *x; }");
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("cannot dereference a value of type 'u64'"));
+}
+
+#[test]
+fn char_lit_too_long() {
+    let errors = errors("fn main() { let c = 'ab'; return c; }");
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("character literals hold exactly one character"));
+}
+
+#[test]
+fn invalid_escapes() {
+    let src = r#"
+    const A: str = "\q";
+    fn main() {
+        let ok = "\n\t\r\0\\\"\'";
+        let s = "\x41";
+        let c = '\d';
+        let n = '\n';
+    }
+    "#;
+    let errors = errors(src);
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].contains(r"(1,19)->(1,23) invalid escape '\q' in literal"));
+    assert!(errors[1].contains(r"(4,16)->(4,22) invalid escape '\x' in literal"));
+    assert!(errors[2].contains(r"invalid escape '\d' in literal"));
+}
+
+#[test]
+fn print_takes_one_value() {
+    let errors = errors("fn main() { let a = [1, 2]; println(1, 2); print(&a); return 0; }");
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("'println' takes at most 1 argument(s)"));
+    assert!(errors[1].contains("cannot print a value of type '&[u64; 2]'"));
+}
+
+#[test]
+fn main_args() {
+    let errors = errors("fn main(a: u64, b: *u64, c: *u64, d: u64) { return arg(); }");
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("'main' takes at most three parameters"));
+    assert!(errors[1].contains("'arg' takes 1 argument(s)"));
+}
+
+#[test]
+fn main_param_types() {
+    let errors = errors("fn main(argc: str, argv: *u8, envp: bool) { return 0; }");
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].contains("the argc parameter of 'main' has to be a 'u64' but is a 'str'"));
+    assert!(errors[1]
+        .contains("the argv parameter of 'main' has to be a '&mut u64' but is a '&mut u8'"));
+    assert!(
+        errors[2].contains("the envp parameter of 'main' has to be a '&mut u64' but is a 'bool'")
+    );
+}
+
+#[test]
+fn syscall_args() {
+    let errors = errors("fn main() { let a = [1, 2]; syscall(1, a); return len(3); }");
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("cannot pass a value of type '[u64; 2]' to a syscall"));
+    assert!(errors[1].contains("a value of type 'u64' has no length"));
+}
+
+#[test]
+fn call_declared_later() {
+    let src = "
+    fn main() -> u64 {
+        return later(1);
+    }
+    fn later(x: u64) -> u64 {
+        return x;
+    }
+    ";
+    assert_eq!(errors(src), Vec::<String>::new());
+}
+
+#[test]
+fn duplicate_fn() {
+    let errors = errors("fn f() {}\nfn main() { return f(1); }\nfn f() {}\nfn main() { g(); }");
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].contains("(2,3)->(2,4) function 'f' is already defined at (0,3)->(0,4)"));
+    assert!(errors[1].contains("function 'main' is already defined at (1,3)->(1,7)"));
+    assert!(errors[2].contains("'f' takes 0 argument(s) but 1 were given"));
+    assert!(errors[3].contains("unknown function 'g'"));
+}
+
+#[test]
+fn var_out_of_scope() {
+    let src = "
+    fn f() -> u64 {
+        let a = 1;
+        if a > 0 {
+            let b = a;
+        }
+        return b;
+    }
+    fn main() -> u64 {
+        return a;
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("unknown variable 'b'"));
+    assert!(errors[1].contains("unknown variable 'a'"));
+}
+
+#[test]
+fn globals_ok() {
+    let src = "
+    const N: u64 = M * 2 + 1;
+    const M: u64 = 10 / 5;
+    static TOTAL: u64 = N;
+    fn main() -> u64 {
+        TOTAL = TOTAL + N;
+        let p = &mut TOTAL;
+        let N = 1;
+        return *p + N;
+    }
+    ";
+    assert_eq!(errors(src), Vec::<String>::new());
+}
+
+#[test]
+fn bad_consts() {
+    let src = "
+    const A: u64 = B;
+    const B: u64 = A + 1;
+    const C: u64 = 1 / 0;
+    const D: u64 = 18446744073709551615 + 1;
+    static E: u64 = f();
+    static F: u64 = E;
+    const G: [u64; 2] = 1;
+    const H: u64 = 1;
+    fn f() -> u64 {
+        H = 2;
+        return 0;
+    }
+    fn H() {}
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 8);
+    assert!(errors[0].contains("'H' is already defined at (13,7)->(13,8)"));
+    assert!(errors[1].contains("the value of 'A' depends on itself"));
+    assert!(errors[2].contains("constant divides by zero"));
+    assert!(errors[3].contains("constant does not fit in a 'u64'"));
+    assert!(errors[4].contains("can not be worked out while compiling"));
+    assert!(errors[5].contains("(6,20)->(6,21) can not be worked out while compiling"));
+    assert!(errors[6].contains("a const or static can not be of type '[u64; 2]'"));
+    assert!(errors[7].contains("invalid left-hand side of assignment"));
+}
+
+#[test]
+fn global_array_type() {
+    let src = "
+    const N: u64 = 3;
+    static A: [u64; N] = 0;
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("a const or static can not be of type '[u64; 3]'"));
+}
+
+#[test]
+fn global_types() {
+    let src = "
+    const N: u64 = 2;
+    const NAME: str = \"a\";
+    const LOW: i32 = 0 - 2;
+    const HALF: f64 = 1.0 / 2.0;
+    static TOO_LOW: u32 = 0 - 1;
+    static WRONG: str = 1.5;
+    const MIXED: f64 = 1.0 + 1;
+    fn first(a: [u64; N]) -> [u64; N] {
+        let b: [u64; NAME] = [0; LOW];
+        return [a[0]; N];
+    }
+    fn main() -> u64 {
+        let name: str = NAME;
+        let low: i32 = LOW;
+        let half: f64 = HALF;
+        return first([1, 2])[1];
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 5);
+    assert!(errors[0].contains("(5,26)->(5,31) constant does not fit in a 'u32'"));
+    assert!(errors[1].contains("expected 'str' but found 'f64'"));
+    assert!(errors[2].contains("(7,23)->(7,30) can not be worked out while compiling"));
+    assert!(errors[3].contains("'NAME' is not a 'u64' const to use as the length of an array"));
+    assert!(errors[4].contains("'LOW' is not a 'u64' const to use as the length of an array"));
+}
+
+#[test]
+fn generic_inference() {
+    let src = "
+    fn pick<T>(a: T, b: T) -> T {
+        return a;
+    }
+    fn make<T>() -> u64 {
+        return 0;
+    }
+    fn main() -> u64 {
+        let x = pick(1, 2);
+        let p = pick(&x, &x);
+        pick(1, true);
+        make();
+        return x;
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("'T' is inferred as both 'u64' and 'bool'"));
+    assert!(errors[1].contains("can not infer 'T' in the call to 'make'"));
+}
+
+#[test]
+fn trait_impls() {
+    let src = "
+    trait Show {
+        fn show(self);
+        fn size(self) -> u64;
+    }
+    trait Named {
+        fn name();
+    }
+    impl Show for u64 {
+        fn show(self) {}
+        fn size(self) -> u64 {
+            return 8;
+        }
+    }
+    impl Show for bool {
+        fn show(self, x: u64) {}
+        fn hide(self) {}
+    }
+    impl Show for u64 {
+        fn show(self) {}
+        fn size(self) -> u64 {
+            return 8;
+        }
+    }
+    impl Debug for u64 {}
+    fn main() {}
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 6);
+    assert!(errors[0].contains("'name' has to take 'self' as its first parameter"));
+    assert!(errors[1].contains("'show' does not match its declaration in 'Show'"));
+    assert!(errors[2].contains("'hide' is not a method of 'Show'"));
+    assert!(errors[3].contains("the impl of 'Show' for 'bool' is missing 'size'"));
+    assert!(errors[4].contains("'Show' is already implemented for 'u64'"));
+    assert!(errors[5].contains("unknown trait 'Debug'"));
+}
+
+#[test]
+fn method_calls() {
+    let src = "
+    trait Show {
+        fn show(self);
+    }
+    trait Print {
+        fn show(self);
+    }
+    impl Show for u64 {
+        fn show(self) {}
+    }
+    impl Print for u64 {
+        fn show(self) {}
+    }
+    impl Show for bool {
+        fn show(self) {}
+    }
+    fn twice<T: Show>(x: T) {
+        x.show();
+    }
+    fn any<T>(x: T) {
+        x.show();
+    }
+    fn main() {
+        true.show();
+        true.show(1);
+        'c'.show();
+        1.show();
+        twice(true);
+        twice('c');
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 5);
+    assert!(errors[0].contains("no method 'show' on a value of type 'T'"));
+    assert!(errors[1].contains("'show' takes 0 argument(s) but 1 were given"));
+    assert!(errors[2].contains("no method 'show' on a value of type 'char'"));
+    assert!(errors[3].contains("'show' could be from 'Print' or 'Show'"));
+    assert!(errors[4].contains("the trait 'Show' is not implemented for 'char'"));
+}
+
+#[test]
+fn inherent_methods() {
+    let src = "
+    impl Point {
+        fn new() {}
+    }
+    impl u64 {
+        fn zero() -> u64 {
+            return 0;
+        }
+        fn get(&self) -> u64 {
+            return *self;
+        }
+        fn bump(&mut self) {}
+        fn get(self) {}
+        fn pick<T>(self, x: T) {}
+    }
+    fn main() -> u64 {
+        let x = u64::zero();
+        let r = &x;
+        r.get();
+        r.bump();
+        5.get();
+        u64::one();
+        u64::zero(1);
+        return x.get();
+    }
+    ";
+    let errors = errors(src);
+    // NOTE: `5.get()` borrows the slot the `5` is written to.
+    assert_eq!(errors.len(), 6);
+    assert!(errors[0].contains("unknown type 'Point'"));
+    assert!(errors[1].contains("function 'u64::get' is already defined at"));
+    assert!(errors[2].contains("method 'pick' can not have type parameters"));
+    assert!(errors[3].contains("cannot borrow as '&mut' through a '&' reference"));
+    assert!(errors[4].contains("unknown function 'u64::one'"));
+    assert!(errors[5].contains("'u64::zero' takes 0 argument(s) but 1 were given"));
+}
+
+#[test]
+fn structs() {
+    let src = "
+    struct Point {
+        x: u64,
+        x: u64,
+    }
+    struct Pair {
+        first: Pair,
+    }
+    struct Line {
+        from: Point,
+        to: Shape,
+    }
+    impl Point {
+        fn new() -> Self {
+            return Point { x: 1 };
+        }
+    }
+    fn main() {
+        let p = Point { y: 2 };
+        p.z = 1;
+        println(p);
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 7);
+    assert!(errors[0].contains("field 'x' appears more than once"));
+    assert!(errors[1].contains("'Pair' holds itself, put it behind a pointer"));
+    assert!(errors[2].contains("unknown type 'Shape'"));
+    assert!(errors[3].contains("no field 'y' on a value of type 'Point'"));
+    assert!(errors[4].contains("'Point' is missing the field 'x'"));
+    assert!(errors[5].contains("no field 'z' on a value of type 'Point'"));
+    assert!(errors[6].contains("cannot print a value of type 'Point'"));
+}
+
+#[test]
+fn fn_values() {
+    let src = "
+    fn double(x: u64) -> u64 {
+        return x * 2;
+    }
+    fn id<T>(x: T) -> T {
+        return x;
+    }
+    fn main() {
+        let f = double;
+        let x = 1;
+        let g = id;
+        println(f(1, 2));
+        println(x(3));
+        println(f);
+        return f(f(2));
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].contains("generic function 'id' cannot be used as a value"));
+    assert!(errors[1].contains("'fn(u64) -> u64' takes 1 argument(s) but 2 were given"));
+    assert!(errors[2].contains("a value of type 'u64' cannot be called"));
+    assert!(errors[3].contains("cannot print a value of type 'fn(u64) -> u64'"));
+}
+
+#[test]
+fn closures() {
+    let src = "
+    fn main() {
+        let offset = 1;
+        let add = |x| x + offset;
+        println(add(1, 2));
+        println(add);
+        return add(y);
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].contains("'|u64| -> u64' takes 1 argument(s) but 2 were given"));
+    assert!(errors[1].contains("cannot print a value of type '|u64| -> u64'"));
+    assert!(errors[2].contains("unknown variable 'y'"));
+}
+
 #[test]
-fn symbol_table_test() {
-    let tokens = lex("
-    fn add(a: u64, b: u64) -> u64 {
+fn closure_captures() {
+    let src = "
+    fn main() {
+        let a = 1;
+        let b = 2;
+        let f = |x| {
+            let c = x;
+            b = a + c;
+        };
+        f(1);
+        return b;
+    }
+    ";
+    let (_, _, calls) = lex(src).and_then(parse).and_then(check).unwrap();
+    let closure = calls.closures.values().next().unwrap();
+    let captures = closure
+        .captures
+        .iter()
+        .map(|capture| (capture.name.as_str(), capture.by_ref))
+        .collect::<Vec<_>>();
+    assert_eq!(captures, [("b", true), ("a", false)]);
+}
+
+#[test]
+fn floats() {
+    let src = "
+    fn main() {
+        let x = 1.5;
+        let y = x + 2;
+        let z: f32 = x;
+        let ok = 3 as f64 * x;
+        println(x);
+        return true as f64;
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].contains("can not mix 'f64' and 'u64'"));
+    assert!(errors[1].contains("expected 'f32' but found 'f64'"));
+    assert!(errors[2].contains("cannot print a value of type 'f64'"));
+    assert!(errors[3].contains("can not cast a 'bool' to 'f64'"));
+}
+
+#[test]
+fn int_widths() {
+    let src = "
+    fn wide() -> i64 {
+        let n: i32 = 5;
+        return n;
+    }
+    fn main() {
+        let a: u8 = 300;
+        let b: u64 = 7;
+        let c: i32 = b;
+        let d: u8 = 255;
+        let e = d + 1;
+        let f: f32 = 0.5 * 2.0;
+        let g = b + d;
+        for i in 0..d {}
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].contains("expected 'i64' but found 'i32'"));
+    assert!(errors[1].contains("literal '300' does not fit in a 'u8'"));
+    assert!(errors[2].contains("expected 'i32' but found 'u64'"));
+    assert!(errors[3].contains("can not mix 'u64' and 'u8'"));
+}
+
+#[test]
+fn casts() {
+    let src = "
+    fn main() {
+        let x = 300 as u32;
+        let y = x as i32 as f32;
+        let z = 1 as bool;
+        let c = 'a' as u64;
+        let p = &mut c as u64 as *u32;
+        let q = p as i64;
+        return \"no\" as u64;
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].contains("can not cast a 'u64' to 'bool'"));
+    assert!(errors[1].contains("can not cast a '&mut u32' to 'i64'"));
+    assert!(errors[2].contains("can not cast a 'str' to 'u64'"));
+}
+
+#[test]
+fn bools() {
+    let src = "
+    fn main() {
+        let x = 1 + true;
+        let y = true + false;
+        let z: bool = 0;
+        if 1 {
+            println(true == false);
+        }
+        if true > false {}
+        return x;
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 5);
+    assert!(errors[0].contains("can not mix 'u64' and 'bool'"));
+    assert!(errors[1].contains("can not use '+' on a 'bool'"));
+    assert!(errors[2].contains("expected 'bool' but found 'u64'"));
+    assert!(errors[3].contains("expected a 'bool' condition but found 'u64'"));
+    assert!(errors[4].contains("can not use '>' on a 'bool'"));
+}
+
+#[test]
+fn bools_as_ints() {
+    let src = "
+    fn count(n: u64) -> u64 {
+        return n;
+    }
+    fn flag() -> u64 {
+        return true;
+    }
+    fn main() {
+        count(true);
+        let n: u64 = false;
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].contains("expected 'u64' but found 'bool'"));
+    assert!(errors[1].contains("expected 'u64' but found 'bool'"));
+    assert!(errors[2].contains("expected 'u64' but found 'bool'"));
+}
+
+#[test]
+fn generic_instances() {
+    let src = "
+    fn add<T>(a: T, b: T) -> T {
+        return a + b;
+    }
+    fn twice<T>(a: T) -> T {
+        return add(a, a);
+    }
+    fn main() {
+        add(1, 2);
+        add(true, false);
+        twice('a');
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("can not use '+' on a 'bool'"));
+    assert!(errors[0].contains("in 'add' called with T = bool at (9,8)->(9,24)"));
+    assert!(errors[1].contains("can not use '+' on a 'char'"));
+    // NOTE: `twice` is fine for a `char`, the `add` it calls is not.
+    assert!(errors[1].contains("in 'add' called with T = char at (5,15)->(5,24)"));
+}
+
+#[test]
+fn panic_msg() {
+    let src = "
+    fn main() {
+        panic(\"fine\");
+        panic(1);
+        panic();
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("expected a 'str' message to panic with but found 'u64'"));
+    assert!(errors[1].contains("'panic' takes 1 argument(s)"));
+}
+
+#[test]
+fn for_loops() {
+    let src = "
+    struct Point {
+        x: u64,
+    }
+    fn main() {
+        for i in 0..3 {
+            println(i);
+        }
+        for c in \"abc\" {}
+        for i in 0..true {}
+        let p = Point { x: 1 };
+        println(p);
+        return i;
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].contains("can not loop over a value of type 'str'"));
+    assert!(errors[1].contains("can not loop over a value of type 'bool'"));
+    assert!(errors[2].contains("cannot print a value of type 'Point'"));
+    assert!(errors[3].contains("unknown variable 'i'"));
+}
+
+#[test]
+fn extern_fns() {
+    let src = "
+    extern \"C\" fn puts(s: *u8) -> i32;
+    extern \"Rust\" fn drop(p: *u8);
+    extern fn write_str(s: str) -> u64;
+    fn main() {
+        puts(\"hi\" as *u8);
+        let f = puts;
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].contains("unknown calling convention 'Rust'"));
+    assert!(errors[1].contains("a 'str' can not be passed to or returned from an extern function"));
+    assert!(errors[2].contains("extern function 'puts' can only be called"));
+}
+
+#[test]
+fn exported_fns() {
+    let src = "
+    pub extern \"C\" fn add(a: i32, b: i32) -> i32 {
         return a + b;
     }
+    extern fn hidden() {}
+    pub extern \"Rust\" fn len_of(s: str) -> u64 {
+        return 0;
+    }
+    fn main() {
+        let n = add(1 as i32, 2 as i32);
+        let f = add;
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].contains("extern function 'hidden' is exported to C and has to be 'pub'"));
+    assert!(errors[1].contains("unknown calling convention 'Rust'"));
+    assert!(errors[2].contains("a 'str' can not be passed to or returned from an extern function"));
+}
+
+#[test]
+fn mismatched_args() {
+    let src = "
+    fn f(x: u64) -> u64 {
+        return x;
+    }
+    fn g(x: bool) -> u64 {
+        return 0;
+    }
+    fn main() {
+        f(\"hi\");
+        f(true);
+        let h: fn(u64) -> u64 = g;
+        let k = f;
+        k(1.5);
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].contains("expected 'u64' but found 'str'"));
+    assert!(errors[1].contains("expected 'u64' but found 'bool'"));
+    assert!(errors[2].contains("expected 'fn(u64) -> u64' but found 'fn(bool) -> u64'"));
+    assert!(errors[3].contains("expected 'u64' but found 'f64'"));
+}
+
+#[test]
+fn mismatched_assignments() {
+    let src = "
+    struct Point {
+        x: u64,
+    }
     fn main() {
-        return add(1,2);
-    }
-    ")
-    .unwrap();
-    let ast = parse(tokens).unwrap();
-    let mut builder = SymbolTableBuilder::default();
-    builder.visit(&ast);
-    let symbol_table = builder.build();
-    let Symbol::Variable(var_param_a) = symbol_table.get("a").unwrap() else {
-        unreachable!()
-    };
-    assert_eq!(var_param_a.scope, Scope::Param);
-    assert_eq!(var_param_a.name, "a");
-    assert_eq!(var_param_a.ty, Type::U64);
+        let x = 1;
+        x = \"hello\";
+        let y = 1.5;
+        y = 3;
+        let p = Point { x: true };
+        p.x = 'c';
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].contains("expected 'u64' but found 'str'"));
+    assert!(errors[1].contains("expected 'f64' but found 'u64'"));
+    assert!(errors[2].contains("expected 'u64' but found 'bool'"));
+    assert!(errors[3].contains("expected 'u64' but found 'char'"));
+}
+
+#[test]
+fn mismatched_returns() {
+    let src = "
+    fn f() -> u64 {
+        return \"hi\";
+    }
+    fn g(p: &u64) -> &mut u64 {
+        return p;
+    }
+    fn main() {
+        let c = |x: u64| -> bool { return x; };
+        return 0;
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].contains("expected 'u64' but found 'str'"));
+    assert!(errors[1].contains("expected '&mut u64' but found '&u64'"));
+    assert!(errors[2].contains("expected 'bool' but found 'u64'"));
+}
+
+#[test]
+fn invalid_ops() {
+    let src = "
+    fn main() {
+        let s = \"a\" * \"b\";
+        let c = 'a' + 'b';
+        let x = 1;
+        let p = &x;
+        let q = p + p;
+        let t = \"a\" + 1;
+        return p == &x;
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].contains("can not use '*' on a 'str'"));
+    assert!(errors[1].contains("can not use '+' on a 'char'"));
+    assert!(errors[2].contains("can not use '+' on a '&u64'"));
+    assert!(errors[3].contains("expected 'str' but found 'u64'"));
+}
+
+#[test]
+fn generic_struct_ok() {
+    let src = "
+    struct Pair<T> {
+        a: T,
+        b: T,
+    }
+    impl<T> Pair<T> {
+        fn new(a: T, b: T) -> Self {
+            return Pair { a: a, b: b };
+        }
+        fn first(&self) -> T {
+            return self.a;
+        }
+    }
+    fn main() {
+        let p: Pair<str> = Pair::new(\"a\", \"b\");
+        let s: str = p.first();
+        let q = Pair { a: 1, b: 2 };
+        let x: u64 = q.first() + q.b;
+    }
+    ";
+    assert_eq!(errors(src), Vec::<String>::new());
+}
+
+#[test]
+fn generic_struct_errors() {
+    let src = "
+    struct Pair<T> {
+        a: T,
+        b: T,
+    }
+    impl Pair<u64> {
+        fn f(&self) {}
+    }
+    fn g(p: Pair<u64, bool>) {}
+    fn main() {
+        let q = Pair { a: 1, b: true };
+        let n = size_of_val(1);
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].contains("an impl of 'Pair' has to declare its type parameters"));
+    assert!(errors[1].contains("'Pair' takes 1 type argument(s) but 2 were given"));
+    assert!(errors[2].contains("expected 'u64' but found 'bool'"));
+    assert!(errors[3].contains("expected a pointer to the value to take the size of"));
+}
+
+#[test]
+fn array_errors() {
+    let src = "
+    fn main() {
+        let a = [1, true];
+        let b: [u64; 2] = [1, 'c'];
+        let x = a[true];
+        let y = b[\"x\"];
+        let z = b[1.5];
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 5);
+    assert!(errors[0].contains("expected 'u64' but found 'bool'"));
+    assert!(errors[1].contains("expected 'u64' but found 'char'"));
+    assert!(errors[2].contains("expected 'u64' but found 'bool'"));
+    assert!(errors[3].contains("expected 'u64' but found 'str'"));
+    assert!(errors[4].contains("expected 'u64' but found 'f64'"));
+}
+
+#[test]
+fn escaping_closures() {
+    let src = "
+    struct Holder {
+        f: |u64| -> u64,
+    }
+    fn make(n: u64) -> |u64| -> u64 {
+        return |x| x + n;
+    }
+    fn wrap(n: u64) -> Holder {
+        return Holder { f: |x| x + n };
+    }
+    fn store(p: &mut Holder, n: u64) {
+        p.f = |x| x + n;
+    }
+    fn apply<T>(f: T) -> T {
+        return f;
+    }
+    fn main() -> u64 {
+        let n = 1;
+        let f = |x| x + n;
+        let g = f;
+        g = |x| x * n;
+        let adder = |a: u64| |b: u64| a + b;
+        let set = |y: u64| {
+            f = |x| x + y;
+            return 0;
+        };
+        return apply(g)(2);
+    }
+    ";
+    let errors = errors(src);
+    assert_eq!(errors.len(), 5);
+    assert!(errors[0].contains("(4,23)->(4,35) a closure cannot outlive the function"));
+    assert!(errors[1].contains("(7,23)->(7,29) a closure cannot outlive the function"));
+    assert!(errors[2].contains("(11,8)->(11,23) a closure cannot outlive the function"));
+    assert!(errors[3].contains("(21,20)->(21,43) a closure cannot outlive the function"));
+    assert!(errors[4].contains("(23,12)->(23,25) a closure cannot outlive the function"));
 }
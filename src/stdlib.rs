@@ -0,0 +1,24 @@
+//! The standard library is written in A and bundled with the compiler.
+//!
+//! It is a module of every program, `std::write`. The `pub` functions, consts and statics are
+//! also seen by their own name in any module that does not declare one like it.
+use crate::lexer;
+use crate::parse::{self, Item};
+use crate::symbol_table::SymbolTable;
+
+pub const SOURCE: &str = include_str!("../std/std.a");
+/// The path std items are named by, `std::alloc`.
+pub const MODULE: &str = "std";
+/// Where the std functions say they are written when they panic.
+pub const FILE: &str = "std/std.a";
+/// The std struct a `for` loop can go over the items of and `v[i]` indexes.
+pub const VEC: &str = "Vec";
+/// The method of `Vec` that `v[i]` calls for the address of the item, it checks the index.
+pub const VEC_INDEX: &str = "index";
+/// The std trait `print` and friends call for a struct.
+pub const DISPLAY: &str = "Display";
+
+/// The items of std, for the modules to be linked with.
+pub fn parse() -> Result<(Vec<Item>, SymbolTable), Vec<String>> {
+    lexer::lex(SOURCE).and_then(parse::parse)
+}
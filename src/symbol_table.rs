@@ -29,6 +29,7 @@
 // }
 
 use crate::lexer::Span;
+use crate::parse::Type;
 use std::collections::HashMap;
 pub type SymbolTable = HashMap<Symbol, SymbolData>;
 
@@ -36,7 +37,10 @@ pub type SymbolTable = HashMap<Symbol, SymbolData>;
 pub enum SymbolType {
     Function, // Return type
     Variable, // Variable type
-    // Constant, // Constant type
+    /// `const` items, replaced by their value wherever they are used.
+    Constant,
+    /// `static` items, living in the data segment.
+    Static,
     /// variable names created inside of a function definition
     Parameter, // Parameter type
                // / variable names created inside of a for loop
@@ -48,16 +52,17 @@ pub enum SymbolType {
                // Module, // Module type? ......
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub enum Scope {
+    #[default]
     Global,
+    /// Declared at the top of a file pulled in with `use`, `foo::bar` for nested ones.
+    Module(String),
     Function(String),
-}
-
-impl Default for Scope {
-    fn default() -> Self {
-        Self::Global
-    }
+    /// A `{ }` block, numbered in the order they show up in a file.
+    Block(usize),
+    /// Methods and associated functions declared in an `impl` of the type.
+    Type(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -66,11 +71,29 @@ pub struct Symbol {
     pub name: String,
 }
 
+impl Symbol {
+    /// The symbol a function is declared as, `foo::bar` is `bar` in module `foo`.
+    pub fn function(name: &str) -> Self {
+        match name.rsplit_once("::") {
+            Some((module, name)) => Self {
+                scope: Scope::Module(module.into()),
+                name: name.into(),
+            },
+            None => Self {
+                scope: Scope::Global,
+                name: name.into(),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TypeName {
     Bool,
     Char,
     Custom(String),
+    /// A generic struct and the types its type parameters stand for.
+    Generic(String, Vec<TypeName>),
     F32,
     F64,
     I32,
@@ -79,15 +102,132 @@ pub enum TypeName {
     Str,
     U32,
     U64,
+    U8,
     Void,
+    Array(Box<TypeName>, usize),
+    /// `mutable` is false for `&T`, true for `&mut T` and `*T`
+    Ptr(bool, Box<TypeName>),
+    /// A function value, its params and what it returns.
+    Fn(Vec<TypeName>, Box<TypeName>),
+    /// A closure, its params and what it returns.
+    Closure(Vec<TypeName>, Box<TypeName>),
+}
+
+impl std::fmt::Display for TypeName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool => write!(f, "bool"),
+            Self::Char => write!(f, "char"),
+            Self::Custom(name) => write!(f, "{name}"),
+            Self::Generic(name, args) => {
+                let args = args
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "{name}<{args}>")
+            }
+            Self::F32 => write!(f, "f32"),
+            Self::F64 => write!(f, "f64"),
+            Self::I32 => write!(f, "i32"),
+            Self::I64 => write!(f, "i64"),
+            Self::Null => write!(f, "null"),
+            Self::Str => write!(f, "str"),
+            Self::U32 => write!(f, "u32"),
+            Self::U64 => write!(f, "u64"),
+            Self::U8 => write!(f, "u8"),
+            Self::Void => write!(f, "void"),
+            Self::Array(ty, len) => write!(f, "[{ty}; {len}]"),
+            Self::Ptr(true, ty) => write!(f, "&mut {ty}"),
+            Self::Ptr(false, ty) => write!(f, "&{ty}"),
+            Self::Fn(params, ret) => {
+                let params = params
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "fn({params}) -> {ret}")
+            }
+            Self::Closure(params, ret) => {
+                let params = params
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "|{params}| -> {ret}")
+            }
+        }
+    }
+}
+
+impl TypeName {
+    pub fn is_float(&self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+
+    pub fn is_int(&self) -> bool {
+        matches!(
+            self,
+            Self::I32 | Self::I64 | Self::U32 | Self::U64 | Self::U8
+        )
+    }
+
+    /// The struct a generic one is made from, `Vec` for a `Vec<u64>`. Its impls are declared
+    /// for every type argument at once.
+    pub fn base(&self) -> TypeName {
+        match self {
+            Self::Generic(name, _) => Self::Custom(name.clone()),
+            _ => self.clone(),
+        }
+    }
+
+    /// The type as it can show up in a label, `arr4_u64` for `[u64; 4]`.
+    pub fn mangle(&self) -> String {
+        match self {
+            Self::Generic(name, args) => {
+                let args = args.iter().map(Self::mangle).collect::<Vec<_>>();
+                format!("{name}_{}", args.join("_"))
+            }
+            Self::Array(ty, len) => format!("arr{len}_{}", ty.mangle()),
+            Self::Ptr(_, ty) => format!("ptr_{}", ty.mangle()),
+            Self::Fn(params, ret) => {
+                let params = params.iter().map(Self::mangle).collect::<Vec<_>>();
+                format!("fn_{}_to_{}", params.join("_"), ret.mangle())
+            }
+            Self::Closure(params, ret) => {
+                let params = params.iter().map(Self::mangle).collect::<Vec<_>>();
+                format!("closure_{}_to_{}", params.join("_"), ret.mangle())
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    /// Replaces the type parameters in `bindings` with the types they stand for.
+    pub fn substitute(&self, bindings: &HashMap<String, TypeName>) -> TypeName {
+        match self {
+            Self::Custom(name) => bindings.get(name).cloned().unwrap_or(self.clone()),
+            Self::Generic(name, args) => Self::Generic(
+                name.clone(),
+                args.iter().map(|ty| ty.substitute(bindings)).collect(),
+            ),
+            Self::Array(ty, len) => Self::Array(Box::new(ty.substitute(bindings)), *len),
+            Self::Ptr(mutable, ty) => Self::Ptr(*mutable, Box::new(ty.substitute(bindings))),
+            Self::Fn(params, ret) => Self::Fn(
+                params.iter().map(|ty| ty.substitute(bindings)).collect(),
+                Box::new(ret.substitute(bindings)),
+            ),
+            Self::Closure(params, ret) => Self::Closure(
+                params.iter().map(|ty| ty.substitute(bindings)).collect(),
+                Box::new(ret.substitute(bindings)),
+            ),
+            _ => self.clone(),
+        }
+    }
 }
 
-impl<T> From<T> for TypeName
-where
-    T: std::fmt::Display,
-{
-    fn from(value: T) -> Self {
-        match value.to_string().as_str() {
+impl From<&str> for TypeName {
+    fn from(value: &str) -> Self {
+        match value {
             "null" => TypeName::Null,
             "i32" => TypeName::I32,
             "i64" => TypeName::I64,
@@ -95,6 +235,7 @@ where
             "f64" => TypeName::F64,
             "u32" => TypeName::U32,
             "u64" => TypeName::U64,
+            "u8" => TypeName::U8,
             "void" => TypeName::Void,
             "bool" => TypeName::Bool,
             "char" => TypeName::Char,
@@ -104,6 +245,31 @@ where
     }
 }
 
+impl From<&Type> for TypeName {
+    fn from(value: &Type) -> Self {
+        match value {
+            Type::Name(ident) => Self::from(ident.value.as_str()),
+            Type::Generic(generic) => Self::Generic(
+                generic.name.value.clone(),
+                generic.args.iter().map(Self::from).collect(),
+            ),
+            Type::Array(array) => {
+                Self::Array(Box::new(array.elem.as_ref().into()), array.len.value())
+            }
+            Type::Ptr(ptr) => Self::Ptr(ptr.is_mutable(), Box::new(ptr.elem.as_ref().into())),
+            Type::Fn(func) => Self::Fn(
+                func.params.iter().map(Self::from).collect(),
+                Box::new(func.ret.as_deref().map(Self::from).unwrap_or(Self::Null)),
+            ),
+            Type::Closure(closure) => Self::Closure(
+                closure.params.iter().map(Self::from).collect(),
+                Box::new(closure.ret.as_deref().map(Self::from).unwrap_or(Self::Null)),
+            ),
+        }
+    }
+}
+
+#[allow(unused)]
 #[derive(Debug, Clone)]
 pub struct SymbolData {
     pub ty: SymbolType,
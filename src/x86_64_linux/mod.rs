@@ -1,4 +1,5 @@
 mod reg_state;
+pub mod runtime;
 #[cfg(test)]
 mod test;
 pub mod x86reg;
@@ -11,23 +12,88 @@ use crate::{ir, symbol_table::SymbolTable};
 //     compile_ir_code(ir).and_then(instruction_to_string)
 // }
 
+/// `object` compiles to a relocatable object that is linked against C, otherwise to an executable
+/// on its own.
 pub fn compile_ir_code(
     (ir, symbol_table): (Vec<ir::Instruction>, SymbolTable),
+    object: bool,
 ) -> Result<Vec<Instruction>, Vec<String>> {
     let mut state = RegState::default();
-    Ok(ir
+    let (externs, ir): (Vec<_>, Vec<_>) = ir
         .iter()
-        .map(|i| i.compile(&mut state, &symbol_table))
-        .collect::<Vec<Vec<Instruction>>>()
+        .partition(|i| matches!(i, ir::Instruction::Extern(_)));
+    if !object && !externs.is_empty() {
+        return Err(externs
+            .iter()
+            .filter_map(|i| match i {
+                ir::Instruction::Extern(ir::Extern(name)) => Some(format!(
+                    "extern function '{name}' can only be linked into an object, build with --object"
+                )),
+                _ => None,
+            })
+            .collect());
+    }
+    // NOTE: an executable has no symbols for C to find them by, they are left as A functions.
+    let (exports, ir): (Vec<_>, Vec<_>) = ir
+        .into_iter()
+        .partition(|i| object && matches!(i, ir::Instruction::Export(_)));
+    let (statics, ir): (Vec<_>, Vec<_>) = ir
         .into_iter()
-        .flatten()
-        .collect::<Vec<Instruction>>())
+        .partition(|i| matches!(i, ir::Instruction::DefStatic(_)));
+    let (data, code): (Vec<_>, Vec<_>) = ir
+        .into_iter()
+        .partition(|i| matches!(i, ir::Instruction::DefData(_)));
+    // NOTE: a library has no main, the C program it is linked into brings its own.
+    let has_main = code
+        .iter()
+        .any(|i| matches!(i, ir::Instruction::DefFunc(f) if f.name == "main"));
+    let exports_main = exports
+        .iter()
+        .any(|i| matches!(i, ir::Instruction::Export(e) if e.symbol.0 == "main"));
+    let mut result = match object && has_main && !exports_main {
+        true => runtime::c_main(),
+        false => vec![],
+    };
+    result.extend(
+        externs
+            .iter()
+            .chain(exports.iter())
+            .chain(code.iter())
+            .flat_map(|i| i.compile(&mut state, &symbol_table)),
+    );
+    if !data.is_empty() {
+        result.push(match object {
+            true => Instruction::Section("'.rodata'".into()),
+            false => Instruction::Segment("readable".into()),
+        });
+        result.extend(
+            data.iter()
+                .flat_map(|i| i.compile(&mut state, &symbol_table)),
+        );
+    }
+    // NOTE: the runtime data is written to the end of this segment.
+    result.push(match object {
+        true => Instruction::Section("'.data' writeable".into()),
+        false => Instruction::Segment("readable writable".into()),
+    });
+    result.extend(
+        statics
+            .iter()
+            .flat_map(|i| i.compile(&mut state, &symbol_table)),
+    );
+    Ok(result)
 }
 
 pub fn instruction_to_string(ir: Vec<Instruction>) -> Result<String, Vec<String>> {
     Ok(ir.iter().map(ToString::to_string).collect())
 }
 
+/// `foo::bar` is written `foo.bar` for the assembler, for functions and statics alike. It never clashes with the local label
+/// `.bar` of a function `foo` as that one ends up as `foo__.bar`.
+fn fn_label(name: &str) -> String {
+    name.replace("::", ".")
+}
+
 trait Compile {
     fn compile(&self, state: &mut RegState, symbol_table: &SymbolTable) -> Vec<Instruction>;
 }
@@ -39,12 +105,71 @@ trait TypeSize {
 impl TypeSize for ir::Type {
     fn size(&self) -> &'static str {
         match self {
-            Self::U64 => "qword",
+            Self::U64 | Self::I64 => "qword",
+            Self::U32 | Self::I32 => "dword",
             Self::Null => unreachable!("no size"),
+            Self::Ptr(_) | Self::Fn(_) | Self::Closure(_) | Self::Char => "qword",
+            Self::Bool | Self::U8 => "byte",
+            Self::F32 => "dword",
+            Self::F64 => "qword",
+            Self::Str => unreachable!("a str is a ptr and len pair"),
+            Self::Array(..) => unreachable!("arrays are not a single operand"),
+            Self::Struct(..) => unreachable!("structs are copied field by field"),
+        }
+    }
+}
+
+/// Which of the scalar SSE instructions a float uses, `addss` for an `f32` and `addsd` for an
+/// `f64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    Single,
+    Double,
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Single => write!(f, "ss"),
+            Self::Double => write!(f, "sd"),
+        }
+    }
+}
+
+impl Precision {
+    fn other(&self) -> Self {
+        match self {
+            Self::Single => Self::Double,
+            Self::Double => Self::Single,
         }
     }
 }
 
+/// The flags a `setcc` or `jcc` looks at, `A` and `B` are the unsigned ones `ucomisd` sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cond {
+    G,
+    L,
+    Ge,
+    Le,
+    E,
+    Ne,
+    A,
+    Ae,
+    B,
+    Be,
+    P,
+    Np,
+    No,
+    Nc,
+}
+
+impl fmt::Display for Cond {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Instruction {
     Comment(String),
@@ -53,20 +178,78 @@ pub enum Instruction {
     MoveMemReg(Mem, X86Reg),
     MoveRegMem(X86Reg, Mem),
     MoveZx(X86Reg),
+    MoveZxReg(X86Reg, X86Reg),
+    MoveSx(X86Reg, X86Reg),
+    MoveSxd(X86Reg, X86Reg),
     Add(X86Reg, X86Reg),
+    AddImm(X86Reg, u64),
     Sub(X86Reg, X86Reg),
+    SubImm(X86Reg, u64),
     Mul(X86Reg, X86Reg),
-    Div(X86Reg, X86Reg),
+    /// Signed divide of rdx:rax, the quotient goes in rax.
+    IDiv(X86Reg),
+    UDiv(X86Reg),
+    /// `mul`, rdx:rax is rax times the register.
+    UMul(X86Reg),
+    /// Sign extends rax into rdx.
+    Cqo,
+    AndImm(X86Reg, u64),
+    OrImm(X86Reg, u64),
+    ShrImm(X86Reg, u64),
+    And(X86Reg, X86Reg),
+    Or(X86Reg, X86Reg),
+    AddF(Precision, X86Reg, X86Reg),
+    SubF(Precision, X86Reg, X86Reg),
+    MulF(Precision, X86Reg, X86Reg),
+    DivF(Precision, X86Reg, X86Reg),
+    /// `ucomisd`, sets the flags like an unsigned compare and the parity flag for a NaN.
+    CmpF(Precision, X86Reg, X86Reg),
+    /// An int to a float of the given precision.
+    IntToF(Precision, X86Reg, X86Reg),
+    /// A float of the given precision to an int, rounding towards zero.
+    FToInt(Precision, X86Reg, X86Reg),
+    /// A float to the given precision from the other one.
+    FToF(Precision, X86Reg, X86Reg),
     DefLabel(String),
     Call(String),
+    CallReg(X86Reg),
+    /// A function from outside of the object, through the PLT so it can come from a shared
+    /// library.
+    CallExtern(String),
+    /// `and rsp, -16`, down to the 16 byte boundary the stack is at when C calls something.
+    AlignStack,
     Jump(String),
     JumpZero(String),
+    JumpNotZero(String),
+    JumpBelow(String),
+    JumpAboveEqual(String),
+    JumpIf(Cond, String),
     Cmp(X86Reg, X86Reg),
+    CmpImm(X86Reg, u64),
     Test(X86Reg, X86Reg),
-    SetG,
+    Set(Cond, X86Reg),
     ProLog,
     Epilog,
+    Ret,
     Syscall,
+    Push(X86Reg),
+    /// A whole qword from memory.
+    PushMem(Mem),
+    Pop(X86Reg),
+    LoadAddr(X86Reg, String),
+    Lea(X86Reg, Mem),
+    DefBytes(String, Vec<u8>),
+    /// Quad words that can be addresses, the linker fills those in.
+    DefQwords(String, Vec<String>),
+    Segment(String),
+    /// A section of a relocatable object, which has them where an executable has segments.
+    Section(String),
+    /// A symbol another object defines.
+    Extrn(String),
+    /// A symbol other objects can link against.
+    Public(String),
+    /// A label under its own name, without the suffix the generated ones get, for C to find it by.
+    Symbol(String),
 }
 
 impl fmt::Display for Instruction {
@@ -86,23 +269,85 @@ impl fmt::Display for Instruction {
                 )
             }
             Self::MoveReg(des, src) => {
-                writeln!(f, "{:>4}{:<10}{:<10}{}", " ", "mov", format!("{des},"), src)
+                let mov = match (des.is_xmm(), src.is_xmm()) {
+                    (true, true) => "movaps",
+                    (false, false) => "mov",
+                    _ => "movq",
+                };
+                writeln!(f, "{:>4}{:<10}{:<10}{}", " ", mov, format!("{des},"), src)
             }
             Self::MoveMemReg(mem, reg) => {
-                writeln!(f, "{:>4}{:<16}{:<10}{}", " ", "mov", format!("{mem},"), reg)
+                // NOTE: a byte or a dword goes out of the low bits of the register.
+                let (mov, reg) = match (mem.size(), reg.is_xmm()) {
+                    ("byte", false) => ("mov", reg.as_low_8_bit().into()),
+                    ("dword", false) => ("mov", reg.as_32_bit().into()),
+                    _ => (mem.mov(reg), *reg),
+                };
+                writeln!(f, "{:>4}{:<16}{:<10}{}", " ", mov, format!("{mem},"), reg)
             }
             Self::MoveRegMem(reg, mem) => {
-                writeln!(f, "{:>4}{:<10}{:<16}{}", " ", "mov", format!("{reg},"), mem,)
+                // NOTE: writing the 32 bit register clears the top of a `u32`, an `i32` is sign
+                // extended.
+                let (mov, reg) = match (mem.size(), reg.is_xmm(), mem.ty()) {
+                    ("dword", false, ir::Type::I32) => ("movsxd", *reg),
+                    ("dword", false, _) => ("mov", reg.as_32_bit().into()),
+                    _ => (mem.mov(reg), *reg),
+                };
+                writeln!(f, "{:>4}{:<10}{:<16}{}", " ", mov, format!("{reg},"), mem,)
             }
             Self::MoveZx(src) => {
                 writeln!(f, "{:>4}{:<10}{:<10}al", " ", "movzx", format!("{src},"),)
             }
+            Self::MoveZxReg(des, src) => writeln!(
+                f,
+                "{:>4}{:<10}{:<10}{}",
+                " ",
+                "movzx",
+                format!("{des},"),
+                src
+            ),
+            Self::MoveSx(des, src) => writeln!(
+                f,
+                "{:>4}{:<10}{:<10}{}",
+                " ",
+                "movsx",
+                format!("{des},"),
+                src
+            ),
+            Self::MoveSxd(des, src) => writeln!(
+                f,
+                "{:>4}{:<10}{:<10}{}",
+                " ",
+                "movsxd",
+                format!("{des},"),
+                src
+            ),
             Self::Add(des, reg) => {
                 writeln!(f, "{:>4}{:<10}{:<10}{}", " ", "add", format!("{des},"), reg)
             }
+            Self::AddImm(des, value) => {
+                writeln!(
+                    f,
+                    "{:>4}{:<10}{:<10}{}",
+                    " ",
+                    "add",
+                    format!("{des},"),
+                    value
+                )
+            }
             Self::Sub(des, reg) => {
                 writeln!(f, "{:>4}{:<10}{:<10}{}", " ", "sub", format!("{des},"), reg)
             }
+            Self::SubImm(des, value) => {
+                writeln!(
+                    f,
+                    "{:>4}{:<10}{:<10}{}",
+                    " ",
+                    "sub",
+                    format!("{des},"),
+                    value
+                )
+            }
             Self::Mul(des, reg) => writeln!(
                 f,
                 "{:>4}{:<10}{:<10}{}",
@@ -111,21 +356,142 @@ impl fmt::Display for Instruction {
                 format!("{des},"),
                 reg
             ),
-            Self::Div(des, reg) => writeln!(
+            Self::IDiv(reg) => writeln!(f, "{:>4}{:<10}{reg}", " ", "idiv"),
+            Self::UDiv(reg) => writeln!(f, "{:>4}{:<10}{reg}", " ", "div"),
+            Self::UMul(reg) => writeln!(f, "{:>4}{:<10}{reg}", " ", "mul"),
+            Self::Cqo => writeln!(f, "{:>4}cqo", " "),
+            Self::AndImm(des, value) => {
+                writeln!(
+                    f,
+                    "{:>4}{:<10}{:<10}{}",
+                    " ",
+                    "and",
+                    format!("{des},"),
+                    value
+                )
+            }
+            Self::OrImm(des, value) => {
+                writeln!(
+                    f,
+                    "{:>4}{:<10}{:<10}{}",
+                    " ",
+                    "or",
+                    format!("{des},"),
+                    value
+                )
+            }
+            Self::ShrImm(des, value) => {
+                writeln!(
+                    f,
+                    "{:>4}{:<10}{:<10}{}",
+                    " ",
+                    "shr",
+                    format!("{des},"),
+                    value
+                )
+            }
+            Self::And(des, reg) => {
+                writeln!(f, "{:>4}{:<10}{:<10}{}", " ", "and", format!("{des},"), reg)
+            }
+            Self::Or(des, reg) => {
+                writeln!(f, "{:>4}{:<10}{:<10}{}", " ", "or", format!("{des},"), reg)
+            }
+            Self::AddF(p, des, reg) => {
+                writeln!(
+                    f,
+                    "{:>4}{:<10}{:<10}{}",
+                    " ",
+                    format!("add{p}"),
+                    format!("{des},"),
+                    reg
+                )
+            }
+            Self::SubF(p, des, reg) => {
+                writeln!(
+                    f,
+                    "{:>4}{:<10}{:<10}{}",
+                    " ",
+                    format!("sub{p}"),
+                    format!("{des},"),
+                    reg
+                )
+            }
+            Self::MulF(p, des, reg) => {
+                writeln!(
+                    f,
+                    "{:>4}{:<10}{:<10}{}",
+                    " ",
+                    format!("mul{p}"),
+                    format!("{des},"),
+                    reg
+                )
+            }
+            Self::DivF(p, des, reg) => {
+                writeln!(
+                    f,
+                    "{:>4}{:<10}{:<10}{}",
+                    " ",
+                    format!("div{p}"),
+                    format!("{des},"),
+                    reg
+                )
+            }
+            Self::CmpF(p, lhs, rhs) => writeln!(
+                f,
+                "{:>4}{:<10}{:<10}{}",
+                " ",
+                format!("ucomi{p}"),
+                format!("{lhs},"),
+                rhs
+            ),
+            Self::IntToF(p, des, src) => writeln!(
                 f,
                 "{:>4}{:<10}{:<10}{}",
                 " ",
-                "idiv",
+                format!("cvtsi2{p}"),
                 format!("{des},"),
-                reg
+                src
+            ),
+            Self::FToInt(p, des, src) => writeln!(
+                f,
+                "{:>4}{:<10}{:<10}{}",
+                " ",
+                format!("cvtt{p}2si"),
+                format!("{des},"),
+                src
+            ),
+            Self::FToF(p, des, src) => writeln!(
+                f,
+                "{:>4}{:<10}{:<10}{}",
+                " ",
+                format!("cvt{}2{p}", p.other()),
+                format!("{des},"),
+                src
             ),
             Self::DefLabel(name) => writeln!(f, "{name}__:"),
             Self::Call(name) => writeln!(f, "{:>4}{:<10}{name}__", " ", "call"),
+            Self::CallReg(reg) => writeln!(f, "{:>4}{:<10}{reg}", " ", "call"),
+            Self::CallExtern(name) => writeln!(f, "{:>4}{:<10}plt {name}", " ", "call"),
+            Self::AlignStack => writeln!(f, "{:>4}{:<10}{:<10}-16", " ", "and", "rsp,"),
             Self::Jump(name) => writeln!(f, "{:>4}{:<10}{name}__", " ", "jmp"),
             Self::JumpZero(name) => writeln!(f, "{:>4}{:<10}{name}__", " ", "jz"),
+            Self::JumpNotZero(name) => writeln!(f, "{:>4}{:<10}{name}__", " ", "jnz"),
+            Self::JumpBelow(name) => writeln!(f, "{:>4}{:<10}{name}__", " ", "jb"),
+            Self::JumpAboveEqual(name) => writeln!(f, "{:>4}{:<10}{name}__", " ", "jae"),
+            Self::JumpIf(cond, name) => writeln!(f, "{:>4}{:<10}{name}__", " ", format!("j{cond}")),
             Self::Cmp(lhs, rhs) => {
                 writeln!(f, "{:>4}{:<10}{:<10}{}", " ", "cmp", format!("{lhs},"), rhs)
             }
+            Self::CmpImm(lhs, value) => {
+                writeln!(
+                    f,
+                    "{:>4}{:<10}{:<10}{}",
+                    " ",
+                    "cmp",
+                    format!("{lhs},"),
+                    value
+                )
+            }
             Self::Test(lhs, rhs) => writeln!(
                 f,
                 "{:>4}{:<10}{:<10}{}",
@@ -134,19 +500,75 @@ impl fmt::Display for Instruction {
                 format!("{lhs},"),
                 rhs
             ),
-            Self::SetG => writeln!(f, "{:>4}{:<10}al", " ", "setg"),
+            Self::Set(cond, reg) => writeln!(f, "{:>4}{:<10}{reg}", " ", format!("set{cond}")),
             Self::ProLog => {
                 let push = format!("{:>4}{:<10}rbp", " ", "push");
                 let mov = format!("{:>4}{:<10}{:<10}rsp", " ", "mov", "rbp,");
                 writeln!(f, "{push}\n{mov}")
             }
             Self::Epilog => {
-                let mov = format!("{:>4}{:<10}{:<10}rsp", " ", "mov", "rbp,");
+                let mov = format!("{:>4}{:<10}{:<10}rbp", " ", "mov", "rsp,");
                 let pop = format!("{:>4}{:<10}rbp", " ", "pop");
                 let ret = format!("{:>4}ret", " ");
                 writeln!(f, "{mov}\n{pop}\n{ret}")
             }
+            Self::Ret => writeln!(f, "{:>4}ret", " "),
             Self::Syscall => writeln!(f, "{:>4}syscall", " "),
+            // NOTE: there is no push or pop for an xmm register, the stack is moved by hand.
+            Self::Push(reg) if reg.is_xmm() => {
+                let sub = format!("{:>4}{:<10}{:<10}8", " ", "sub", "rsp,");
+                let mov = format!("{:>4}{:<10}{:<16}{reg}", " ", "movsd", "qword [rsp],");
+                writeln!(f, "{sub}\n{mov}")
+            }
+            Self::Pop(reg) if reg.is_xmm() => {
+                let mov = format!(
+                    "{:>4}{:<10}{:<16}qword [rsp]",
+                    " ",
+                    "movsd",
+                    format!("{reg},")
+                );
+                let add = format!("{:>4}{:<10}{:<10}8", " ", "add", "rsp,");
+                writeln!(f, "{mov}\n{add}")
+            }
+            Self::Push(reg) => writeln!(f, "{:>4}{:<10}{reg}", " ", "push"),
+            Self::PushMem(mem) => writeln!(f, "{:>4}{:<10}{mem}", " ", "push"),
+            Self::Pop(reg) => writeln!(f, "{:>4}{:<10}{reg}", " ", "pop"),
+            Self::LoadAddr(reg, name) => {
+                writeln!(
+                    f,
+                    "{:>4}{:<10}{:<10}[{name}__]",
+                    " ",
+                    "lea",
+                    format!("{reg},")
+                )
+            }
+            Self::Lea(reg, mem) => {
+                writeln!(
+                    f,
+                    "{:>4}{:<10}{:<10}{}",
+                    " ",
+                    "lea",
+                    format!("{reg},"),
+                    mem.address()
+                )
+            }
+            Self::DefBytes(name, bytes) => {
+                let bytes = bytes
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                if bytes.is_empty() {
+                    return writeln!(f, "{name}__:");
+                }
+                writeln!(f, "{name}__ db {bytes}")
+            }
+            Self::DefQwords(name, qwords) => writeln!(f, "{name}__ dq {}", qwords.join(",")),
+            Self::Segment(flags) => writeln!(f, "segment {flags}"),
+            Self::Section(name) => writeln!(f, "section {name}"),
+            Self::Extrn(name) => writeln!(f, "extrn {name}"),
+            Self::Public(name) => writeln!(f, "public {name}"),
+            Self::Symbol(name) => writeln!(f, "{name}:"),
         }
     }
 }
@@ -155,6 +577,7 @@ impl Compile for ir::Instruction {
     fn compile(&self, state: &mut RegState, st: &SymbolTable) -> Vec<Instruction> {
         match self {
             ir::Instruction::LoadImm(i) => i.compile(state, st),
+            ir::Instruction::LoadFloat(i) => i.compile(state, st),
             ir::Instruction::CopyReg(i) => i.compile(state, st),
             ir::Instruction::DefFunc(i) => i.compile(state, st),
             ir::Instruction::Add(i) => i.compile(state, st),
@@ -162,6 +585,15 @@ impl Compile for ir::Instruction {
             ir::Instruction::Mul(i) => i.compile(state, st),
             ir::Instruction::Div(i) => i.compile(state, st),
             ir::Instruction::Grt(i) => i.compile(state, st),
+            ir::Instruction::Les(i) => i.compile(state, st),
+            ir::Instruction::Geq(i) => i.compile(state, st),
+            ir::Instruction::Leq(i) => i.compile(state, st),
+            ir::Instruction::Equal(i) => i.compile(state, st),
+            ir::Instruction::Neq(i) => i.compile(state, st),
+            ir::Instruction::Convert(i) => i.compile(state, st),
+            ir::Instruction::Trunc(i) => i.compile(state, st),
+            ir::Instruction::ZExt(i) => i.compile(state, st),
+            ir::Instruction::SExt(i) => i.compile(state, st),
             ir::Instruction::Copy(i) => i.compile(state, st),
             ir::Instruction::Conditional(i) => i.compile(state, st),
             ir::Instruction::Jump(i) => i.compile(state, st),
@@ -170,6 +602,31 @@ impl Compile for ir::Instruction {
             ir::Instruction::Return(i) => i.compile(state, st),
             ir::Instruction::Enter(i) => i.compile(state, st),
             ir::Instruction::Leave(i) => i.compile(state, st),
+            ir::Instruction::LoadLocal(i) => i.compile(state, st),
+            ir::Instruction::StoreLocal(i) => i.compile(state, st),
+            ir::Instruction::LoadElem(i) => i.compile(state, st),
+            ir::Instruction::StoreElem(i) => i.compile(state, st),
+            ir::Instruction::FillArray(i) => i.compile(state, st),
+            ir::Instruction::BoundsCheck(i) => i.compile(state, st),
+            ir::Instruction::OverflowCheck(i) => i.compile(state, st),
+            ir::Instruction::ZeroCheck(i) => i.compile(state, st),
+            ir::Instruction::DivOverflowCheck(i) => i.compile(state, st),
+            ir::Instruction::Panic(i) => i.compile(state, st),
+            ir::Instruction::AddrOf(i) => i.compile(state, st),
+            ir::Instruction::Load(i) => i.compile(state, st),
+            ir::Instruction::Store(i) => i.compile(state, st),
+            ir::Instruction::CopyMem(i) => i.compile(state, st),
+            ir::Instruction::ElemPtr(i) => i.compile(state, st),
+            ir::Instruction::DataAddr(i) => i.compile(state, st),
+            ir::Instruction::DefData(i) => i.compile(state, st),
+            ir::Instruction::Extern(i) => i.compile(state, st),
+            ir::Instruction::Export(i) => i.compile(state, st),
+            ir::Instruction::Discard(i) => i.compile(state, st),
+            ir::Instruction::Push(i) => i.compile(state, st),
+            ir::Instruction::Syscall(i) => i.compile(state, st),
+            ir::Instruction::LoadStatic(i) => i.compile(state, st),
+            ir::Instruction::StoreStatic(i) => i.compile(state, st),
+            ir::Instruction::DefStatic(i) => i.compile(state, st),
         }
     }
 }
@@ -185,6 +642,23 @@ impl Compile for ir::LoadImm {
         vec![Instruction::MoveImm(reg, *imm)]
     }
 }
+// LoadFloat(LoadFloat),
+impl Compile for ir::LoadFloat {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::LoadFloat { des, bits } = self;
+        let bits = match state.precision(des) {
+            Some(Precision::Single) => (f64::from_bits(*bits) as f32).to_bits() as u64,
+            _ => *bits,
+        };
+        // NOTE: there is no immediate form for xmm registers, the bits go through rax.
+        let rax = X86Reg64::RAX.into();
+        let des = state.get_reg(des);
+        vec![
+            Instruction::MoveImm(rax, bits),
+            Instruction::MoveReg(des, rax),
+        ]
+    }
+}
 // CopyReg(CopyReg),
 impl Compile for ir::CopyReg {
     fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
@@ -198,43 +672,44 @@ impl Compile for ir::CopyReg {
 impl Compile for ir::DefFunc {
     fn compile(&self, state: &mut RegState, st: &SymbolTable) -> Vec<Instruction> {
         let ir::DefFunc {
-            name, params, body, ..
+            name,
+            params,
+            body,
+            stack_size,
+            mem_types,
         } = self;
-        let mut result = vec![Instruction::DefLabel(name.into())];
-        let params = params
-            .iter()
-            .enumerate()
-            .flat_map(|(idx, (reg, ty))| {
-                let xreg = state.get_param_reg(reg);
-                vec![
-                    Instruction::MoveMemReg(
-                        Mem::Param {
-                            ty: *ty,
-                            offset: (idx + 1) * 8,
-                        },
-                        xreg,
-                    ),
-                    Instruction::MoveRegMem(
-                        xreg,
-                        Mem::Param {
-                            ty: *ty,
-                            offset: (idx + 1) * 8,
-                        },
-                    ),
-                ]
-            })
-            .collect::<Vec<Instruction>>();
-        let mut body = body
-            .iter()
-            .flat_map(|inst| inst.compile(state, st))
-            .collect::<Vec<Instruction>>();
-        result.push(body.remove(0));
-        result.extend_from_slice(&params);
-        result.extend_from_slice(&body);
-        // let ret_reg = state.get_ret_reg();
-        // let last_reg = state.last_used_reg();
-        // let instruction = Instruction::MoveReg(ret_reg, last_reg);
-        // result.insert(result.len().saturating_sub(1), instruction);
+        state.set_mem_types(mem_types.clone());
+        state.set_locals_size(*stack_size);
+        let locs = arg_locs(params.iter().map(|(reg, _)| state.precision(reg).is_some()));
+        for ((reg, _), loc) in params.iter().zip(locs) {
+            match loc {
+                ArgLoc::Reg(_) => {
+                    state.get_param_reg(reg);
+                }
+                // NOTE: above the saved rbp and the return address.
+                ArgLoc::Stack(n) => state.set_stack_arg(reg, 16 + n * 8),
+            }
+        }
+        let mut result = vec![Instruction::DefLabel(fn_label(name))];
+        let mut body = body.iter();
+        // The frame has to be set up before the params can be spilled into it.
+        if let Some(enter) = body.next() {
+            result.extend(enter.compile(state, st));
+        }
+        let frame_at = result.len();
+        for inst in body {
+            let code = inst.compile(state, st);
+            // NOTE: a param passed on the stack is loaded the first time it is used, a value
+            // spilled to free up a register the next time it is.
+            result.extend(state.take_moves());
+            result.extend(code);
+        }
+        // NOTE: the spilled values are only known once the body is compiled.
+        let frame_size = *stack_size + state.spills_size();
+        if frame_size > 0 {
+            let sub = Instruction::SubImm(X86Reg64::RSP.into(), frame_size as u64);
+            result.insert(frame_at, sub);
+        }
         state.reset();
         result
     }
@@ -248,10 +723,14 @@ impl Compile for ir::Add {
         state.release_reg(lhs);
         let xrhs = state.get_reg(rhs);
         state.release_reg(rhs);
+        let op = match state.precision(des) {
+            Some(p) => Instruction::AddF(p, xdes, xrhs),
+            None => Instruction::Add(xdes, xrhs),
+        };
         vec![
             Instruction::Comment("Add".into()),
             Instruction::MoveReg(xdes, xlhs),
-            Instruction::Add(xdes, xrhs),
+            op,
         ]
     }
 }
@@ -261,11 +740,17 @@ impl Compile for ir::Sub {
         let ir::Sub { des, lhs, rhs } = self;
         let des = state.get_reg(des);
         let lhs = state.get_reg(lhs);
+        state.release_reg(&self.lhs);
         let rhs = state.get_reg(rhs);
+        state.release_reg(&self.rhs);
+        let op = match state.precision(&self.des) {
+            Some(p) => Instruction::SubF(p, des, rhs),
+            None => Instruction::Sub(des, rhs),
+        };
         vec![
             Instruction::Comment("Sub".into()),
             Instruction::MoveReg(des, lhs),
-            Instruction::Sub(des, rhs),
+            op,
         ]
     }
 }
@@ -273,46 +758,199 @@ impl Compile for ir::Sub {
 impl Compile for ir::Mul {
     fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
         let ir::Mul { des, lhs, rhs } = self;
+        let signed = state.is_signed(lhs);
         let xdes = state.get_reg(des);
         let xlhs = state.get_reg(lhs);
         state.release_reg(lhs);
         let xrhs = state.get_reg(rhs);
         state.release_reg(rhs);
+        let op = match state.precision(des) {
+            Some(p) => Instruction::MulF(p, xdes, xrhs),
+            None if !signed => return umul(xdes, xlhs, xrhs),
+            None => Instruction::Mul(xdes, xrhs),
+        };
         vec![
             Instruction::Comment("Mul".into()),
             Instruction::MoveReg(xdes, xlhs),
-            Instruction::Mul(xdes, xrhs),
+            op,
         ]
     }
 }
+/// An unsigned multiply goes through rdx:rax like a divide, so the carry after it is set when the
+/// product does not fit in 64 bits.
+fn umul(des: X86Reg, lhs: X86Reg, rhs: X86Reg) -> Vec<Instruction> {
+    let rax = X86Reg64::RAX.into();
+    let rdx = X86Reg64::RDX.into();
+    let r11 = X86Reg64::R11.into();
+    vec![
+        Instruction::Comment("Mul".into()),
+        Instruction::MoveReg(rax, lhs),
+        Instruction::Push(rdx),
+        Instruction::MoveReg(r11, rhs),
+        Instruction::UMul(r11),
+        Instruction::Pop(rdx),
+        Instruction::MoveReg(des, rax),
+    ]
+}
+
 // Div(Div),
 impl Compile for ir::Div {
     fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
         let ir::Div { des, lhs, rhs } = self;
+        let signed = state.is_signed(lhs);
         let des = state.get_reg(des);
         let lhs = state.get_reg(lhs);
+        state.release_reg(&self.lhs);
         let rhs = state.get_reg(rhs);
-        vec![
+        state.release_reg(&self.rhs);
+        if let Some(p) = state.precision(&self.des) {
+            return vec![
+                Instruction::Comment("Div".into()),
+                Instruction::MoveReg(des, lhs),
+                Instruction::DivF(p, des, rhs),
+            ];
+        }
+        let rax = X86Reg64::RAX.into();
+        let rdx = X86Reg64::RDX.into();
+        let r11 = X86Reg64::R11.into();
+        // NOTE: the dividend is rdx:rax, rdx is saved around it as it may be holding a value and
+        // the divisor moves to r11 in case it is the one in rdx.
+        let divide = match signed {
+            true => [Instruction::Cqo, Instruction::IDiv(r11)],
+            false => [Instruction::MoveImm(rdx, 0), Instruction::UDiv(r11)],
+        };
+        let mut result = vec![
             Instruction::Comment("Div".into()),
-            Instruction::MoveReg(des, lhs),
-            Instruction::Div(des, rhs),
-        ]
+            Instruction::MoveReg(rax, lhs),
+            Instruction::Push(rdx),
+            Instruction::MoveReg(r11, rhs),
+        ];
+        result.extend(divide);
+        result.extend([Instruction::Pop(rdx), Instruction::MoveReg(des, rax)]);
+        result
     }
 }
 
+/// `lhs` compared to `rhs` as a 0 or 1 in `des`. Floats compare unordered, a NaN is never equal
+/// to, less or greater than anything.
+fn compare(
+    state: &mut RegState,
+    name: &str,
+    (des, lhs, rhs): (&ir::Reg, &ir::Reg, &ir::Reg),
+    cond: Cond,
+) -> Vec<Instruction> {
+    let precision = state.precision(lhs);
+    let signed = state.is_signed(lhs);
+    let xdes = state.get_reg(des);
+    let xlhs = state.get_reg(lhs);
+    state.release_reg(lhs);
+    let xrhs = state.get_reg(rhs);
+    state.release_reg(rhs);
+    let al = X86RegLow8::AL.into();
+    let mut result = vec![Instruction::Comment(name.into())];
+    let Some(p) = precision else {
+        // NOTE: only `i32` and `i64` read the flags as signed, addresses and the rest do not.
+        let cond = match (signed, cond) {
+            (false, Cond::G) => Cond::A,
+            (false, Cond::Ge) => Cond::Ae,
+            (false, Cond::L) => Cond::B,
+            (false, Cond::Le) => Cond::Be,
+            (_, cond) => cond,
+        };
+        result.extend([
+            Instruction::MoveReg(xdes, xlhs),
+            Instruction::Cmp(xdes, xrhs),
+            Instruction::Set(cond, al),
+            Instruction::MoveZx(xdes),
+        ]);
+        return result;
+    };
+    // NOTE: `a < b` is `b > a` as only the unsigned above flags leave out a NaN.
+    let (cond, lhs, rhs) = match cond {
+        Cond::L => (Cond::A, xrhs, xlhs),
+        Cond::Le => (Cond::Ae, xrhs, xlhs),
+        Cond::G => (Cond::A, xlhs, xrhs),
+        Cond::Ge => (Cond::Ae, xlhs, xrhs),
+        cond => (cond, xlhs, xrhs),
+    };
+    result.push(Instruction::CmpF(p, lhs, rhs));
+    // r11 is never handed out to ir registers, it holds whether the compare was unordered.
+    let r11b = X86RegLow8::R11B.into();
+    match cond {
+        Cond::E => result.extend([
+            Instruction::Set(Cond::E, al),
+            Instruction::Set(Cond::Np, r11b),
+            Instruction::And(al, r11b),
+        ]),
+        Cond::Ne => result.extend([
+            Instruction::Set(Cond::Ne, al),
+            Instruction::Set(Cond::P, r11b),
+            Instruction::Or(al, r11b),
+        ]),
+        cond => result.push(Instruction::Set(cond, al)),
+    }
+    result.push(Instruction::MoveZx(xdes));
+    result
+}
+
 impl Compile for ir::Grt {
     fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
         let ir::Grt { des, lhs, rhs } = self;
-        let des = state.get_reg(des);
-        let lhs = state.get_reg(lhs);
-        let rhs = state.get_reg(rhs);
-        vec![
-            Instruction::Comment("Grt".into()),
-            Instruction::MoveReg(des, lhs),
-            Instruction::Cmp(des, rhs),
-            Instruction::SetG,
-            Instruction::MoveZx(des),
-        ]
+        compare(state, "Grt", (des, lhs, rhs), Cond::G)
+    }
+}
+
+impl Compile for ir::Les {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Les { des, lhs, rhs } = self;
+        compare(state, "Les", (des, lhs, rhs), Cond::L)
+    }
+}
+
+impl Compile for ir::Geq {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Geq { des, lhs, rhs } = self;
+        compare(state, "Geq", (des, lhs, rhs), Cond::Ge)
+    }
+}
+
+impl Compile for ir::Leq {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Leq { des, lhs, rhs } = self;
+        compare(state, "Leq", (des, lhs, rhs), Cond::Le)
+    }
+}
+
+impl Compile for ir::Equal {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Equal { des, lhs, rhs } = self;
+        compare(state, "Equal", (des, lhs, rhs), Cond::E)
+    }
+}
+
+impl Compile for ir::Neq {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Neq { des, lhs, rhs } = self;
+        compare(state, "Neq", (des, lhs, rhs), Cond::Ne)
+    }
+}
+
+impl Compile for ir::Convert {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Convert { des, src, .. } = self;
+        let from = state.precision(src);
+        let to = state.precision(des);
+        let xsrc = state.get_reg(src);
+        state.release_reg(src);
+        let xdes = state.get_reg(des);
+        // NOTE: ints are converted as signed, a u64 past i64::MAX comes out negative.
+        let convert = match (from, to) {
+            (None, Some(p)) => Instruction::IntToF(p, xdes, xsrc),
+            (Some(p), None) => Instruction::FToInt(p, xdes, xsrc),
+            (Some(from), Some(p)) if from != p => Instruction::FToF(p, xdes, xsrc),
+            _ => Instruction::MoveReg(xdes, xsrc),
+        };
+        vec![Instruction::Comment("Convert".into()), convert]
     }
 }
 
@@ -325,6 +963,7 @@ impl Compile for ir::Copy {
 impl Compile for ir::Conditional {
     fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
         let des = state.get_reg(&self.reg);
+        state.release_reg(&self.reg);
         vec![
             Instruction::Comment("Conditional".into()),
             Instruction::Test(des, des),
@@ -332,6 +971,54 @@ impl Compile for ir::Conditional {
         ]
     }
 }
+/// The low `bits` of `src` into the whole of `des`, with the bits above them cleared.
+fn zero_extend(
+    state: &mut RegState,
+    name: &str,
+    (des, src): (&ir::Reg, &ir::Reg),
+    bits: usize,
+) -> Vec<Instruction> {
+    let xsrc = state.get_reg(src);
+    state.release_reg(src);
+    let xdes = state.get_reg(des);
+    // NOTE: writing to a 32 bit register clears the top half, there is no movzx for it.
+    let extend = match bits {
+        32 => Instruction::MoveReg(xdes.as_32_bit().into(), xsrc.as_32_bit().into()),
+        16 => Instruction::MoveZxReg(xdes, xsrc.as_16_bit().into()),
+        _ => Instruction::MoveZxReg(xdes, xsrc.as_low_8_bit().into()),
+    };
+    vec![Instruction::Comment(name.into()), extend]
+}
+
+impl Compile for ir::Trunc {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Trunc { des, src, bits } = self;
+        zero_extend(state, "Trunc", (des, src), *bits)
+    }
+}
+
+impl Compile for ir::ZExt {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::ZExt { des, src, bits } = self;
+        zero_extend(state, "ZExt", (des, src), *bits)
+    }
+}
+
+impl Compile for ir::SExt {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::SExt { des, src, bits } = self;
+        let xsrc = state.get_reg(src);
+        state.release_reg(src);
+        let xdes = state.get_reg(des);
+        let extend = match bits {
+            32 => Instruction::MoveSxd(xdes, xsrc.as_32_bit().into()),
+            16 => Instruction::MoveSx(xdes, xsrc.as_16_bit().into()),
+            _ => Instruction::MoveSx(xdes, xsrc.as_low_8_bit().into()),
+        };
+        vec![Instruction::Comment("SExt".into()), extend]
+    }
+}
+
 // Jump(Jump),
 impl Compile for ir::Jump {
     fn compile(&self, _state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
@@ -350,15 +1037,123 @@ impl Compile for ir::DefLabel {
         ]
     }
 }
+/// Where System V passes an argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArgLoc {
+    Reg(X86Reg),
+    /// The nth of the ones on the stack, the first is on top when the call is made.
+    Stack(usize),
+}
+
+/// The ints and the floats are handed their own registers in order, the ones left over once they
+/// run out go on the stack.
+fn arg_locs(is_float: impl IntoIterator<Item = bool>) -> Vec<ArgLoc> {
+    let (mut ints, mut floats, mut stack) = (0, 0, 0);
+    is_float
+        .into_iter()
+        .map(|is_float| match is_float {
+            true if floats < 8 => {
+                floats += 1;
+                ArgLoc::Reg(X86RegXmm::from(floats - 1).into())
+            }
+            false if ints < 6 => {
+                ints += 1;
+                ArgLoc::Reg(X86RegParam::from(ints - 1).into())
+            }
+            _ => {
+                stack += 1;
+                ArgLoc::Stack(stack - 1)
+            }
+        })
+        .collect()
+}
+
 // Call(Call),
 impl Compile for ir::Call {
-    fn compile(&self, _state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
-        vec![
-            Instruction::Comment("Call".into()),
-            Instruction::Call(self.caller.0.to_string()),
-            // // FIXME: This is just a hot fix.... figure out a better way.
-            // Instruction::MoveReg(X86Reg64::RDX.into(), X86Reg64::RAX.into()),
-        ]
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Call { caller, args, ret } = self;
+        let mut result = vec![Instruction::Comment("Call".into())];
+        let callee = match caller {
+            ir::Callee::Label(_) | ir::Callee::Extern(_) => None,
+            ir::Callee::Reg(reg) => {
+                let xreg = state.get_reg(reg);
+                state.release_reg(reg);
+                Some(xreg)
+            }
+        };
+        // Values still alive after the call live in caller saved registers, they are saved on top
+        // of the arguments.
+        let live = state.live_regs();
+        result.extend(live.iter().copied().map(Instruction::Push));
+        // NOTE: r11 is never handed out, the address is moved there before the arguments are
+        // loaded over it.
+        let r11: X86Reg = X86Reg64::R11.into();
+        if let Some(callee) = callee {
+            result.push(Instruction::MoveReg(r11, callee));
+        }
+        let locs = arg_locs(args.iter().map(|reg| state.precision(reg).is_some()));
+        let on_stack = locs
+            .iter()
+            .filter(|loc| matches!(loc, ArgLoc::Stack(_)))
+            .count();
+        // NOTE: rsp is at a 16 byte boundary when the call is made. The old rsp is pushed, twice
+        // when there is an even number of arguments on the stack to keep it so.
+        let ret_reg = state.get_ret_reg();
+        let rsp = X86Reg64::RSP.into();
+        let padding = 1 - on_stack % 2;
+        result.extend([Instruction::MoveReg(ret_reg, rsp), Instruction::AlignStack]);
+        result.extend((0..=padding).map(|_| Instruction::Push(ret_reg)));
+        // The argument pushed last is just below the live values.
+        let pushed = |i: usize, ty: ir::Type| {
+            Mem::offset(ty, ret_reg, (live.len() + args.len() - 1 - i) * 8)
+        };
+        // The first of the arguments on the stack ends up on top.
+        let stack_args = locs
+            .iter()
+            .enumerate()
+            .filter(|(_, loc)| matches!(loc, ArgLoc::Stack(_)))
+            .map(|(i, _)| Instruction::PushMem(pushed(i, ir::Type::U64)))
+            .collect::<Vec<_>>();
+        result.extend(stack_args.into_iter().rev());
+        let mut floats = 0;
+        for (i, loc) in locs.iter().enumerate() {
+            let ArgLoc::Reg(xreg) = loc else {
+                continue;
+            };
+            let ty = match xreg.is_xmm() {
+                true => {
+                    floats += 1;
+                    ir::Type::F64
+                }
+                false => ir::Type::U64,
+            };
+            result.push(Instruction::MoveRegMem(*xreg, pushed(i, ty)));
+        }
+        match caller {
+            ir::Callee::Label(label) => result.push(Instruction::Call(fn_label(&label.0))),
+            ir::Callee::Reg(_) => result.push(Instruction::CallReg(r11)),
+            // NOTE: a variadic function like printf reads how many of its arguments are in xmm
+            // registers from al.
+            ir::Callee::Extern(label) => result.extend([
+                Instruction::MoveImm(ret_reg, floats),
+                Instruction::CallExtern(label.0.clone()),
+            ]),
+        }
+        result.extend([
+            Instruction::AddImm(rsp, (on_stack + padding) as u64 * 8),
+            Instruction::Pop(rsp),
+        ]);
+        // NOTE: a float comes back in xmm0, which one of the live values may be popped into.
+        if state.precision(ret).is_some() {
+            result.push(Instruction::MoveReg(ret_reg, X86RegXmm::XMM0.into()));
+        }
+        result.extend(live.iter().rev().copied().map(Instruction::Pop));
+        if !args.is_empty() {
+            result.push(Instruction::AddImm(rsp, args.len() as u64 * 8));
+        }
+        let ret = state.get_reg(ret);
+        result.push(Instruction::MoveReg(ret, ret_reg));
+        result
     }
 }
 
@@ -368,8 +1163,12 @@ impl Compile for ir::Return {
         let Some(r) = self.0 else {
             return vec![];
         };
+        let ret = match state.precision(&r) {
+            Some(_) => X86RegXmm::XMM0.into(),
+            None => state.get_ret_reg(),
+        };
         let reg = state.get_reg(&r);
-        let ret = state.get_ret_reg();
+        state.release_reg(&r);
         vec![
             Instruction::Comment("Return".into()),
             Instruction::MoveReg(ret, reg),
@@ -389,16 +1188,564 @@ impl Compile for ir::Leave {
         vec![Instruction::Comment("Leave".into()), Instruction::Epilog]
     }
 }
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+// LoadLocal(LoadLocal),
+impl Compile for ir::LoadLocal {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::LoadLocal { des, slot } = self;
+        let des = state.get_reg(des);
+        vec![Instruction::MoveRegMem(des, Mem::local(slot))]
+    }
+}
+
+// StoreLocal(StoreLocal),
+impl Compile for ir::StoreLocal {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::StoreLocal { slot, src } = self;
+        let xsrc = state.get_reg(src);
+        state.release_reg(src);
+        vec![Instruction::MoveMemReg(Mem::local(slot), xsrc)]
+    }
+}
+
+// LoadElem(LoadElem),
+impl Compile for ir::LoadElem {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::LoadElem { des, slot, index } = self;
+        let xindex = state.get_reg(index);
+        state.release_reg(index);
+        let des = state.get_reg(des);
+        vec![
+            Instruction::Comment("LoadElem".into()),
+            Instruction::MoveRegMem(des, Mem::elem(slot, xindex)),
+        ]
+    }
+}
+
+// StoreElem(StoreElem),
+impl Compile for ir::StoreElem {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::StoreElem { slot, index, src } = self;
+        let xindex = state.get_reg(index);
+        state.release_reg(index);
+        let xsrc = state.get_reg(src);
+        state.release_reg(src);
+        vec![
+            Instruction::Comment("StoreElem".into()),
+            Instruction::MoveMemReg(Mem::elem(slot, xindex), xsrc),
+        ]
+    }
+}
+
+// FillArray(FillArray),
+impl Compile for ir::FillArray {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::FillArray { slot, src } = self;
+        let Some((_, len)) = slot.array() else {
+            unreachable!("{slot} is not an array");
+        };
+        let xsrc = state.get_reg(src);
+        state.release_reg(src);
+        // rax is never handed out to ir registers so it is free to use as the counter.
+        let counter: X86Reg = X86Reg64::RAX.into();
+        let label = state.gen_label("fill");
+        vec![
+            Instruction::Comment("FillArray".into()),
+            Instruction::MoveImm(counter, 0),
+            Instruction::DefLabel(label.clone()),
+            Instruction::MoveMemReg(Mem::elem(slot, counter), xsrc),
+            Instruction::AddImm(counter, 1),
+            Instruction::CmpImm(counter, len as u64),
+            Instruction::JumpBelow(label),
+        ]
+    }
+}
+
+// BoundsCheck(BoundsCheck),
+impl Compile for ir::BoundsCheck {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::BoundsCheck { index, len } = self;
+        let xindex = state.get_reg(index);
+        // NOTE: unsigned compare so a "negative" index is out of bounds as well.
+        vec![
+            Instruction::Comment("BoundsCheck".into()),
+            Instruction::CmpImm(xindex, *len as u64),
+            Instruction::JumpAboveEqual(runtime::INDEX_OUT_OF_BOUNDS.into()),
+        ]
+    }
+}
+
+// OverflowCheck(OverflowCheck),
+impl Compile for ir::OverflowCheck {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::OverflowCheck {
+            reg,
+            bits,
+            signed,
+            msg,
+            len,
+        } = self;
+        let xreg = state.get_reg(reg);
+        let fits = state.gen_label("fits");
+        let rax = X86Reg64::RAX.into();
+        let mut result = vec![Instruction::Comment("OverflowCheck".into())];
+        // NOTE: a 64 bit result still has the flags of the add, sub or mul before this, an unsigned
+        // mul sets the carry when the high half of the product is not zero. A 32 bit result is
+        // kept extended to 64 bits, it fits when extending its low half gives it back.
+        match (bits, signed) {
+            (64, true) => result.push(Instruction::JumpIf(Cond::No, fits.clone())),
+            (64, false) => result.push(Instruction::JumpIf(Cond::Nc, fits.clone())),
+            (32, true) => result.extend([
+                Instruction::MoveSxd(rax, xreg.as_32_bit().into()),
+                Instruction::Cmp(rax, xreg),
+                Instruction::JumpIf(Cond::E, fits.clone()),
+            ]),
+            (32, false) => result.extend([
+                Instruction::MoveReg(X86Reg64::RAX.as_32_bit().into(), xreg.as_32_bit().into()),
+                Instruction::Cmp(rax, xreg),
+                Instruction::JumpIf(Cond::E, fits.clone()),
+            ]),
+            (8, false) => result.extend([
+                Instruction::MoveZxReg(rax, xreg.as_low_8_bit().into()),
+                Instruction::Cmp(rax, xreg),
+                Instruction::JumpIf(Cond::E, fits.clone()),
+            ]),
+            _ => unreachable!("there are no {bits} bit ints to overflow"),
+        }
+        result.extend(runtime::panic(msg, *len, runtime::EXIT_OVERFLOW));
+        result.push(Instruction::DefLabel(fits));
+        result
+    }
+}
+
+// ZeroCheck(ZeroCheck),
+impl Compile for ir::ZeroCheck {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::ZeroCheck { reg, msg, len } = self;
+        let xreg = state.get_reg(reg);
+        let nonzero = state.gen_label("nonzero");
+        let mut result = vec![
+            Instruction::Comment("ZeroCheck".into()),
+            Instruction::Test(xreg, xreg),
+            Instruction::JumpNotZero(nonzero.clone()),
+        ];
+        result.extend(runtime::panic(msg, *len, runtime::EXIT_DIVIDE_BY_ZERO));
+        result.push(Instruction::DefLabel(nonzero));
+        result
+    }
+}
+
+// DivOverflowCheck(DivOverflowCheck),
+impl Compile for ir::DivOverflowCheck {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::DivOverflowCheck {
+            lhs,
+            rhs,
+            bits,
+            msg,
+            len,
+        } = self;
+        let lhs = state.get_reg(lhs);
+        let rhs = state.get_reg(rhs);
+        let fits = state.gen_label("fits");
+        let rax = X86Reg64::RAX.into();
+        // NOTE: a 32 bit int is kept sign extended, its smallest value is as well.
+        let min = (-1i64 << (bits - 1)) as u64;
+        let mut result = vec![
+            Instruction::Comment("DivOverflowCheck".into()),
+            Instruction::MoveImm(rax, u64::MAX),
+            Instruction::Cmp(rhs, rax),
+            Instruction::JumpIf(Cond::Ne, fits.clone()),
+            Instruction::MoveImm(rax, min),
+            Instruction::Cmp(lhs, rax),
+            Instruction::JumpIf(Cond::Ne, fits.clone()),
+        ];
+        result.extend(runtime::panic(msg, *len, runtime::EXIT_OVERFLOW));
+        result.push(Instruction::DefLabel(fits));
+        result
+    }
+}
+
+// Panic(Panic),
+impl Compile for ir::Panic {
+    fn compile(&self, _: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Panic { code, msg, len } = self;
+        let mut result = vec![Instruction::Comment("Panic".into())];
+        result.extend(runtime::panic(msg, *len, *code));
+        result
+    }
+}
+
+// AddrOf(AddrOf),
+impl Compile for ir::AddrOf {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::AddrOf { des, slot } = self;
+        let des = state.get_reg(des);
+        vec![Instruction::Lea(des, Mem::local(slot))]
+    }
+}
+
+// Load(Load),
+impl Compile for ir::Load {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Load { des, addr } = self;
+        let xaddr = state.get_reg(addr);
+        state.release_reg(addr);
+        let ty = state.mem_type(des);
+        let des = state.get_reg(des);
+        vec![Instruction::MoveRegMem(des, Mem::addr(ty, xaddr))]
+    }
+}
+
+// Store(Store),
+impl Compile for ir::Store {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Store { addr, src } = self;
+        let xaddr = state.get_reg(addr);
+        state.release_reg(addr);
+        let ty = state.mem_type(src);
+        let xsrc = state.get_reg(src);
+        state.release_reg(src);
+        vec![Instruction::MoveMemReg(Mem::addr(ty, xaddr), xsrc)]
+    }
+}
+
+// CopyMem(CopyMem),
+impl Compile for ir::CopyMem {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::CopyMem { to, from, bytes } = self;
+        let mut result = vec![Instruction::Comment("CopyMem".into())];
+        let xto = state.get_reg(to);
+        let xfrom = state.get_reg(from);
+        // NOTE: every field is qword aligned, so a struct goes over a qword at a time. An array
+        // of narrower items can end in a dword or a few bytes.
+        let rax = X86Reg64::RAX.into();
+        let mut left = *bytes;
+        for ty in [ir::Type::U64, ir::Type::U32, ir::Type::U8] {
+            let size = ty.bytes();
+            while left >= size {
+                result.extend([
+                    Instruction::MoveRegMem(rax, Mem::addr(ty.clone(), xfrom)),
+                    Instruction::MoveMemReg(Mem::addr(ty.clone(), xto), rax),
+                    Instruction::AddImm(xfrom, size as u64),
+                    Instruction::AddImm(xto, size as u64),
+                ]);
+                left -= size;
+            }
+        }
+        state.release_reg(to);
+        state.release_reg(from);
+        result
+    }
+}
+
+// ElemPtr(ElemPtr),
+impl Compile for ir::ElemPtr {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::ElemPtr {
+            des,
+            base,
+            index,
+            ty,
+        } = self;
+        let xbase = state.get_reg(base);
+        state.release_reg(base);
+        let xindex = state.get_reg(index);
+        state.release_reg(index);
+        let des = state.get_reg(des);
+        let mem = Mem::AddrIndex {
+            ty: ty.clone(),
+            base: xbase,
+            index: xindex,
+        };
+        vec![Instruction::Lea(des, mem)]
+    }
+}
+
+// DataAddr(DataAddr),
+impl Compile for ir::DataAddr {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::DataAddr { des, label } = self;
+        let des = state.get_reg(des);
+        vec![Instruction::LoadAddr(des, fn_label(&label.0))]
+    }
+}
+
+// Extern(Extern),
+impl Compile for ir::Extern {
+    fn compile(&self, _: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        vec![Instruction::Extrn(self.0.to_string())]
+    }
+}
+
+/// Widens the low `bits` of `reg` to the whole register.
+fn extend_in_place(reg: X86Reg, bits: usize, signed: bool) -> Instruction {
+    match (bits, signed) {
+        (32, true) => Instruction::MoveSxd(reg, reg.as_32_bit().into()),
+        (32, false) => Instruction::MoveReg(reg.as_32_bit().into(), reg.as_32_bit().into()),
+        (_, true) => Instruction::MoveSx(reg, reg.as_low_8_bit().into()),
+        (_, false) => Instruction::MoveZxReg(reg, reg.as_low_8_bit().into()),
+    }
+}
+
+// Export(Export),
+impl Compile for ir::Export {
+    fn compile(&self, _: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Export {
+            symbol,
+            func,
+            params,
+        } = self;
+        let mut result = vec![
+            Instruction::Public(symbol.0.clone()),
+            Instruction::Symbol(symbol.0.clone()),
+        ];
+        // NOTE: C leaves the bits above a narrow int undefined, A expects them extended. One
+        // passed on the stack is extended where it is, just above the return address.
+        let locs = arg_locs(params.iter().map(ir::Type::is_float));
+        for (ty, loc) in params.iter().zip(locs) {
+            let Some((bits, signed)) = ty.int_width().filter(|(bits, _)| *bits < 64) else {
+                continue;
+            };
+            match loc {
+                ArgLoc::Reg(reg) => result.push(extend_in_place(reg, bits, signed)),
+                ArgLoc::Stack(n) => {
+                    let rax = X86Reg64::RAX.into();
+                    let mem = Mem::stack(ir::Type::U64, 8 + n * 8);
+                    result.extend([
+                        Instruction::MoveRegMem(rax, mem.clone()),
+                        extend_in_place(rax, bits, signed),
+                        Instruction::MoveMemReg(mem, rax),
+                    ]);
+                }
+            }
+        }
+        // NOTE: the A function returns straight to C, floats already come back in xmm0.
+        result.push(Instruction::Jump(fn_label(&func.0)));
+        result
+    }
+}
+
+// DefData(DefData),
+impl Compile for ir::DefData {
+    fn compile(&self, _: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::DefData { label, bytes } = self;
+        vec![Instruction::DefBytes(label.to_string(), bytes.clone())]
+    }
+}
+
+// LoadStatic(LoadStatic),
+impl Compile for ir::LoadStatic {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::LoadStatic { des, label, ty } = self;
+        let des = state.get_reg(des);
+        let mem = Mem::label(ty.clone(), &fn_label(&label.0));
+        vec![Instruction::MoveRegMem(des, mem)]
+    }
+}
+
+// StoreStatic(StoreStatic),
+impl Compile for ir::StoreStatic {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::StoreStatic { label, ty, src } = self;
+        let xsrc = state.get_reg(src);
+        state.release_reg(src);
+        let mem = Mem::label(ty.clone(), &fn_label(&label.0));
+        vec![Instruction::MoveMemReg(mem, xsrc)]
+    }
+}
+
+// DefStatic(DefStatic),
+impl Compile for ir::DefStatic {
+    fn compile(&self, _: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::DefStatic { label, ty, value } = self;
+        match value {
+            ir::StaticValue::Bits(bits) => {
+                let bytes = bits.to_le_bytes()[..ty.bytes()].to_vec();
+                vec![Instruction::DefBytes(fn_label(&label.0), bytes)]
+            }
+            ir::StaticValue::Str(data, len) => {
+                let qwords = vec![format!("{}__", fn_label(&data.0)), len.to_string()];
+                vec![Instruction::DefQwords(fn_label(&label.0), qwords)]
+            }
+        }
+    }
+}
+
+// Discard(Discard),
+impl Compile for ir::Discard {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        state.release_reg(&self.0);
+        vec![]
+    }
+}
+
+// Push(Push),
+impl Compile for ir::Push {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let reg = state.get_reg(&self.0);
+        state.release_reg(&self.0);
+        vec![Instruction::Push(reg)]
+    }
+}
+
+// Syscall(Syscall),
+impl Compile for ir::Syscall {
+    fn compile(&self, state: &mut RegState, _: &SymbolTable) -> Vec<Instruction> {
+        let ir::Syscall { des, argc } = self;
+        let regs = [
+            X86Reg64::RAX,
+            X86Reg64::RDI,
+            X86Reg64::RSI,
+            X86Reg64::RDX,
+            X86Reg64::R10,
+            X86Reg64::R8,
+            X86Reg64::R9,
+        ];
+        let mut result = vec![Instruction::Comment("Syscall".into())];
+        // The kernel clobbers rcx and r11, live values are saved on top of the arguments.
+        let live = state.live_regs();
+        result.extend(live.iter().copied().map(Instruction::Push));
+        for (i, reg) in regs.iter().take(*argc).enumerate() {
+            let offset = (live.len() + argc - 1 - i) * 8;
+            let mem = Mem::stack(ir::Type::U64, offset);
+            result.push(Instruction::MoveRegMem((*reg).into(), mem));
+        }
+        result.push(Instruction::Syscall);
+        result.extend(live.iter().rev().copied().map(Instruction::Pop));
+        result.push(Instruction::AddImm(X86Reg64::RSP.into(), *argc as u64 * 8));
+        let des = state.get_reg(des);
+        result.push(Instruction::MoveReg(des, state.get_ret_reg()));
+        result
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Mem {
-    Param { ty: ir::Type, offset: usize },
+    Local {
+        ty: ir::Type,
+        offset: usize,
+    },
+    Index {
+        ty: ir::Type,
+        offset: usize,
+        index: X86Reg,
+    },
+    /// Whatever a pointer in `base` points at.
+    Addr {
+        ty: ir::Type,
+        base: X86Reg,
+    },
+    AddrIndex {
+        ty: ir::Type,
+        base: X86Reg,
+        index: X86Reg,
+    },
+    /// Pushed values, `offset` bytes above the top of the stack.
+    Stack {
+        ty: ir::Type,
+        offset: usize,
+    },
+    /// `offset` bytes past the address in `base`.
+    Offset {
+        ty: ir::Type,
+        base: X86Reg,
+        offset: usize,
+    },
+    /// Something in one of the data segments.
+    Label {
+        ty: ir::Type,
+        name: String,
+    },
 }
-impl fmt::Display for Mem {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+impl Mem {
+    fn local(slot: &ir::Slot) -> Self {
+        Self::Local {
+            ty: slot.ty.clone(),
+            offset: slot.offset,
+        }
+    }
+
+    fn elem(slot: &ir::Slot, index: X86Reg) -> Self {
+        let Some((ty, _)) = slot.array() else {
+            unreachable!("{slot} is not an array");
+        };
+        Self::Index {
+            ty: ty.clone(),
+            offset: slot.offset,
+            index,
+        }
+    }
+
+    pub fn addr(ty: ir::Type, base: X86Reg) -> Self {
+        Self::Addr { ty, base }
+    }
+
+    pub fn stack(ty: ir::Type, offset: usize) -> Self {
+        Self::Stack { ty, offset }
+    }
+
+    pub fn offset(ty: ir::Type, base: X86Reg, offset: usize) -> Self {
+        Self::Offset { ty, base, offset }
+    }
+
+    pub fn label(ty: ir::Type, name: &str) -> Self {
+        Self::Label {
+            ty,
+            name: name.into(),
+        }
+    }
+
+    /// `mov` for the general purpose registers, `movzx` when loading a byte into a wider one,
+    /// `movss` or `movsd` for an xmm register.
+    fn mov(&self, reg: &X86Reg) -> &'static str {
+        match (reg.is_xmm(), self.size()) {
+            (false, "byte") if !matches!(reg, X86Reg::RegLow8(_) | X86Reg::RegHigh8(_)) => "movzx",
+            (false, _) => "mov",
+            (true, "dword") => "movss",
+            (true, _) => "movsd",
+        }
+    }
+
+    /// The operand without a size, as `lea` wants it.
+    fn address(&self) -> String {
         match self {
-            Self::Param { ty, offset } => write!(f, "{} [rbp-{offset}]", ty.size()),
+            Self::Local { offset, .. } => format!("[rbp-{offset}]"),
+            Self::Index { ty, offset, index } => {
+                format!("[rbp-{offset}+{index}*{}]", ty.bytes())
+            }
+            Self::Addr { base, .. } => format!("[{base}]"),
+            Self::AddrIndex { ty, base, index } => format!("[{base}+{index}*{}]", ty.bytes()),
+            Self::Stack { offset, .. } => format!("[rsp+{offset}]"),
+            Self::Offset {
+                base, offset: 0, ..
+            } => format!("[{base}]"),
+            Self::Offset { base, offset, .. } => format!("[{base}+{offset}]"),
+            Self::Label { name, .. } => format!("[{name}__]"),
         }
     }
+
+    fn ty(&self) -> &ir::Type {
+        match self {
+            Self::Local { ty, .. }
+            | Self::Index { ty, .. }
+            | Self::Addr { ty, .. }
+            | Self::AddrIndex { ty, .. }
+            | Self::Stack { ty, .. }
+            | Self::Offset { ty, .. }
+            | Self::Label { ty, .. } => ty,
+        }
+    }
+
+    fn size(&self) -> &'static str {
+        self.ty().size()
+    }
+}
+
+impl fmt::Display for Mem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.size(), self.address())
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,6 @@
 #![warn(clippy::upper_case_acronyms)]
-use super::{X86Reg, X86RegParam, X86RegRet};
-use crate::ir::Reg;
+use super::{Instruction, Mem, Precision, X86Reg, X86Reg64, X86RegParam, X86RegRet, X86RegXmm};
+use crate::ir::{self, Reg};
 use std::collections::HashMap;
 
 #[derive(Debug, Default)]
@@ -9,25 +9,76 @@ pub struct RegState {
     // _scratch: [bool; 9],
     // _preserved: [bool; 7],
     ret: [bool; 2],
+    /// xmm0 to xmm7, handed out to floats the same way the param registers are to the rest.
+    xmm: [bool; 8],
+    /// The registers of the function being compiled that hold an `f32`, `f64`, `bool` or signed
+    /// int.
+    mem_types: HashMap<Reg, ir::Type>,
     in_use: HashMap<Reg, X86Reg>,
+    /// The values in a register by when they were put there, the first is spilled first.
+    handed_out: Vec<Reg>,
     last_used: Option<(Reg, X86Reg)>,
+    labels: usize,
+    /// Values not in a register until they are used again, params the caller passed on the
+    /// stack and values spilled to free up a register.
+    on_stack: HashMap<Reg, Mem>,
+    /// Spills and loads of the values on the stack since they were last taken, they go before
+    /// the instruction that needed the register.
+    moves: Vec<Instruction>,
+    /// The registers handed out to the instruction being compiled, none of them is spilled for it.
+    pinned: Vec<Reg>,
+    /// The registers the instruction being compiled is done with, it may still read them so a
+    /// value is never loaded into one of them.
+    released: Vec<X86Reg>,
+    /// How much of the frame the locals take, the spilled values go below them.
+    locals_size: usize,
+    spills: usize,
 }
 
 impl RegState {
     fn push_reg(&mut self, reg: &Reg, xreg: X86Reg) {
         self.last_used = Some((*reg, xreg));
         self.in_use.insert(*reg, xreg);
+        self.handed_out.push(*reg);
     }
 
     pub fn reset(&mut self) {
         *self = Self::default()
     }
 
+    pub fn set_mem_types(&mut self, mem_types: HashMap<Reg, ir::Type>) {
+        self.mem_types = mem_types;
+    }
+
+    /// Whether `reg` holds an `f32` or an `f64`, `None` when it is not a float at all.
+    pub fn precision(&self, reg: &Reg) -> Option<Precision> {
+        match self.mem_types.get(reg)? {
+            ir::Type::F32 => Some(Precision::Single),
+            ir::Type::F64 => Some(Precision::Double),
+            _ => None,
+        }
+    }
+
+    /// Whether `reg` holds an `i32` or `i64`.
+    pub fn is_signed(&self, reg: &Reg) -> bool {
+        matches!(self.mem_types.get(reg), Some(ir::Type::I32 | ir::Type::I64))
+    }
+
+    /// The type of the value in `reg` as far as memory is concerned.
+    pub fn mem_type(&self, reg: &Reg) -> ir::Type {
+        self.mem_types.get(reg).cloned().unwrap_or(ir::Type::U64)
+    }
+
     pub fn release_reg(&mut self, reg: &Reg) {
-        self.in_use.get(reg).and_then(|r| {
+        self.handed_out.retain(|r| r != reg);
+        self.in_use.remove(reg).and_then(|r| {
+            if self.pinned.contains(reg) {
+                self.released.push(r);
+            }
             match r {
-                X86Reg::RegRet(reg) => self.ret[(*reg) as usize] = false,
-                X86Reg::RegParam(reg) => self.args[(*reg) as usize] = false,
+                X86Reg::RegRet(reg) => self.ret[reg as usize] = false,
+                X86Reg::RegParam(reg) => self.args[reg as usize] = false,
+                X86Reg::RegXmm(reg) => self.xmm[reg as usize] = false,
                 X86Reg::Reg64(..) => unreachable!(),
                 X86Reg::Reg32(..) => unreachable!(),
                 X86Reg::Reg16(..) => unreachable!(),
@@ -39,28 +90,129 @@ impl RegState {
     }
 
     pub fn get_reg(&mut self, reg: &Reg) -> X86Reg {
-        self.in_use.get(reg).copied().unwrap_or_else(|| {
-            let xreg = self.get_param_reg(reg);
-            self.push_reg(reg, xreg);
-            xreg
-        })
+        self.pinned.push(*reg);
+        if let Some(xreg) = self.in_use.get(reg) {
+            return *xreg;
+        }
+        let Some(mem) = self.on_stack.remove(reg) else {
+            return self.get_param_reg(reg);
+        };
+        let xreg = self.hand_out(reg, true);
+        self.moves.push(Instruction::MoveRegMem(xreg, mem));
+        xreg
+    }
+
+    /// `reg` is a param the caller left `offset` bytes above rbp.
+    pub fn set_stack_arg(&mut self, reg: &Reg, offset: usize) {
+        let mem = Mem::offset(self.spill_type(reg), X86Reg64::RBP.into(), offset);
+        self.on_stack.insert(*reg, mem);
+    }
+
+    /// The locals of the function being compiled take `size` bytes below rbp.
+    pub fn set_locals_size(&mut self, size: usize) {
+        self.locals_size = size;
+    }
+
+    /// How many bytes below the locals the spilled values take.
+    pub fn spills_size(&self) -> usize {
+        self.spills * 8
+    }
+
+    /// Takes the spills and loads the instruction just compiled needs done before it.
+    pub fn take_moves(&mut self) -> Vec<Instruction> {
+        self.pinned.clear();
+        self.released.clear();
+        std::mem::take(&mut self.moves)
     }
 
+    /// Hands `reg` the first free param register, or xmm register for a float. When all of them
+    /// are taken the value that has been in one the longest is spilled, unless the instruction
+    /// being compiled uses it.
     pub fn get_param_reg(&mut self, reg: &Reg) -> X86Reg {
-        let xreg = self
-            .args
-            .iter_mut()
-            .enumerate()
-            .find(|(_, r)| !**r)
-            .map(|(i, r)| {
-                *r = true;
-                X86RegParam::from(i).into()
-            })
-            .unwrap();
+        self.hand_out(reg, false)
+    }
+
+    /// A register for `reg` that the value it had on the stack can be loaded into when `load`
+    /// is set.
+    fn hand_out(&mut self, reg: &Reg, load: bool) -> X86Reg {
+        let is_float = self.precision(reg).is_some();
+        let xreg = |i: usize| -> X86Reg {
+            match is_float {
+                true => X86RegXmm::from(i).into(),
+                false => X86RegParam::from(i).into(),
+            }
+        };
+        let taken = match is_float {
+            true => &mut self.xmm[..],
+            false => &mut self.args[..],
+        };
+        let usable = (0..taken.len())
+            .filter(|&i| !taken[i])
+            .find(|&i| !load || !self.released.contains(&xreg(i)));
+        let xreg = match usable {
+            Some(i) => {
+                taken[i] = true;
+                xreg(i)
+            }
+            None => self.spill(is_float),
+        };
         self.push_reg(reg, xreg);
         xreg
     }
 
+    /// Moves a value out of its register into a new slot below the locals and hands back the
+    /// register, it is loaded again the next time it is used.
+    fn spill(&mut self, is_float: bool) -> X86Reg {
+        let (reg, xreg) = self
+            .handed_out
+            .iter()
+            .map(|reg| (*reg, self.in_use[reg]))
+            .find(|(reg, xreg)| xreg.is_xmm() == is_float && !self.pinned.contains(reg))
+            .expect("an instruction uses more registers than there are");
+        self.in_use.remove(&reg);
+        self.handed_out.retain(|r| *r != reg);
+        self.spills += 1;
+        let mem = Mem::Local {
+            ty: self.spill_type(&reg),
+            offset: self.locals_size + self.spills * 8,
+        };
+        self.moves.push(Instruction::MoveMemReg(mem.clone(), xreg));
+        self.on_stack.insert(reg, mem);
+        xreg
+    }
+
+    /// A whole register is saved, whatever the value in it is.
+    fn spill_type(&self, reg: &Reg) -> ir::Type {
+        match self.precision(reg) {
+            Some(_) => ir::Type::F64,
+            None => ir::Type::U64,
+        }
+    }
+
+    /// Registers currently holding a value.
+    pub fn live_regs(&self) -> Vec<X86Reg> {
+        let args = self
+            .args
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| **r)
+            .map(|(i, _)| X86RegParam::from(i).into());
+        let xmm = self
+            .xmm
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| **r)
+            .map(|(i, _)| X86RegXmm::from(i).into());
+        args.chain(xmm).collect()
+    }
+
+    /// Local label unique to the function being compiled.
+    pub fn gen_label(&mut self, name: &str) -> String {
+        let label = format!(".{name}{}", self.labels);
+        self.labels += 1;
+        label
+    }
+
     pub fn get_ret_reg(&mut self) -> X86Reg {
         X86RegRet::RAX.into()
         // self.ret
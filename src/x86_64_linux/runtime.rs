@@ -0,0 +1,373 @@
+//! Routines the generated code can jump into at runtime.
+use super::{Cond, Instruction, Mem, X86Reg, X86Reg64};
+use crate::ir;
+
+pub const INDEX_OUT_OF_BOUNDS: &str = "_index_out_of_bounds";
+pub const EXIT_INDEX_OUT_OF_BOUNDS: u64 = 101;
+pub const EXIT_OVERFLOW: u64 = 102;
+pub const EXIT_DIVIDE_BY_ZERO: u64 = 103;
+/// What a call to the `panic` builtin exits with.
+pub const EXIT_PANIC: u64 = 104;
+
+/// `panic(code, ptr, len)` writes the message to stderr and exits with `code`.
+pub const PANIC: &str = "_panic";
+
+/// `write(fd, ptr, len)`
+pub const WRITE: &str = "_write";
+/// `print_u64(fd, value)` in decimal.
+pub const PRINT_U64: &str = "_print_u64";
+/// `print_i64(fd, value)` in decimal, with a `-` in front of a negative value.
+pub const PRINT_I64: &str = "_print_i64";
+/// `print_bool(fd, value)` as `true` or `false`.
+pub const PRINT_BOOL: &str = "_print_bool";
+/// `print_char(fd, value)` utf-8 encoded.
+pub const PRINT_CHAR: &str = "_print_char";
+/// `arg(index)` pointer to a nul terminated command line argument.
+pub const ARG: &str = "_arg";
+/// `strlen(ptr)` bytes before the nul terminator.
+pub const STRLEN: &str = "_strlen";
+/// Command line argument count, set by `_start` or the C `main`.
+pub const ARGC: &str = "_argc";
+/// Command line argument vector, set by `_start` or the C `main`.
+pub const ARGV: &str = "_argv";
+
+const STDERR: u64 = 2;
+const SYS_WRITE: u64 = 1;
+const SYS_EXIT: u64 = 60;
+
+const TRUE: &str = "_true";
+const FALSE: &str = "_false";
+
+fn msg_label(name: &str) -> String {
+    format!("{name}_msg")
+}
+
+fn rax() -> X86Reg {
+    X86Reg64::RAX.into()
+}
+
+fn rcx() -> X86Reg {
+    X86Reg64::RCX.into()
+}
+
+fn rdx() -> X86Reg {
+    X86Reg64::RDX.into()
+}
+
+fn rsi() -> X86Reg {
+    X86Reg64::RSI.into()
+}
+
+fn rbp() -> X86Reg {
+    X86Reg64::RBP.into()
+}
+
+/// Prints `msg` to stderr and exits with `code`.
+fn abort(name: &str, msg: &str, code: u64) -> Vec<Instruction> {
+    vec![
+        Instruction::DefLabel(name.into()),
+        Instruction::MoveImm(rax(), SYS_WRITE),
+        Instruction::MoveImm(X86Reg64::RDI.into(), STDERR),
+        Instruction::LoadAddr(rsi(), msg_label(name)),
+        Instruction::MoveImm(rdx(), msg.len() as u64),
+        Instruction::Syscall,
+        Instruction::MoveImm(rax(), SYS_EXIT),
+        Instruction::MoveImm(X86Reg64::RDI.into(), code),
+        Instruction::Syscall,
+    ]
+}
+
+/// Jumps to the panic routine with the `len` bytes at `msg`, it never comes back.
+pub fn panic(msg: &ir::Label, len: usize, code: u64) -> Vec<Instruction> {
+    vec![
+        Instruction::MoveImm(X86Reg64::RDI.into(), code),
+        Instruction::LoadAddr(rsi(), msg.0.clone()),
+        Instruction::MoveImm(rdx(), len as u64),
+        Instruction::Jump(PANIC.into()),
+    ]
+}
+
+/// The exit code is kept in r8 as the syscall writes over rcx and r11.
+fn panic_routine() -> Vec<Instruction> {
+    let rdi: X86Reg = X86Reg64::RDI.into();
+    let r8: X86Reg = X86Reg64::R8.into();
+    vec![
+        Instruction::DefLabel(PANIC.into()),
+        Instruction::MoveReg(r8, rdi),
+        Instruction::MoveImm(rax(), SYS_WRITE),
+        Instruction::MoveImm(rdi, STDERR),
+        Instruction::Syscall,
+        Instruction::MoveImm(rax(), SYS_EXIT),
+        Instruction::MoveReg(rdi, r8),
+        Instruction::Syscall,
+    ]
+}
+
+fn write() -> Vec<Instruction> {
+    vec![
+        Instruction::DefLabel(WRITE.into()),
+        Instruction::MoveImm(rax(), SYS_WRITE),
+        Instruction::Syscall,
+        Instruction::Ret,
+    ]
+}
+
+fn print_bool() -> Vec<Instruction> {
+    vec![
+        Instruction::DefLabel(PRINT_BOOL.into()),
+        Instruction::Test(rsi(), rsi()),
+        Instruction::JumpZero(".false".into()),
+        Instruction::LoadAddr(rsi(), TRUE.into()),
+        Instruction::MoveImm(rdx(), 4),
+        Instruction::Jump(WRITE.into()),
+        Instruction::DefLabel(".false".into()),
+        Instruction::LoadAddr(rsi(), FALSE.into()),
+        Instruction::MoveImm(rdx(), 5),
+        Instruction::Jump(WRITE.into()),
+    ]
+}
+
+/// Stores the low byte of `src` in front of the bytes already written below rbp.
+fn push_byte(src: X86Reg) -> Vec<Instruction> {
+    vec![
+        Instruction::SubImm(rsi(), 1),
+        Instruction::MoveMemReg(
+            Mem::addr(ir::Type::U8, rsi()),
+            X86Reg::RegLow8(src.as_low_8_bit()),
+        ),
+    ]
+}
+
+/// Writes the bytes between rsi and rbp then returns from the routine.
+fn write_buffer() -> Vec<Instruction> {
+    vec![
+        Instruction::MoveReg(rdx(), rbp()),
+        Instruction::Sub(rdx(), rsi()),
+        Instruction::MoveImm(rax(), SYS_WRITE),
+        Instruction::Syscall,
+        Instruction::Epilog,
+    ]
+}
+
+/// Divides rax by ten until nothing is left, writing the digits back to front.
+fn digits() -> Vec<Instruction> {
+    let mut code = vec![
+        Instruction::MoveImm(rcx(), 10),
+        Instruction::MoveReg(rsi(), rbp()),
+        Instruction::DefLabel(".digit".into()),
+        Instruction::MoveImm(rdx(), 0),
+        Instruction::UDiv(rcx()),
+        Instruction::AddImm(rdx(), b'0' as u64),
+    ];
+    code.extend(push_byte(rdx()));
+    code.extend([
+        Instruction::Test(rax(), rax()),
+        Instruction::JumpNotZero(".digit".into()),
+    ]);
+    code
+}
+
+fn print_u64() -> Vec<Instruction> {
+    let mut code = vec![
+        Instruction::DefLabel(PRINT_U64.into()),
+        Instruction::ProLog,
+        Instruction::SubImm(X86Reg64::RSP.into(), 32),
+        Instruction::MoveReg(rax(), rsi()),
+    ];
+    code.extend(digits());
+    code.extend(write_buffer());
+    code
+}
+
+/// Writes the digits of the magnitude, r8 keeps the value to tell whether a `-` goes in front.
+fn print_i64() -> Vec<Instruction> {
+    let r8: X86Reg = X86Reg64::R8.into();
+    let mut code = vec![
+        Instruction::DefLabel(PRINT_I64.into()),
+        Instruction::ProLog,
+        Instruction::SubImm(X86Reg64::RSP.into(), 32),
+        Instruction::MoveReg(rax(), rsi()),
+        Instruction::MoveReg(r8, rsi()),
+        Instruction::Test(rax(), rax()),
+        Instruction::JumpIf(Cond::Ge, ".positive".into()),
+        Instruction::MoveImm(rax(), 0),
+        Instruction::Sub(rax(), rsi()),
+        Instruction::DefLabel(".positive".into()),
+    ];
+    code.extend(digits());
+    code.extend([
+        Instruction::Test(r8, r8),
+        Instruction::JumpIf(Cond::Ge, ".write".into()),
+        Instruction::MoveImm(rdx(), b'-' as u64),
+    ]);
+    code.extend(push_byte(rdx()));
+    code.push(Instruction::DefLabel(".write".into()));
+    code.extend(write_buffer());
+    code
+}
+
+/// A utf-8 continuation byte holding the low six bits of rax.
+fn continuation_byte() -> Vec<Instruction> {
+    let mut code = vec![
+        Instruction::MoveReg(rcx(), rax()),
+        Instruction::AndImm(rcx(), 0x3f),
+        Instruction::OrImm(rcx(), 0x80),
+    ];
+    code.extend(push_byte(rcx()));
+    code.push(Instruction::ShrImm(rax(), 6));
+    code
+}
+
+fn lead_byte(prefix: u64) -> Vec<Instruction> {
+    let mut code = vec![Instruction::OrImm(rax(), prefix)];
+    code.extend(push_byte(rax()));
+    code.push(Instruction::Jump(".write".into()));
+    code
+}
+
+fn print_char() -> Vec<Instruction> {
+    let mut code = vec![
+        Instruction::DefLabel(PRINT_CHAR.into()),
+        Instruction::ProLog,
+        Instruction::SubImm(X86Reg64::RSP.into(), 16),
+        Instruction::MoveReg(rax(), rsi()),
+        Instruction::MoveReg(rsi(), rbp()),
+        Instruction::CmpImm(rax(), 0x80),
+        Instruction::JumpBelow(".one".into()),
+        Instruction::CmpImm(rax(), 0x800),
+        Instruction::JumpBelow(".two".into()),
+        Instruction::CmpImm(rax(), 0x10000),
+        Instruction::JumpBelow(".three".into()),
+    ];
+    for (label, len, prefix) in [
+        (None, 3, 0xf0),
+        (Some(".three"), 2, 0xe0),
+        (Some(".two"), 1, 0xc0),
+        (Some(".one"), 0, 0x00),
+    ] {
+        code.extend(label.map(|l| Instruction::DefLabel(l.into())));
+        for _ in 0..len {
+            code.extend(continuation_byte());
+        }
+        code.extend(lead_byte(prefix));
+    }
+    code.push(Instruction::DefLabel(".write".into()));
+    code.extend(write_buffer());
+    code
+}
+
+fn arg() -> Vec<Instruction> {
+    let rdi: X86Reg = X86Reg64::RDI.into();
+    vec![
+        Instruction::DefLabel(ARG.into()),
+        Instruction::MoveRegMem(rax(), Mem::label(ir::Type::U64, ARGC)),
+        Instruction::Cmp(rdi, rax()),
+        Instruction::JumpAboveEqual(INDEX_OUT_OF_BOUNDS.into()),
+        Instruction::MoveRegMem(rax(), Mem::label(ir::Type::U64, ARGV)),
+        Instruction::MoveRegMem(
+            rax(),
+            Mem::AddrIndex {
+                ty: ir::Type::U64,
+                base: rax(),
+                index: rdi,
+            },
+        ),
+        Instruction::Ret,
+    ]
+}
+
+fn strlen() -> Vec<Instruction> {
+    let cl = X86Reg::RegLow8(rcx().as_low_8_bit());
+    vec![
+        Instruction::DefLabel(STRLEN.into()),
+        Instruction::MoveReg(rax(), X86Reg64::RDI.into()),
+        Instruction::DefLabel(".next".into()),
+        Instruction::MoveRegMem(cl, Mem::addr(ir::Type::U8, rax())),
+        Instruction::Test(cl, cl),
+        Instruction::JumpZero(".done".into()),
+        Instruction::AddImm(rax(), 1),
+        Instruction::Jump(".next".into()),
+        Instruction::DefLabel(".done".into()),
+        Instruction::Sub(rax(), X86Reg64::RDI.into()),
+        Instruction::Ret,
+    ]
+}
+
+/// Entry point, hands `argc`, `argv` and `envp` to main and exits with what it returns.
+pub fn start() -> Vec<Instruction> {
+    let rdi: X86Reg = X86Reg64::RDI.into();
+    let rsp: X86Reg = X86Reg64::RSP.into();
+    vec![
+        Instruction::DefLabel("_start".into()),
+        Instruction::MoveRegMem(rdi, Mem::addr(ir::Type::U64, rsp)),
+        Instruction::MoveReg(rsi(), rsp),
+        Instruction::AddImm(rsi(), 8),
+        Instruction::Lea(
+            rdx(),
+            Mem::AddrIndex {
+                ty: ir::Type::U64,
+                base: rsi(),
+                index: rdi,
+            },
+        ),
+        Instruction::AddImm(rdx(), 8),
+        Instruction::MoveMemReg(Mem::label(ir::Type::U64, ARGC), rdi),
+        Instruction::MoveMemReg(Mem::label(ir::Type::U64, ARGV), rsi()),
+        Instruction::Call("main".into()),
+        Instruction::MoveReg(rdi, rax()),
+        Instruction::MoveImm(rax(), SYS_EXIT),
+        Instruction::Syscall,
+    ]
+}
+
+/// Entry point of an object, the C runtime calls `main(argc, argv)` and exits with what it
+/// returns. The A main only touches registers C lets it clobber.
+pub fn c_main() -> Vec<Instruction> {
+    let rdi: X86Reg = X86Reg64::RDI.into();
+    vec![
+        Instruction::Public("main".into()),
+        Instruction::Symbol("main".into()),
+        Instruction::MoveMemReg(Mem::label(ir::Type::U64, ARGC), rdi),
+        Instruction::MoveMemReg(Mem::label(ir::Type::U64, ARGV), rsi()),
+        Instruction::Call("main".into()),
+        Instruction::Ret,
+    ]
+}
+
+pub fn runtime_assembly() -> String {
+    [
+        abort(
+            INDEX_OUT_OF_BOUNDS,
+            "index out of bounds\n",
+            EXIT_INDEX_OUT_OF_BOUNDS,
+        ),
+        panic_routine(),
+        write(),
+        print_bool(),
+        print_u64(),
+        print_i64(),
+        print_char(),
+        arg(),
+        strlen(),
+    ]
+    .concat()
+    .iter()
+    .map(ToString::to_string)
+    .collect()
+}
+
+pub fn runtime_data() -> String {
+    [
+        Instruction::DefBytes(
+            msg_label(INDEX_OUT_OF_BOUNDS),
+            "index out of bounds\n".into(),
+        ),
+        Instruction::DefBytes(TRUE.into(), "true".into()),
+        Instruction::DefBytes(FALSE.into(), "false".into()),
+        Instruction::DefBytes(ARGC.into(), vec![0; 8]),
+        Instruction::DefBytes(ARGV.into(), vec![0; 8]),
+    ]
+    .iter()
+    .map(ToString::to_string)
+    .collect()
+}
@@ -1,5 +1,17 @@
 macro_rules! snapshot {
+    ($name:tt, $path:tt, std) => {
+        snapshot!($name, $path, false, false, true);
+    };
     ($name:tt, $path:tt) => {
+        snapshot!($name, $path, false);
+    };
+    ($name:tt, $path:tt, $overflow_checks:expr) => {
+        snapshot!($name, $path, $overflow_checks, false);
+    };
+    ($name:tt, $path:tt, $overflow_checks:expr, $object:expr) => {
+        snapshot!($name, $path, $overflow_checks, $object, false);
+    };
+    ($name:tt, $path:tt, $overflow_checks:expr, $object:expr, $std:expr) => {
         #[test]
         fn $name() {
             use super::*;
@@ -9,8 +21,16 @@ macro_rules! snapshot {
             let contents = include_str!($path);
             let tokens = lex(contents).unwrap();
             let ast = parse(tokens).unwrap();
-            let ir_code = ir::code_gen(ast).unwrap();
-            let asm_data = compile_ir_code(ir_code).unwrap();
+            let ast = match $std {
+                true => {
+                    let file = concat!(env!("CARGO_MANIFEST_DIR"), "/src/x86_64_linux/", $path);
+                    $crate::module::link(file, ast).unwrap()
+                }
+                false => ast,
+            };
+            let ast = $crate::semantic_analysis::check(ast).unwrap();
+            let ir_code = ir::code_gen(ast, $overflow_checks).unwrap();
+            let asm_data = compile_ir_code(ir_code, $object).unwrap();
             let result = instruction_to_string(asm_data).unwrap();
             let mut settings = insta::Settings::clone_current();
             settings.set_snapshot_path("testdata/output/");
@@ -24,3 +44,30 @@ macro_rules! snapshot {
 snapshot!(binary, "../../snapshots/binary.a");
 snapshot!(ifelse, "../../snapshots/ifelse.a");
 snapshot!(max, "../../snapshots/max.a");
+snapshot!(array, "../../snapshots/array.a");
+snapshot!(pointer, "../../snapshots/pointer.a");
+snapshot!(string, "../../snapshots/string.a");
+snapshot!(print, "../../snapshots/print.a");
+snapshot!(args, "../../snapshots/args.a");
+snapshot!(syscall, "../../snapshots/syscall.a");
+snapshot!(recursion, "../../snapshots/recursion.a");
+snapshot!(scope, "../../snapshots/scope.a");
+snapshot!(globals, "../../snapshots/globals.a");
+snapshot!(generics, "../../snapshots/generics.a");
+snapshot!(traits, "../../snapshots/traits.a");
+snapshot!(methods, "../../snapshots/methods.a");
+snapshot!(structs, "../../snapshots/structs.a");
+snapshot!(fn_values, "../../snapshots/fn_values.a");
+snapshot!(closures, "../../snapshots/closures.a");
+snapshot!(floats, "../../snapshots/floats.a");
+snapshot!(casts, "../../snapshots/casts.a");
+snapshot!(bools, "../../snapshots/bools.a");
+snapshot!(overflow, "../../snapshots/overflow.a", true);
+snapshot!(mul_overflow, "../../snapshots/mul_overflow.a", true);
+snapshot!(panics, "../../snapshots/panics.a");
+snapshot!(loops, "../../snapshots/loops.a", std);
+snapshot!(extern_fn, "../../snapshots/extern.a", false, true);
+snapshot!(export, "../../snapshots/export.a", false, true);
+snapshot!(abi, "../../snapshots/abi.a", false, true);
+snapshot!(widths, "../../snapshots/widths.a");
+snapshot!(std, "../../std/std.a");
@@ -33,6 +33,7 @@ pub enum X86Reg {
     Reg16(X86Reg16),
     RegHigh8(X86RegHigh8),
     RegLow8(X86RegLow8),
+    RegXmm(X86RegXmm),
 }
 
 impl fmt::Display for X86Reg {
@@ -45,11 +46,16 @@ impl fmt::Display for X86Reg {
             Self::Reg16(reg) => write!(f, "{reg}"),
             Self::RegHigh8(reg) => write!(f, "{reg}"),
             Self::RegLow8(reg) => write!(f, "{reg}"),
+            Self::RegXmm(reg) => write!(f, "{reg}"),
         }
     }
 }
 
 impl X86Reg {
+    pub fn is_xmm(&self) -> bool {
+        matches!(self, Self::RegXmm(_))
+    }
+
     pub fn as_32_bit(&self) -> X86Reg32 {
         match self {
             Self::RegRet(reg) => reg.as_64_bit().as_32_bit(),
@@ -59,6 +65,7 @@ impl X86Reg {
             Self::Reg16(reg) => reg.as_32_bit(),
             Self::RegHigh8(reg) => reg.as_32_bit(),
             Self::RegLow8(reg) => reg.as_32_bit(),
+            Self::RegXmm(_) => unreachable!("xmm registers have no smaller parts"),
         }
     }
 
@@ -71,6 +78,7 @@ impl X86Reg {
             Self::Reg16(reg) => *reg,
             Self::RegHigh8(reg) => reg.as_16_bit(),
             Self::RegLow8(reg) => reg.as_16_bit(),
+            Self::RegXmm(_) => unreachable!("xmm registers have no smaller parts"),
         }
     }
     pub fn as_high_8_bit(&self) -> X86RegHigh8 {
@@ -82,6 +90,7 @@ impl X86Reg {
             Self::Reg16(reg) => reg.as_high_8_bit().unwrap(),
             Self::RegHigh8(reg) => *reg,
             Self::RegLow8(reg) => reg.as_high_8_bit().unwrap(),
+            Self::RegXmm(_) => unreachable!("xmm registers have no smaller parts"),
         }
     }
     pub fn as_low_8_bit(&self) -> X86RegLow8 {
@@ -93,6 +102,7 @@ impl X86Reg {
             Self::Reg16(reg) => reg.as_low_8_bit(),
             Self::RegHigh8(reg) => reg.as_low_8_bit(),
             Self::RegLow8(reg) => *reg,
+            Self::RegXmm(_) => unreachable!("xmm registers have no smaller parts"),
         }
     }
 }
@@ -139,6 +149,12 @@ impl From<X86RegHigh8> for X86Reg {
     }
 }
 
+impl From<X86RegXmm> for X86Reg {
+    fn from(value: X86RegXmm) -> Self {
+        Self::RegXmm(value)
+    }
+}
+
 impl From<X86RegLow8> for X86Reg {
     fn from(value: X86RegLow8) -> Self {
         Self::RegLow8(value)
@@ -652,3 +668,53 @@ impl X86RegLow8 {
         }
     }
 }
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum X86RegXmm {
+    XMM0,
+    XMM1,
+    XMM2,
+    XMM3,
+    XMM4,
+    XMM5,
+    XMM6,
+    XMM7,
+    XMM8,
+    XMM9,
+    XMM10,
+    XMM11,
+    XMM12,
+    XMM13,
+    XMM14,
+    XMM15,
+}
+
+impl From<usize> for X86RegXmm {
+    fn from(value: usize) -> Self {
+        match value {
+            0 => Self::XMM0,
+            1 => Self::XMM1,
+            2 => Self::XMM2,
+            3 => Self::XMM3,
+            4 => Self::XMM4,
+            5 => Self::XMM5,
+            6 => Self::XMM6,
+            7 => Self::XMM7,
+            8 => Self::XMM8,
+            9 => Self::XMM9,
+            10 => Self::XMM10,
+            11 => Self::XMM11,
+            12 => Self::XMM12,
+            13 => Self::XMM13,
+            14 => Self::XMM14,
+            15 => Self::XMM15,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl fmt::Display for X86RegXmm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
@@ -0,0 +1,219 @@
+//! Compiles programs with the `a` binary, runs them and checks what they print.
+//!
+//! They need `fasm` to assemble the output and are skipped when it is not on the `PATH`, the
+//! ones linking an object into a C program need `cc` as well. With `A_NEEDS_FASM=true`, as on
+//! CI, a missing `fasm` fails them instead.
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn has_fasm() -> bool {
+    match Command::new("fasm").output() {
+        Ok(_) => true,
+        Err(_) if std::env::var("A_NEEDS_FASM").is_ok_and(|needs| needs == "true") => {
+            panic!("fasm is not installed but A_NEEDS_FASM is set")
+        }
+        Err(_) => {
+            eprintln!("fasm is not installed, skipping");
+            false
+        }
+    }
+}
+
+fn has_cc() -> bool {
+    match Command::new("cc").arg("--version").output() {
+        Ok(_) => true,
+        Err(_) => {
+            eprintln!("cc is not installed, skipping");
+            false
+        }
+    }
+}
+
+/// A directory of its own for the test, the compiler writes its output next to the source.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("a-run-{}-{name}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Copies the snapshot into `dir` and compiles it there, `args` are passed to the compiler.
+fn compile(dir: &Path, snapshot: &str, args: &[&str]) -> PathBuf {
+    let src = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("snapshots")
+        .join(snapshot);
+    let file = dir.join(snapshot);
+    std::fs::copy(&src, &file).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_a"))
+        .arg(&file)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    file.with_extension("")
+}
+
+/// Copies the C program `harness` into `dir` and links `object` into it with `cc`.
+fn link_c(dir: &Path, harness: &str, object: &Path) -> PathBuf {
+    let src = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("snapshots")
+        .join(harness);
+    let file = dir.join(harness);
+    std::fs::copy(&src, &file).unwrap();
+    let exe = file.with_extension("");
+    let output = Command::new("cc")
+        .arg(&file)
+        .arg(object)
+        .arg("-o")
+        .arg(&exe)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    exe
+}
+
+fn run(exe: &Path) -> Output {
+    Command::new(exe).output().unwrap()
+}
+
+#[test]
+fn loops() {
+    if !has_fasm() {
+        return;
+    }
+    let dir = scratch("loops");
+    let output = run(&compile(&dir, "loops.a", &[]));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "[8, 4, 5]\nhi\nhello, world\n12\n"
+    );
+    assert_eq!(output.status.code(), Some(115));
+}
+
+#[test]
+fn array() {
+    if !has_fasm() {
+        return;
+    }
+    let dir = scratch("array");
+    let output = run(&compile(&dir, "array.a", &[]));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "bc\n");
+    assert_eq!(output.status.code(), Some(85));
+}
+
+#[test]
+fn widths() {
+    if !has_fasm() {
+        return;
+    }
+    let dir = scratch("widths");
+    let output = run(&compile(&dir, "widths.a", &[]));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "-5\n-7\n12\n4000000000\n"
+    );
+    assert_eq!(output.status.code(), Some(255));
+}
+
+#[test]
+fn methods() {
+    if !has_fasm() {
+        return;
+    }
+    let dir = scratch("methods");
+    let output = run(&compile(&dir, "methods.a", &[]));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "bool: true\n6\n0\n"
+    );
+    assert_eq!(output.status.code(), Some(9));
+}
+
+#[test]
+fn structs() {
+    if !has_fasm() {
+        return;
+    }
+    let dir = scratch("structs");
+    let output = run(&compile(&dir, "structs.a", &[]));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "3\nfirst\n40\n30\n"
+    );
+    assert_eq!(output.status.code(), Some(17));
+}
+
+#[test]
+fn globals() {
+    if !has_fasm() {
+        return;
+    }
+    let dir = scratch("globals");
+    let output = run(&compile(&dir, "globals.a", &[]));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "true15\n11\ngrid\n-2\n3\nhi\ngrid\n"
+    );
+    assert_eq!(output.status.code(), Some(115));
+}
+
+#[test]
+fn panics() {
+    if !has_fasm() {
+        return;
+    }
+    let dir = scratch("panics");
+    let output = run(&compile(&dir, "panics.a", &[]));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "-4611686018427387904\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("attempt to divide with overflow"));
+    assert_eq!(output.status.code(), Some(102));
+}
+
+#[test]
+fn mul_overflow() {
+    if !has_fasm() {
+        return;
+    }
+    let dir = scratch("mul_overflow");
+    let output = run(&compile(&dir, "mul_overflow.a", &["--overflow-checks"]));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "18446744073709551615\n18446744073709551614\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("attempt to multiply with overflow"));
+    assert_eq!(output.status.code(), Some(102));
+}
+
+#[test]
+fn abi() {
+    if !has_fasm() || !has_cc() {
+        return;
+    }
+    let dir = scratch("abi");
+    let object = compile(&dir, "abi.a", &["--object"]).with_extension("o");
+    let output = run(&link_c(&dir, "abi.c", &object));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn export() {
+    if !has_fasm() || !has_cc() {
+        return;
+    }
+    let dir = scratch("export");
+    let object = compile(&dir, "export.a", &["--object"]).with_extension("o");
+    let output = run(&link_c(&dir, "export.c", &object));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+    assert_eq!(output.status.code(), Some(0));
+}